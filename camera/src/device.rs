@@ -6,6 +6,7 @@ use libc::{c_int, c_uint, c_void, O_CLOEXEC, O_NONBLOCK, O_RDWR};
 use std::{
     ffi::{CStr, CString},
     io, mem,
+    ops::RangeInclusive,
     os::unix::ffi::OsStrExt,
     path::Path,
     ptr,
@@ -160,7 +161,18 @@ impl Device {
         }
     }
 
+    /// Returns the range of valid values of a camera control by name.
+    pub fn control_range(&self, name: &str) -> io::Result<RangeInclusive<i64>> {
+        let queryctl = self.query_control(name)?;
+        Ok(i64::from(queryctl.minimum)..=i64::from(queryctl.maximum))
+    }
+
     fn find_control(&self, name: &str) -> io::Result<(u32, v4l2_ctrl_type)> {
+        let queryctl = self.query_control(name)?;
+        Ok((queryctl.id, queryctl.type_))
+    }
+
+    fn query_control(&self, name: &str) -> io::Result<v4l2_queryctrl> {
         let c_name = CString::new(name).unwrap();
         let mut queryctl: v4l2_queryctrl = unsafe { mem::zeroed() };
         // Due to proprietary NVidia IDs we have to review the ID by its name.
@@ -181,7 +193,7 @@ impl Device {
                 break;
             }
         }
-        Ok((queryctl.id, queryctl.type_))
+        Ok(queryctl)
     }
 }
 
@@ -0,0 +1,48 @@
+//! Channel count conversion.
+
+/// Mixes interleaved `samples` with `channels` channels down to a single
+/// channel by averaging each frame.
+///
+/// A trailing incomplete frame is ignored.
+///
+/// # Panics
+///
+/// If `channels` is zero.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn downmix_to_mono(samples: &[i16], channels: u32) -> Vec<i16> {
+    assert!(channels > 0);
+    samples
+        .chunks_exact(channels as usize)
+        .map(|frame| {
+            let sum = frame.iter().copied().map(i64::from).sum::<i64>();
+            (sum / i64::from(channels)) as i16
+        })
+        .collect()
+}
+
+/// Duplicates each of the mono `samples` into both channels of an interleaved
+/// stereo stream.
+#[must_use]
+pub fn upmix_to_stereo(samples: &[i16]) -> Vec<i16> {
+    samples.iter().flat_map(|&sample| [sample, sample]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downmix_to_mono() {
+        let input = [100, 200, -100, 100, i16::MAX, i16::MAX, i16::MIN, i16::MIN, 7];
+        assert_eq!(downmix_to_mono(&input, 2), [150, 0, i16::MAX, i16::MIN]);
+        assert_eq!(downmix_to_mono(&input, 1), input);
+    }
+
+    #[test]
+    fn test_upmix_to_stereo() {
+        let input = [1, -2, i16::MAX];
+        assert_eq!(upmix_to_stereo(&input), [1, 1, -2, -2, i16::MAX, i16::MAX]);
+        assert_eq!(downmix_to_mono(&upmix_to_stereo(&input), 2), input);
+    }
+}
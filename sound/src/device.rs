@@ -1,19 +1,33 @@
-use super::{alsa_to_io_error, Access, AlsaResult, Format, HwParams, ToAlsaResult};
+use super::{
+    alsa_to_io_error,
+    wav::{self, Header},
+    Access, AlsaResult, Format, HwParams, ToAlsaResult,
+};
 use alsa_sys::{
     _snd_pcm_state_SND_PCM_STATE_DISCONNECTED, _snd_pcm_state_SND_PCM_STATE_DRAINING,
     _snd_pcm_state_SND_PCM_STATE_OPEN, _snd_pcm_state_SND_PCM_STATE_PAUSED,
     _snd_pcm_state_SND_PCM_STATE_PREPARED, _snd_pcm_state_SND_PCM_STATE_RUNNING,
     _snd_pcm_state_SND_PCM_STATE_SETUP, _snd_pcm_state_SND_PCM_STATE_SUSPENDED,
     _snd_pcm_state_SND_PCM_STATE_XRUN, _snd_pcm_stream_SND_PCM_STREAM_PLAYBACK,
+    snd_device_name_free_hint, snd_device_name_get_hint, snd_device_name_hint,
     snd_pcm_bytes_to_frames, snd_pcm_close, snd_pcm_drain, snd_pcm_drop, snd_pcm_frames_to_bytes,
     snd_pcm_hw_params, snd_pcm_open, snd_pcm_pause, snd_pcm_prepare, snd_pcm_recover,
     snd_pcm_reset, snd_pcm_resume, snd_pcm_start, snd_pcm_state, snd_pcm_state_t, snd_pcm_t,
     snd_pcm_writei, EPIPE, ESTRPIPE,
 };
-use std::{ffi::CString, io, io::prelude::*, ptr, thread::sleep, time::Duration};
+use libc::c_void;
+use std::{
+    ffi::{CStr, CString},
+    io,
+    io::prelude::*,
+    ptr,
+    thread::sleep,
+    time::Duration,
+};
 
-const WAV_FORMAT_PCM: u16 = 0x01;
-const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+// Silence written before the sound to prime the DAC and avoid a pop at the
+// start of the playback.
+const PRIME_SILENCE_MS: u32 = 5;
 
 /// PCM handle.
 pub struct Device {
@@ -22,6 +36,17 @@ pub struct Device {
 
 unsafe impl Send for Device {}
 
+/// PCM playback device description returned by [`Device::list`].
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    /// PCM name to be passed to [`Device::open`].
+    pub name: String,
+    /// Sound card identifier, if the PCM is bound to a card.
+    pub card: Option<String>,
+    /// Human-readable description.
+    pub description: Option<String>,
+}
+
 /// PCM state.
 #[derive(Clone, Copy, Debug)]
 pub enum State {
@@ -46,6 +71,34 @@ pub enum State {
 }
 
 impl Device {
+    /// Enumerates PCM devices capable of playback.
+    pub fn list() -> AlsaResult<Vec<DeviceInfo>> {
+        let mut hints: *mut *mut c_void = ptr::null_mut();
+        unsafe {
+            snd_device_name_hint(-1, b"pcm\0".as_ptr().cast(), &mut hints).to_alsa_result()?;
+        }
+        let mut devices = Vec::new();
+        // The hint list is terminated by a null pointer.
+        let mut hint = hints;
+        unsafe {
+            while !(*hint).is_null() {
+                let name = take_hint(*hint, "NAME");
+                let description = take_hint(*hint, "DESC");
+                // A missing IOID means the PCM supports both directions.
+                let ioid = take_hint(*hint, "IOID");
+                if let Some(name) = name {
+                    if ioid.as_deref().map_or(true, |ioid| ioid == "Output") {
+                        let card = card_id(&name).map(ToOwned::to_owned);
+                        devices.push(DeviceInfo { name, card, description });
+                    }
+                }
+                hint = hint.add(1);
+            }
+            snd_device_name_free_hint(hints).to_alsa_result()?;
+        }
+        Ok(devices)
+    }
+
     /// Opens a PCM.
     ///
     /// # Panics
@@ -108,37 +161,32 @@ impl Device {
         unsafe { snd_pcm_resume(self.as_raw()).to_alsa_result() }
     }
 
+    /// Recovers the PCM from an underrun (`-EPIPE`) or a suspend (`-ESTRPIPE`)
+    /// `err` returned by a previous call. When `silent` is set, ALSA doesn't
+    /// print the error message.
+    pub fn recover(&mut self, err: i32, silent: bool) -> AlsaResult<()> {
+        unsafe { snd_pcm_recover(self.as_raw(), err, silent.into()).to_alsa_result() }
+    }
+
+    /// Returns the channels count accepted by the PCM which is nearest to
+    /// `channels`.
+    pub fn negotiated_channels(&mut self, channels: u32) -> AlsaResult<u32> {
+        let mut hw_params = HwParams::new()?;
+        hw_params.any(self)?;
+        hw_params.set_access(self, Access::RwInterleaved)?;
+        hw_params.set_channels_near(self, channels)
+    }
+
     /// Writes a WAV file from a generic `reader` to the PCM buffer. Returns
     /// the duration of the sound.
-    #[allow(clippy::similar_names)] // complains about `reader` and `header`
     pub fn play_wav<T: Read + Seek>(
         &mut self,
         reader: &mut T,
         hw_params: &mut HwParams,
         volume: f64,
     ) -> io::Result<Duration> {
-        let wav = riff::Chunk::read(reader, 0)?;
-        if wav.read_type(reader)?.as_str() != "WAVE" {
-            return Err(io::Error::new(io::ErrorKind::Other, "RIFF file type is not WAVE"));
-        }
-
-        let header = wav
-            .iter(reader)
-            .find(|chunk| chunk.id().as_str() == "fmt ")
-            .map(|chunk| chunk.read_contents(reader))
-            .transpose()?
-            .ok_or_else(|| {
-                io::Error::new(io::ErrorKind::Other, "RIFF data is missing the \"fmt \" chunk")
-            })?;
-
-        let audio_format = u16::from_le_bytes([header[0], header[1]]);
-        let channel_count = u16::from_le_bytes([header[2], header[3]]);
-        let sampling_rate = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
-        let bits_per_sample = u16::from_le_bytes([header[14], header[15]]);
-
-        if audio_format != WAV_FORMAT_PCM && audio_format != WAV_FORMAT_EXTENSIBLE {
-            return Err(io::Error::new(io::ErrorKind::Other, "WAV is not in PCM format"));
-        }
+        let (riff, Header { channels: channel_count, rate: sampling_rate, bits_per_sample }) =
+            wav::read_header(reader)?;
         hw_params.any(self).map_err(alsa_to_io_error)?;
         hw_params.set_access(self, Access::RwInterleaved).map_err(alsa_to_io_error)?;
         hw_params.set_channels(self, channel_count.into()).map_err(alsa_to_io_error)?;
@@ -161,13 +209,14 @@ impl Device {
             sleep(Duration::from_millis(RETRY_TIMEOUT_US));
         }
 
-        let (offset, len) = wav
-            .iter(reader)
-            .find(|chunk| chunk.id().as_str() == "data")
-            .map(|chunk| (chunk.offset(), chunk.len()))
-            .ok_or_else(|| {
-                io::Error::new(io::ErrorKind::Other, "RIFF data is missing the \"data\" chunk")
-            })?;
+        let data = wav::data_chunk(&riff, reader)?;
+        let (offset, len) = (data.offset(), data.len());
+
+        let prime_frames = (sampling_rate * PRIME_SILENCE_MS / 1000) as usize;
+        let silence = format
+            .silence(prime_frames, channel_count.into())
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        self.write_all(&silence)?;
 
         reader.seek(io::SeekFrom::Start(offset + 8))?;
         let reader = reader.take(len.into());
@@ -193,6 +242,25 @@ impl Device {
     }
 }
 
+/// Returns the value of the `id` field of the hint, freeing the string
+/// allocated by ALSA.
+unsafe fn take_hint(hint: *const c_void, id: &str) -> Option<String> {
+    let id = CString::new(id).unwrap();
+    let value = unsafe { snd_device_name_get_hint(hint, id.as_ptr()) };
+    if value.is_null() {
+        return None;
+    }
+    let string = unsafe { CStr::from_ptr(value) }.to_string_lossy().into_owned();
+    unsafe { libc::free(value.cast::<c_void>()) };
+    Some(string)
+}
+
+/// Extracts the card identifier from a PCM name like `hw:CARD=foo,DEV=0`.
+fn card_id(name: &str) -> Option<&str> {
+    let (_, args) = name.split_once(':')?;
+    args.split(',').find_map(|arg| arg.strip_prefix("CARD="))
+}
+
 impl Drop for Device {
     fn drop(&mut self) {
         unsafe {
@@ -205,19 +273,17 @@ impl Write for Device {
     #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         loop {
-            unsafe {
+            let written = unsafe {
                 let frames = snd_pcm_bytes_to_frames(self.as_raw(), buf.len() as _);
-                let written = snd_pcm_writei(self.as_raw(), buf.as_ptr().cast(), frames as _);
-                if written == -i64::from(EPIPE) || written == -i64::from(ESTRPIPE) {
-                    log::error!("audio buffer underrun occurred");
-                    snd_pcm_recover(self.as_raw(), written as _, 0)
-                        .to_alsa_result()
-                        .map_err(alsa_to_io_error)?;
-                    continue;
-                }
-                written.to_alsa_result().map_err(alsa_to_io_error)?;
-                break Ok(snd_pcm_frames_to_bytes(self.as_raw(), written) as _);
+                snd_pcm_writei(self.as_raw(), buf.as_ptr().cast(), frames as _)
+            };
+            if written == -i64::from(EPIPE) || written == -i64::from(ESTRPIPE) {
+                log::error!("audio buffer underrun occurred");
+                self.recover(written as _, true).map_err(alsa_to_io_error)?;
+                continue;
             }
+            written.to_alsa_result().map_err(alsa_to_io_error)?;
+            break Ok(unsafe { snd_pcm_frames_to_bytes(self.as_raw(), written) } as _);
         }
     }
 
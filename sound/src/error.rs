@@ -1,3 +1,4 @@
+use crate::Format;
 use alsa_sys::snd_strerror;
 use libc::{c_int, c_long};
 use std::{ffi::CStr, io};
@@ -12,6 +13,17 @@ pub type AlsaResult<T> = Result<T, AlsaError>;
 #[error("{}", .0)]
 pub struct AlsaError(pub(crate) &'static str);
 
+/// Error returned when parsing an unrecognized PCM format name.
+#[derive(Error, Debug)]
+#[error("unknown PCM format name: {}", .0)]
+pub struct ParseFormatError(pub(crate) String);
+
+/// Error returned when a PCM format has no silence encoding, e.g. a compressed
+/// format.
+#[derive(Error, Debug)]
+#[error("PCM format {:?} has no silence encoding", .0)]
+pub struct SilenceFormatError(pub(crate) Format);
+
 impl AsRef<str> for AlsaError {
     fn as_ref(&self) -> &str {
         self.0
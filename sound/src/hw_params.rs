@@ -1,4 +1,4 @@
-use super::{AlsaResult, Device, ToAlsaResult};
+use super::{AlsaError, AlsaResult, Device, ParseFormatError, SilenceFormatError, ToAlsaResult};
 use alsa_sys::{
     _snd_pcm_access_SND_PCM_ACCESS_MMAP_COMPLEX, _snd_pcm_access_SND_PCM_ACCESS_MMAP_INTERLEAVED,
     _snd_pcm_access_SND_PCM_ACCESS_MMAP_NONINTERLEAVED,
@@ -28,11 +28,12 @@ use alsa_sys::{
     _snd_pcm_format_SND_PCM_FORMAT_U24_LE, _snd_pcm_format_SND_PCM_FORMAT_U32,
     _snd_pcm_format_SND_PCM_FORMAT_U32_BE, _snd_pcm_format_SND_PCM_FORMAT_U32_LE,
     _snd_pcm_format_SND_PCM_FORMAT_U8, _snd_pcm_format_SND_PCM_FORMAT_UNKNOWN, snd_pcm_access_t,
-    snd_pcm_format_t, snd_pcm_hw_params_any, snd_pcm_hw_params_free, snd_pcm_hw_params_malloc,
-    snd_pcm_hw_params_set_access, snd_pcm_hw_params_set_channels, snd_pcm_hw_params_set_format,
-    snd_pcm_hw_params_set_rate, snd_pcm_hw_params_set_rate_resample, snd_pcm_hw_params_t,
+    snd_pcm_format_t, snd_pcm_hw_params_any, snd_pcm_hw_params_free, snd_pcm_hw_params_get_format,
+    snd_pcm_hw_params_malloc, snd_pcm_hw_params_set_access, snd_pcm_hw_params_set_channels,
+    snd_pcm_hw_params_set_channels_near, snd_pcm_hw_params_set_format, snd_pcm_hw_params_set_rate,
+    snd_pcm_hw_params_set_rate_resample, snd_pcm_hw_params_t,
 };
-use std::ptr;
+use std::{ptr, str::FromStr};
 
 /// PCM hardware configuration space container.
 pub struct HwParams {
@@ -157,6 +158,87 @@ pub enum Format {
     IeC958Subframe,
 }
 
+impl Format {
+    /// Returns the number of bytes occupied by a single sample, or `None` if
+    /// the format is compressed or doesn't have a fixed sample width.
+    #[must_use]
+    pub fn byte_width(self) -> Option<usize> {
+        match self {
+            Self::S8 | Self::U8 => Some(1),
+            Self::S16Le | Self::S16Be | Self::U16Le | Self::U16Be | Self::S16 | Self::U16 => {
+                Some(2)
+            }
+            Self::S243Le
+            | Self::S243Be
+            | Self::U243Le
+            | Self::U243Be
+            | Self::S203Le
+            | Self::S203Be
+            | Self::U203Le
+            | Self::U203Be
+            | Self::S183Le
+            | Self::S183Be
+            | Self::U183Le
+            | Self::U183Be => Some(3),
+            Self::S24Le
+            | Self::S24Be
+            | Self::U24Le
+            | Self::U24Be
+            | Self::S32Le
+            | Self::S32Be
+            | Self::U32Le
+            | Self::U32Be
+            | Self::FloatLe
+            | Self::FloatBe
+            | Self::IeC958SubframeLe
+            | Self::IeC958SubframeBe
+            | Self::S24
+            | Self::U24
+            | Self::S32
+            | Self::U32
+            | Self::Float
+            | Self::IeC958Subframe => Some(4),
+            Self::FloaT64Le | Self::FloaT64Be | Self::FloaT64 => Some(8),
+            Self::Unknown
+            | Self::MuLaw
+            | Self::ALaw
+            | Self::ImaAdpcm
+            | Self::Mpeg
+            | Self::Gsm
+            | Self::Special => None,
+        }
+    }
+
+    /// Returns `frames` frames of `channels` interleaved silent samples
+    /// encoded in this format. Unsigned formats are silent at the middle of
+    /// their range, and the companded formats use the same silence bytes as
+    /// ALSA.
+    pub fn silence(self, frames: usize, channels: u32) -> Result<Vec<u8>, SilenceFormatError> {
+        let sample = match self {
+            Self::U8 => vec![0x80],
+            Self::U16Le => 0x8000_u16.to_le_bytes().to_vec(),
+            Self::U16Be => 0x8000_u16.to_be_bytes().to_vec(),
+            Self::U16 => 0x8000_u16.to_ne_bytes().to_vec(),
+            Self::U24Le => 0x0080_0000_u32.to_le_bytes().to_vec(),
+            Self::U24Be => 0x0080_0000_u32.to_be_bytes().to_vec(),
+            Self::U24 => 0x0080_0000_u32.to_ne_bytes().to_vec(),
+            Self::U32Le => 0x8000_0000_u32.to_le_bytes().to_vec(),
+            Self::U32Be => 0x8000_0000_u32.to_be_bytes().to_vec(),
+            Self::U32 => 0x8000_0000_u32.to_ne_bytes().to_vec(),
+            Self::U243Le => 0x0080_0000_u32.to_le_bytes()[..3].to_vec(),
+            Self::U243Be => 0x0080_0000_u32.to_be_bytes()[1..].to_vec(),
+            Self::U203Le => 0x0008_0000_u32.to_le_bytes()[..3].to_vec(),
+            Self::U203Be => 0x0008_0000_u32.to_be_bytes()[1..].to_vec(),
+            Self::U183Le => 0x0002_0000_u32.to_le_bytes()[..3].to_vec(),
+            Self::U183Be => 0x0002_0000_u32.to_be_bytes()[1..].to_vec(),
+            Self::MuLaw => vec![0x7F],
+            Self::ALaw => vec![0x55],
+            format => vec![0; format.byte_width().ok_or(SilenceFormatError(format))?],
+        };
+        Ok(sample.repeat(frames * channels as usize))
+    }
+}
+
 impl HwParams {
     /// Allocates an invalid `HwParams` using standard `malloc`.
     pub fn new() -> AlsaResult<Self> {
@@ -207,6 +289,16 @@ impl HwParams {
         Ok(())
     }
 
+    /// Restricts a configuration space to have the channels count nearest to
+    /// `channels`. Returns the chosen channels count.
+    pub fn set_channels_near(&mut self, device: &mut Device, mut channels: u32) -> AlsaResult<u32> {
+        unsafe {
+            snd_pcm_hw_params_set_channels_near(device.as_raw(), self.as_raw(), &mut channels)
+                .to_alsa_result()?;
+        }
+        Ok(channels)
+    }
+
     /// Restricts a configuration space to contain only one rate.
     pub fn set_rate(&mut self, device: &mut Device, rate: u32) -> AlsaResult<()> {
         unsafe {
@@ -215,6 +307,14 @@ impl HwParams {
         Ok(())
     }
 
+    /// Returns the format from a configuration space. The configuration space
+    /// must contain only one format, e.g. after [`Device::hw_params`].
+    pub fn format(&mut self) -> AlsaResult<Format> {
+        let mut format = _snd_pcm_format_SND_PCM_FORMAT_UNKNOWN;
+        unsafe { snd_pcm_hw_params_get_format(self.as_raw(), &mut format).to_alsa_result()? };
+        format.try_into()
+    }
+
     pub(crate) fn as_raw(&mut self) -> *mut snd_pcm_hw_params_t {
         self.hw_params
     }
@@ -294,3 +394,170 @@ impl Into<snd_pcm_format_t> for Format {
         }
     }
 }
+
+impl TryFrom<snd_pcm_format_t> for Format {
+    type Error = AlsaError;
+
+    fn try_from(format: snd_pcm_format_t) -> AlsaResult<Self> {
+        // The CPU endian constants are aliases of the explicit endian ones, so
+        // they are matched last and never take precedence.
+        Ok(match format {
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_UNKNOWN => Self::Unknown,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S8 => Self::S8,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U8 => Self::U8,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S16_LE => Self::S16Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S16_BE => Self::S16Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U16_LE => Self::U16Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U16_BE => Self::U16Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S24_LE => Self::S24Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S24_BE => Self::S24Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U24_LE => Self::U24Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U24_BE => Self::U24Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S32_LE => Self::S32Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S32_BE => Self::S32Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U32_LE => Self::U32Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U32_BE => Self::U32Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT_LE => Self::FloatLe,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT_BE => Self::FloatBe,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT64_LE => Self::FloaT64Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT64_BE => Self::FloaT64Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_IEC958_SUBFRAME_LE => {
+                Self::IeC958SubframeLe
+            }
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_IEC958_SUBFRAME_BE => {
+                Self::IeC958SubframeBe
+            }
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_MU_LAW => Self::MuLaw,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_A_LAW => Self::ALaw,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_IMA_ADPCM => Self::ImaAdpcm,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_MPEG => Self::Mpeg,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_GSM => Self::Gsm,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_SPECIAL => Self::Special,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S24_3LE => Self::S243Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S24_3BE => Self::S243Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U24_3LE => Self::U243Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U24_3BE => Self::U243Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S20_3LE => Self::S203Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S20_3BE => Self::S203Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U20_3LE => Self::U203Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U20_3BE => Self::U203Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S18_3LE => Self::S183Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S18_3BE => Self::S183Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U18_3LE => Self::U183Le,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U18_3BE => Self::U183Be,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S16 => Self::S16,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U16 => Self::U16,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S24 => Self::S24,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U24 => Self::U24,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_S32 => Self::S32,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_U32 => Self::U32,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT => Self::Float,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_FLOAT64 => Self::FloaT64,
+            format if format == _snd_pcm_format_SND_PCM_FORMAT_IEC958_SUBFRAME => {
+                Self::IeC958Subframe
+            }
+            _ => return Err(AlsaError("invalid snd_pcm_format_t value")),
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    /// Parses ALSA short format names, e.g. `"S16_LE"` or `"FLOAT64_BE"`.
+    /// Matching is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = match s.to_ascii_uppercase().as_str() {
+            "UNKNOWN" => Self::Unknown,
+            "S8" => Self::S8,
+            "U8" => Self::U8,
+            "S16_LE" => Self::S16Le,
+            "S16_BE" => Self::S16Be,
+            "U16_LE" => Self::U16Le,
+            "U16_BE" => Self::U16Be,
+            "S24_LE" => Self::S24Le,
+            "S24_BE" => Self::S24Be,
+            "U24_LE" => Self::U24Le,
+            "U24_BE" => Self::U24Be,
+            "S32_LE" => Self::S32Le,
+            "S32_BE" => Self::S32Be,
+            "U32_LE" => Self::U32Le,
+            "U32_BE" => Self::U32Be,
+            "FLOAT_LE" => Self::FloatLe,
+            "FLOAT_BE" => Self::FloatBe,
+            "FLOAT64_LE" => Self::FloaT64Le,
+            "FLOAT64_BE" => Self::FloaT64Be,
+            "IEC958_SUBFRAME_LE" => Self::IeC958SubframeLe,
+            "IEC958_SUBFRAME_BE" => Self::IeC958SubframeBe,
+            "MU_LAW" => Self::MuLaw,
+            "A_LAW" => Self::ALaw,
+            "IMA_ADPCM" => Self::ImaAdpcm,
+            "MPEG" => Self::Mpeg,
+            "GSM" => Self::Gsm,
+            "SPECIAL" => Self::Special,
+            "S24_3LE" => Self::S243Le,
+            "S24_3BE" => Self::S243Be,
+            "U24_3LE" => Self::U243Le,
+            "U24_3BE" => Self::U243Be,
+            "S20_3LE" => Self::S203Le,
+            "S20_3BE" => Self::S203Be,
+            "U20_3LE" => Self::U203Le,
+            "U20_3BE" => Self::U203Be,
+            "S18_3LE" => Self::S183Le,
+            "S18_3BE" => Self::S183Be,
+            "U18_3LE" => Self::U183Le,
+            "U18_3BE" => Self::U183Be,
+            "S16" => Self::S16,
+            "U16" => Self::U16,
+            "S24" => Self::S24,
+            "U24" => Self::U24,
+            "S32" => Self::S32,
+            "U32" => Self::U32,
+            "FLOAT" => Self::Float,
+            "FLOAT64" => Self::FloaT64,
+            "IEC958_SUBFRAME" => Self::IeC958Subframe,
+            _ => return Err(ParseFormatError(s.to_string())),
+        };
+        Ok(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silence() {
+        assert_eq!(Format::S16Le.silence(2, 2).unwrap(), [0; 8]);
+        assert_eq!(Format::FloatBe.silence(1, 1).unwrap(), [0; 4]);
+        assert_eq!(Format::U8.silence(3, 1).unwrap(), [0x80; 3]);
+        assert_eq!(Format::U16Le.silence(1, 2).unwrap(), [0x00, 0x80, 0x00, 0x80]);
+        assert_eq!(Format::U16Be.silence(1, 1).unwrap(), [0x80, 0x00]);
+        assert_eq!(Format::U24Le.silence(1, 1).unwrap(), [0x00, 0x00, 0x80, 0x00]);
+        assert_eq!(Format::U243Be.silence(1, 1).unwrap(), [0x80, 0x00, 0x00]);
+        assert_eq!(Format::U203Le.silence(1, 1).unwrap(), [0x00, 0x00, 0x08]);
+        assert_eq!(Format::MuLaw.silence(2, 1).unwrap(), [0x7F; 2]);
+        assert_eq!(Format::ALaw.silence(2, 1).unwrap(), [0x55; 2]);
+        assert!(Format::Mpeg.silence(1, 1).is_err());
+        assert!(Format::Unknown.silence(1, 1).is_err());
+    }
+
+    #[test]
+    fn test_byte_width() {
+        assert_eq!(Format::S16Le.byte_width(), Some(2));
+        assert_eq!(Format::S243Le.byte_width(), Some(3));
+        assert_eq!(Format::U183Be.byte_width(), Some(3));
+        assert_eq!(Format::S24Le.byte_width(), Some(4));
+        assert_eq!(Format::FloaT64Be.byte_width(), Some(8));
+        assert_eq!(Format::MuLaw.byte_width(), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert!(matches!("S16_LE".parse::<Format>(), Ok(Format::S16Le)));
+        assert!(matches!("s24_3le".parse::<Format>(), Ok(Format::S243Le)));
+        assert!(matches!("U20_3BE".parse::<Format>(), Ok(Format::U203Be)));
+        assert!(matches!("S24_LE".parse::<Format>(), Ok(Format::S24Le)));
+        assert!("S24_3".parse::<Format>().is_err());
+    }
+}
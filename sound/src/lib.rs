@@ -4,16 +4,22 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
+mod channels;
 mod device;
 mod error;
 mod hw_params;
 mod queue;
+mod resample;
+mod wav;
 
 pub use self::{
-    device::{Device, State},
-    error::{AlsaError, AlsaResult},
+    channels::{downmix_to_mono, upmix_to_stereo},
+    device::{Device, DeviceInfo, State},
+    error::{AlsaError, AlsaResult, ParseFormatError, SilenceFormatError},
     hw_params::{Access, Format, HwParams},
     queue::{Queue, SoundBuilder, SoundFuture},
+    resample::resample,
+    wav::Sound,
 };
 
 use self::error::{alsa_to_io_error, ToAlsaResult};
@@ -25,6 +25,7 @@ pub struct Queue {
     queue_event: c_int,
     cancel_event: c_int,
     counter: AtomicU64,
+    playing: Arc<AtomicU64>,
     thread: Option<thread::JoinHandle<()>>,
 }
 
@@ -35,6 +36,7 @@ pub struct SoundBuilder<'a> {
     name: String,
     reader: Option<Box<dyn Reader>>,
     cancel_all: bool,
+    preempt: bool,
     priority: u8,
     max_delay: Duration,
     volume: f64,
@@ -79,6 +81,8 @@ impl Queue {
         let buffer2 = Arc::clone(&buffer);
         let card_name = card_name.to_string();
         let counter = AtomicU64::new(u64::from(u8::MAX) << 56 ^ u64::MAX);
+        let playing = Arc::new(AtomicU64::new(0));
+        let playing2 = Arc::clone(&playing);
         let thread = thread::Builder::new()
             .name("sound-queue".into())
             .spawn(move || {
@@ -91,6 +95,7 @@ impl Queue {
                         &buffer2,
                         queue_event,
                         cancel_event,
+                        &playing2,
                         &mut master_volume,
                     ) {
                         Ok(()) => break,
@@ -99,7 +104,7 @@ impl Queue {
                 }
             })
             .expect("failed to spawn thread");
-        Ok(Self { buffer, queue_event, cancel_event, counter, thread: Some(thread) })
+        Ok(Self { buffer, queue_event, cancel_event, counter, playing, thread: Some(thread) })
     }
 
     /// Returns a builder object for inserting a new queue element.
@@ -114,32 +119,63 @@ impl Queue {
             name,
             reader: reader.map(|reader| Box::new(reader) as _),
             cancel_all: false,
+            preempt: false,
             priority: 0,
             max_delay: Duration::MAX,
             volume: 1.0,
         }
     }
 
-    fn push(&self, sound: Sound, cancel_all: bool) -> io::Result<()> {
+    /// Returns `true` if a sound with the given `priority` would preempt the
+    /// currently playing sound. See [`SoundBuilder::preempt`].
+    #[must_use]
+    pub fn preempts(&self, priority: u8) -> bool {
+        self.preempts_id(u64::from(priority) << 56)
+    }
+
+    /// Stops the currently playing sound and removes all pending sounds from
+    /// the queue.
+    pub fn stop_all(&self) -> io::Result<()> {
+        let mut guard = self.buffer.lock().unwrap();
+        if let Some(buffer) = guard.as_mut() {
+            Self::clear(buffer);
+        }
+        self.cancel_playing()
+    }
+
+    fn preempts_id(&self, id: u64) -> bool {
+        let playing = self.playing.load(Ordering::SeqCst);
+        playing != 0 && id >> 56 > playing >> 56
+    }
+
+    fn clear(buffer: &mut Vec<Sound>) {
+        for sound in mem::take(buffer) {
+            let state = &mut *sound.state.lock().unwrap();
+            if let State::Waiting(waker) = mem::replace(state, State::Pending) {
+                waker.wake();
+            }
+            *state = State::Done(false);
+        }
+    }
+
+    fn cancel_playing(&self) -> io::Result<()> {
+        unsafe {
+            let arg: u64 = 1;
+            write(self.cancel_event, ptr::addr_of!(arg).cast::<c_void>(), mem::size_of_val(&arg))?;
+        }
+        Ok(())
+    }
+
+    fn push(&self, sound: Sound, cancel_all: bool, preempt: bool) -> io::Result<()> {
         {
             let mut guard = self.buffer.lock().unwrap();
             let buffer = guard.as_mut().unwrap();
             if cancel_all {
-                for sound in mem::take(buffer) {
-                    let state = &mut *sound.state.lock().unwrap();
-                    if let State::Waiting(waker) = mem::replace(state, State::Pending) {
-                        waker.wake();
-                    }
-                    *state = State::Done(false);
-                }
-                unsafe {
-                    let arg: u64 = 1;
-                    write(
-                        self.cancel_event,
-                        ptr::addr_of!(arg).cast::<c_void>(),
-                        mem::size_of_val(&arg),
-                    )?;
-                }
+                Self::clear(buffer);
+                self.cancel_playing()?;
+            } else if preempt && self.preempts_id(sound.id) {
+                log::info!("Sound {} preempts the currently playing sound", sound.name);
+                self.cancel_playing()?;
             }
             let i = buffer.binary_search_by_key(&sound.id, |sound| sound.id).unwrap_err();
             buffer.insert(i, sound);
@@ -160,6 +196,14 @@ impl SoundBuilder<'_> {
         self
     }
 
+    /// Sets the `preempt` flag. Immediately stops the currently playing sound
+    /// if it has a lower priority than this sound. Pending sounds are kept.
+    #[must_use]
+    pub fn preempt(mut self) -> Self {
+        self.preempt = true;
+        self
+    }
+
     /// Sets the sound priority. Sounds with higher priorities take precedence
     /// over sounds with lower priorities.
     #[must_use]
@@ -190,7 +234,7 @@ impl SoundBuilder<'_> {
     /// The returned future can be safely dropped if there is no need to wait
     /// the end of the sound.
     pub fn push(self) -> io::Result<SoundFuture> {
-        let Self { queue, name, reader, cancel_all, priority, max_delay, volume } = self;
+        let Self { queue, name, reader, cancel_all, preempt, priority, max_delay, volume } = self;
         let Some(queue) = queue else {
             return Ok(SoundFuture { state: None });
         };
@@ -206,7 +250,7 @@ impl SoundBuilder<'_> {
             .saturating_add(max_delay);
         let state = Arc::new(Mutex::new(State::Pending));
         let sound = Sound { id, name, reader, deadline, state: Arc::clone(&state), volume };
-        queue.push(sound, cancel_all)?;
+        queue.push(sound, cancel_all, preempt)?;
         Ok(SoundFuture { state: Some(state) })
     }
 }
@@ -262,6 +306,7 @@ fn queue_loop(
     queue: &SharedBuffer,
     queue_event: c_int,
     cancel_event: c_int,
+    playing: &AtomicU64,
     master_volume: &mut (impl FnMut() -> f64 + Send + 'static),
 ) -> io::Result<()> {
     let mut device = Device::open(card_name).map_err(alsa_to_io_error)?;
@@ -284,11 +329,14 @@ fn queue_loop(
                 log::info!("Playing sound {} with volume {}", sound.name, volume);
                 // Reset any previously set cancel event.
                 cancellable_sleep(Duration::ZERO, cancel_event)?;
+                playing.store(sound.id, Ordering::SeqCst);
                 let start = Instant::now();
                 let mut duration = device.play_wav(&mut sound, &mut hw_params, volume)?;
                 // In case the sound is longer than the buffer.
                 duration = duration.saturating_sub(start.elapsed());
-                let cancelled = cancellable_sleep(duration, cancel_event)?;
+                let cancelled = cancellable_sleep(duration, cancel_event);
+                playing.store(0, Ordering::SeqCst);
+                let cancelled = cancelled?;
                 if cancelled {
                     log::info!("Sound {} cancelled", sound.name);
                     device.drop().map_err(alsa_to_io_error)?;
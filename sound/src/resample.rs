@@ -0,0 +1,78 @@
+//! Sample-rate conversion.
+
+/// Resamples interleaved `input` from the `from` sample rate to the `to` sample
+/// rate using linear interpolation.
+///
+/// `input` contains frames of `channels` interleaved samples. A trailing
+/// incomplete frame is ignored.
+///
+/// # Panics
+///
+/// If `from`, `to`, or `channels` is zero.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn resample(input: &[i16], from: u32, to: u32, channels: u32) -> Vec<i16> {
+    assert!(from > 0 && to > 0 && channels > 0);
+    let channels = channels as usize;
+    let input_frames = input.len() / channels;
+    if from == to {
+        return input[..input_frames * channels].to_vec();
+    }
+    let output_frames = (input_frames as u64 * u64::from(to) / u64::from(from)) as usize;
+    let step = f64::from(from) / f64::from(to);
+    let mut output = Vec::with_capacity(output_frames * channels);
+    for i in 0..output_frames {
+        let position = i as f64 * step;
+        let index = position as usize;
+        let fraction = position - index as f64;
+        let next = (index + 1).min(input_frames - 1);
+        for channel in 0..channels {
+            let a = f64::from(input[index * channels + channel]);
+            let b = f64::from(input[next * channels + channel]);
+            output.push((a + (b - a) * fraction).round() as i16);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn sine(frames: usize, rate: u32, channels: u32) -> Vec<i16> {
+        (0..frames)
+            .flat_map(|i| {
+                let value = (f64::from(i16::MAX)
+                    * (2.0 * PI * 440.0 * i as f64 / f64::from(rate)).sin())
+                    as i16;
+                (0..channels).map(move |_| value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_downsample() {
+        let input = sine(4800, 48000, 2);
+        let output = resample(&input, 48000, 24000, 2);
+        assert_eq!(output.len(), 2400 * 2);
+        // Every second frame of the input is kept exactly.
+        for (i, frame) in output.chunks(2).enumerate() {
+            assert_eq!(frame, &input[i * 4..i * 4 + 2]);
+        }
+    }
+
+    #[test]
+    fn test_upsample() {
+        let input = sine(2400, 24000, 2);
+        let output = resample(&input, 24000, 48000, 2);
+        assert_eq!(output.len(), 4800 * 2);
+        let expected = sine(4800, 48000, 2);
+        // The last frame has no following input frame to interpolate with.
+        let len = output.len() - 2;
+        for (actual, expected) in output[..len].iter().zip(&expected) {
+            assert!((i32::from(*actual) - i32::from(*expected)).abs() < 200);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! WAV file decoding.
+
+use super::{alsa_to_io_error, downmix_to_mono, upmix_to_stereo, Access, Device, Format, HwParams};
+use std::{fs::File, io, io::prelude::*, path::Path, time::Duration};
+
+const WAV_FORMAT_PCM: u16 = 0x01;
+const WAV_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+// Parsed "fmt " chunk of a PCM WAV file.
+pub(crate) struct Header {
+    pub(crate) channels: u16,
+    pub(crate) rate: u32,
+    pub(crate) bits_per_sample: u16,
+}
+
+/// Decoded 16-bit PCM sound.
+#[derive(Clone, Debug)]
+pub struct Sound {
+    samples: Vec<i16>,
+    rate: u32,
+    channels: u16,
+}
+
+impl Sound {
+    /// Reads and decodes a 16-bit or 24-bit PCM WAV file.
+    pub fn from_wav(path: &Path) -> io::Result<Self> {
+        Self::from_reader(&mut io::BufReader::new(File::open(path)?))
+    }
+
+    /// Decodes a 16-bit or 24-bit PCM WAV file from a generic `reader`. 24-bit
+    /// samples are truncated to 16 bits. Compressed and floating-point WAV
+    /// files are rejected.
+    pub fn from_reader<T: Read + Seek>(reader: &mut T) -> io::Result<Self> {
+        let (wav, Header { channels, rate, bits_per_sample }) = read_header(reader)?;
+        let format = match bits_per_sample {
+            16 => Format::S16Le,
+            24 => Format::S243Le,
+            bits_per_sample => {
+                return Err(invalid_data(format!(
+                    "WAV is not 16-bit or 24-bit (bits_per_sample {bits_per_sample})"
+                )));
+            }
+        };
+        let width = format.byte_width().expect("PCM formats have a fixed sample width");
+        let data = data_chunk(&wav, reader)?.read_contents(reader)?;
+        // Keeping the two most significant bytes of each little-endian sample.
+        let samples = data
+            .chunks_exact(width)
+            .map(|sample| i16::from_le_bytes([sample[width - 2], sample[width - 1]]))
+            .collect();
+        Ok(Self { samples, rate, channels })
+    }
+
+    /// Returns the interleaved samples.
+    #[must_use]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Returns the sample rate in Hz.
+    #[must_use]
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Returns the number of channels.
+    #[must_use]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the PCM sample format of the samples.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn format(&self) -> Format {
+        Format::S16Le
+    }
+
+    /// Returns the duration of the sound.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(
+            self.samples.len() as f64 / (f64::from(self.rate) * f64::from(self.channels)),
+        )
+    }
+
+    /// Converts the sound to `channels` channels, e.g. to the count returned by
+    /// [`Device::negotiated_channels`]. Only mixing down to mono and mixing
+    /// mono up to stereo are supported.
+    pub fn with_channels(self, channels: u16) -> io::Result<Self> {
+        let samples = match (self.channels, channels) {
+            (from, to) if from == to => return Ok(self),
+            (from, 1) => downmix_to_mono(&self.samples, from.into()),
+            (1, 2) => upmix_to_stereo(&self.samples),
+            (from, to) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("can't convert {from} channels to {to} channels"),
+                ));
+            }
+        };
+        Ok(Self { samples, channels, ..self })
+    }
+
+    /// Installs the hardware configuration matching this sound to `device`.
+    pub fn configure(&self, device: &mut Device, hw_params: &mut HwParams) -> io::Result<()> {
+        hw_params.any(device).map_err(alsa_to_io_error)?;
+        hw_params.set_access(device, Access::RwInterleaved).map_err(alsa_to_io_error)?;
+        hw_params.set_channels(device, self.channels.into()).map_err(alsa_to_io_error)?;
+        hw_params.set_rate(device, self.rate).map_err(alsa_to_io_error)?;
+        hw_params.set_format(device, self.format()).map_err(alsa_to_io_error)?;
+        device.hw_params(hw_params).map_err(alsa_to_io_error)
+    }
+}
+
+/// Reads the RIFF chunk of a PCM WAV file and parses its "fmt " chunk.
+/// Compressed and floating-point WAV files are rejected.
+pub(crate) fn read_header<T: Read + Seek>(reader: &mut T) -> io::Result<(riff::Chunk, Header)> {
+    let wav = riff::Chunk::read(reader, 0)?;
+    if wav.read_type(reader)?.as_str() != "WAVE" {
+        return Err(invalid_data("RIFF file type is not WAVE"));
+    }
+    let header = wav
+        .iter(reader)
+        .find(|chunk| chunk.id().as_str() == "fmt ")
+        .map(|chunk| chunk.read_contents(reader))
+        .transpose()?
+        .ok_or_else(|| invalid_data("RIFF data is missing the \"fmt \" chunk"))?;
+    if header.len() < 16 {
+        return Err(invalid_data("WAV \"fmt \" chunk is too short"));
+    }
+    let mut audio_format = u16::from_le_bytes([header[0], header[1]]);
+    let channels = u16::from_le_bytes([header[2], header[3]]);
+    let rate = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let bits_per_sample = u16::from_le_bytes([header[14], header[15]]);
+    if audio_format == WAV_FORMAT_EXTENSIBLE && header.len() >= 26 {
+        // The actual format is the first two bytes of the sub-format GUID.
+        audio_format = u16::from_le_bytes([header[24], header[25]]);
+    }
+    if audio_format != WAV_FORMAT_PCM {
+        return Err(invalid_data(format!(
+            "WAV is not in PCM format (format tag {audio_format:#06x})"
+        )));
+    }
+    if channels == 0 || rate == 0 {
+        return Err(invalid_data("WAV has zero channels or sample rate"));
+    }
+    Ok((wav, Header { channels, rate, bits_per_sample }))
+}
+
+/// Finds the "data" chunk of the WAV file read by [`read_header`].
+pub(crate) fn data_chunk<T: Read + Seek>(
+    wav: &riff::Chunk,
+    reader: &mut T,
+) -> io::Result<riff::Chunk> {
+    wav.iter(reader)
+        .find(|chunk| chunk.id().as_str() == "data")
+        .ok_or_else(|| invalid_data("RIFF data is missing the \"data\" chunk"))
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn pcm16(samples: &[i16]) -> Vec<u8> {
+        samples.iter().flat_map(|sample| sample.to_le_bytes()).collect()
+    }
+
+    fn wav(audio_format: u16, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&audio_format.to_le_bytes());
+        fmt.extend_from_slice(&2_u16.to_le_bytes());
+        fmt.extend_from_slice(&48000_u32.to_le_bytes());
+        fmt.extend_from_slice(&(48000_u32 * 4).to_le_bytes());
+        fmt.extend_from_slice(&4_u16.to_le_bytes());
+        fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(
+            &u32::try_from(4 + 8 + fmt.len() + 8 + data.len()).unwrap().to_le_bytes(),
+        );
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.extend_from_slice(&u32::try_from(fmt.len()).unwrap().to_le_bytes());
+        wav.extend_from_slice(&fmt);
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        wav.extend_from_slice(data);
+        wav
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let samples = [0, 1, -1, i16::MAX, i16::MIN, 42];
+        let sound = Sound::from_reader(&mut Cursor::new(wav(WAV_FORMAT_PCM, 16, &pcm16(&samples))))
+            .unwrap();
+        assert_eq!(sound.samples(), samples);
+        assert_eq!(sound.rate(), 48000);
+        assert_eq!(sound.channels(), 2);
+    }
+
+    #[test]
+    fn test_from_reader_24_bit() {
+        // Packed little-endian 24-bit samples: 0, 1, -1, max, min, 0x123456.
+        let data = [
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x00, 0x00,
+            0x80, 0x56, 0x34, 0x12,
+        ];
+        let sound = Sound::from_reader(&mut Cursor::new(wav(WAV_FORMAT_PCM, 24, &data))).unwrap();
+        assert_eq!(sound.samples(), [0, 0, -1, i16::MAX, i16::MIN, 0x1234]);
+        assert_eq!(sound.channels(), 2);
+        assert_eq!(sound.duration(), Duration::from_secs_f64(3.0 / 48000.0));
+
+        // A trailing incomplete sample is ignored.
+        let sound =
+            Sound::from_reader(&mut Cursor::new(wav(WAV_FORMAT_PCM, 24, &data[..8]))).unwrap();
+        assert_eq!(sound.samples(), [0, 0]);
+    }
+
+    #[test]
+    fn test_reject_unsupported() {
+        // IEEE float.
+        let err = Sound::from_reader(&mut Cursor::new(wav(0x03, 32, &[]))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = Sound::from_reader(&mut Cursor::new(wav(WAV_FORMAT_PCM, 32, &[]))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -41,6 +41,8 @@ use crate::{
 use eyre::{ensure, Result, WrapErr};
 use futures::{
     channel::{mpsc, oneshot},
+    executor::block_on,
+    future::{self, Either},
     prelude::*,
 };
 use ndarray::prelude::*;
@@ -51,7 +53,7 @@ use std::{
     convert::TryInto,
     fmt,
     io::prelude::*,
-    ops::Deref,
+    ops::{Deref, RangeInclusive},
     pin::Pin,
     sync::Arc,
     task::Poll,
@@ -68,24 +70,39 @@ const TRIGGER_MODE: i64 = 1; // mode 0: free run | mode 1: external trigger
 /// Infra-red camera sensor.
 ///
 /// See [the module-level documentation](self) for details.
-#[derive(Debug)]
 pub struct Sensor {
     state_tx: Option<mpsc::Sender<super::State>>,
     device_path: &'static str,
     rotation: bool,
+    fake_port: Option<port::Outer<Sensor>>,
+}
+
+impl fmt::Debug for Sensor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("camera::ir::Sensor")
+            .field("device_path", &self.device_path)
+            .field("rotation", &self.rotation)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Sensor {
     /// Initializes IR Eye Camera Sensor mounted in the back of the Orb.
+    ///
+    /// When `fake_port` is set, frames are taken from it instead of the
+    /// device, and commands are forwarded to it.
     #[must_use]
-    pub fn eye(state_tx: Option<mpsc::Sender<super::State>>) -> Self {
-        Self { state_tx, device_path: EYE_DEVICE_PATH, rotation: false }
+    pub fn eye(
+        state_tx: Option<mpsc::Sender<super::State>>,
+        fake_port: Option<port::Outer<Sensor>>,
+    ) -> Self {
+        Self { state_tx, device_path: EYE_DEVICE_PATH, rotation: false, fake_port }
     }
 
     /// Initializes IR Face Camera Sensor mounted in the front of the Orb.
     #[must_use]
     pub fn face(state_tx: Option<mpsc::Sender<super::State>>) -> Self {
-        Self { state_tx, device_path: FACE_DEVICE_PATH, rotation: true }
+        Self { state_tx, device_path: FACE_DEVICE_PATH, rotation: true, fake_port: None }
     }
 }
 
@@ -115,6 +132,8 @@ pub enum Command {
     SetFlip(bool),
     /// Set the Black level of the camera.
     SetBlackLevel(i64),
+    /// Get the range of gain values supported by the sensor.
+    GainRange(oneshot::Sender<RangeInclusive<i64>>),
 }
 
 /// Sensor frame.
@@ -153,6 +172,9 @@ impl super::Agent for Sensor {
 impl super::AgentThread for Sensor {
     #[allow(clippy::too_many_lines)]
     fn run(mut self, mut port: port::Inner<Self>) -> Result<()> {
+        if let Some(fake_port) = self.fake_port.take() {
+            return block_on(run_fake(port, fake_port));
+        }
         let mut restart = false;
         let mut flip = false;
         let mut exit = false;
@@ -167,6 +189,7 @@ impl super::AgentThread for Sensor {
             sensor.set_control("Gain", IR_CAMERA_DEFAULT_GAIN)?;
             sensor.set_control("Exposure", IR_CAMERA_DEFAULT_EXPOSURE)?;
             sensor.set_control("Black Level", IR_CAMERA_DEFAULT_BLACK_LEVEL)?;
+            let gain_range = sensor.control_range("Gain")?;
             let buf = Buffer::new(&sensor, BUF_COUNT)?;
             sensor.with_waiter_context(|waiter, cx| {
                 'enable: loop {
@@ -181,6 +204,10 @@ impl super::AgentThread for Sensor {
                                 Some(Command::SetBlackLevel(black_level)) => {
                                     sensor.set_control("Black Level", black_level)?;
                                 }
+                                Some(Command::GainRange(range_tx)) => {
+                                    #[allow(let_underscore_drop)]
+                                    let _ = range_tx.send(gain_range.clone());
+                                }
                                 Some(Command::Start) => break 'start,
                                 None => {
                                     exit = true;
@@ -241,6 +268,10 @@ impl super::AgentThread for Sensor {
                             Some(Command::SetBlackLevel(black_level)) => {
                                 sensor.set_control("Black Level", black_level)?;
                             }
+                            Some(Command::GainRange(range_tx)) => {
+                                #[allow(let_underscore_drop)]
+                                let _ = range_tx.send(gain_range.clone());
+                            }
                             None => {
                                 exit = true;
                                 break 'enable Ok(());
@@ -299,6 +330,39 @@ impl super::AgentThread for Sensor {
     }
 }
 
+// Forwards frames from the fake port and commands to it. `Stop` commands are
+// answered with an empty log and `GainRange` commands with an unbounded range,
+// so the fake port doesn't have to.
+async fn run_fake(mut port: port::Inner<Sensor>, mut fake_port: port::Outer<Sensor>) -> Result<()> {
+    let mut fake_port_closed = false;
+    loop {
+        let fake_next = if fake_port_closed {
+            future::pending().left_future()
+        } else {
+            fake_port.next().right_future()
+        };
+        match future::select(port.next(), fake_next).await {
+            Either::Left((Some(command), _)) => {
+                if let Command::Stop(tx) = command.value {
+                    let _ = tx.send(Log::default());
+                } else if let Command::GainRange(tx) = command.value {
+                    let _ = tx.send(i64::MIN..=i64::MAX);
+                } else if !fake_port_closed {
+                    let _ = fake_port.send(command).await;
+                }
+            }
+            Either::Right((Some(fake_output), _)) => {
+                port.send(fake_output).await?;
+            }
+            Either::Left((None, _)) => {
+                break;
+            }
+            Either::Right((None, _)) => fake_port_closed = true,
+        }
+    }
+    Ok(())
+}
+
 impl port::Outer<Sensor> {
     /// Stops the capturing and returns configuration history log.
     pub async fn stop(&mut self) -> Result<Log> {
@@ -306,6 +370,13 @@ impl port::Outer<Sensor> {
         self.send(port::Input::new(Command::Stop(tx))).await?;
         Ok(rx.await?)
     }
+
+    /// Returns the range of gain values supported by the sensor.
+    pub async fn gain_range(&mut self) -> Result<RangeInclusive<i64>> {
+        let (tx, rx) = oneshot::channel();
+        self.send(port::Input::new(Command::GainRange(tx))).await?;
+        Ok(rx.await?)
+    }
 }
 
 impl Default for Log {
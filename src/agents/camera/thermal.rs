@@ -22,6 +22,16 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+/// Temperature in degrees Celsius corresponding to the black grayscale level.
+const GRAYSCALE_MIN_TEMPERATURE: f64 = 0.0;
+
+/// Temperature in degrees Celsius corresponding to the white grayscale level.
+const GRAYSCALE_MAX_TEMPERATURE: f64 = 100.0;
+
+/// Fraction of the frame width and height covered by the central region of
+/// interest used for the temperature estimation.
+const TEMPERATURE_ROI_FRACTION: f64 = 0.5;
+
 /// Thermal camera sensor.
 ///
 /// See [the module-level documentation](self) for details.
@@ -166,6 +176,27 @@ impl super::Frame for Frame {
 }
 
 impl Frame {
+    /// Estimates a representative temperature in degrees Celsius as the
+    /// maximum of the central region of interest. The grayscale levels are
+    /// mapped linearly onto the camera temperature range. Returns `None` for an
+    /// empty frame.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    pub fn roi_max_temperature(&self) -> Option<f64> {
+        let (width, height) = (self.0.width(), self.0.height());
+        let roi_width = (width as f64 * TEMPERATURE_ROI_FRACTION) as usize;
+        let roi_height = (height as f64 * TEMPERATURE_ROI_FRACTION) as usize;
+        let (x0, y0) = ((width - roi_width) / 2, (height - roi_height) / 2);
+        let max = (y0..y0 + roi_height)
+            .flat_map(|y| &self.0[y * width + x0..y * width + x0 + roi_width])
+            .max()?;
+        Some(
+            GRAYSCALE_MIN_TEMPERATURE
+                + f64::from(*max) / f64::from(u8::MAX)
+                    * (GRAYSCALE_MAX_TEMPERATURE - GRAYSCALE_MIN_TEMPERATURE),
+        )
+    }
+
     /// Decodes a PNG image into a frame.
     pub fn read_png<R: Read>(reader: R) -> Result<Self> {
         let decoder = png::Decoder::new(reader);
@@ -182,3 +213,24 @@ impl Frame {
         ))))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roi_max_temperature() {
+        let (width, height) = (8, 8);
+        let mut data = vec![0; width * height];
+        // Outside of the central region of interest.
+        data[0] = u8::MAX;
+        data[3 * width + 4] = 51;
+        let frame =
+            Frame(Arc::new(orb_seekcamera::Frame::new(data, Duration::default(), width, height)));
+        let temperature = frame.roi_max_temperature().unwrap();
+        assert!((temperature - 20.0).abs() < 1e-9);
+        let empty =
+            Frame(Arc::new(orb_seekcamera::Frame::new(Vec::new(), Duration::default(), 0, 0)));
+        assert!(empty.roi_max_temperature().is_none());
+    }
+}
@@ -35,6 +35,26 @@ pub struct Agent {
     pub led: Box<dyn led::Engine>,
 }
 
+/// Agent output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistanceState {
+    /// User distance estimated from the RGB-Net landmarks.
+    pub meters: f64,
+    /// Whether the user should come closer or step back.
+    pub zone: Zone,
+}
+
+/// User distance zone relative to the IR focus range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Zone {
+    /// The user is closer than the focus range.
+    TooClose,
+    /// The user is within the focus range.
+    Optimal,
+    /// The user is farther than the focus range.
+    TooFar,
+}
+
 /// Agent input.
 #[derive(Debug)]
 pub enum Input {
@@ -153,7 +173,7 @@ impl Future for Sounds<'_> {
 
 impl Port for Agent {
     type Input = Input;
-    type Output = Infallible;
+    type Output = DistanceState;
 
     const INPUT_CAPACITY: usize = 0;
     const OUTPUT_CAPACITY: usize = 0;
@@ -175,53 +195,64 @@ impl super::AgentTask for Agent {
             let mut sounds = Sounds::new(self.sound.clone(), &state);
             loop {
                 match future::select(port.next(), &mut sounds).await {
-                    Either::Left((Some(input), _)) => match input.value {
-                        Input::IrNetEstimate(ref ir_net_estimate) => {
-                            let python::ir_net::EstimateOutput { sharpness, .. } = *ir_net_estimate;
-                            if sharpness > IRIS_SHARPNESS_MIN && !sharp_iris_detected {
-                                DATADOG.incr(
-                                    "orb.main.count.signup.during.biometric_capture.\
-                                     sharp_iris_detected",
-                                    NO_TAGS,
-                                )?;
-                                sharp_iris_detected = true;
-                            }
-                        }
-                        Input::RgbNetEstimate(rgb_net_estimate) => {
-                            if rgb_net_first_distance_date.is_none() {
-                                rgb_net_first_distance_date = Some(SystemTime::now());
+                    Either::Left((Some(input), _)) => {
+                        let chain = input.chain_fn();
+                        match input.value {
+                            Input::IrNetEstimate(ref ir_net_estimate) => {
+                                let python::ir_net::EstimateOutput { sharpness, .. } =
+                                    *ir_net_estimate;
+                                if sharpness > IRIS_SHARPNESS_MIN && !sharp_iris_detected {
+                                    DATADOG.incr(
+                                        "orb.main.count.signup.during.biometric_capture.\
+                                         sharp_iris_detected",
+                                        NO_TAGS,
+                                    )?;
+                                    sharp_iris_detected = true;
+                                }
                             }
-                            let Some(user_distance) = rgb_net_estimate
-                                .primary()
-                                .map(python::rgb_net::EstimatePredictionOutput::user_distance)
-                            else {
-                                continue;
-                            };
-                            let new_state = if focus_range.contains(&user_distance) {
-                                focus_range = IR_FOCUS_RANGE;
-                                user_came_in_range = true;
-                                rgb_net_first_distance_date = Some(SystemTime::now());
-                                self.led.biometric_capture_distance(true);
-                                IN_RANGE
-                            } else {
-                                // show "user not in range" only if user was in range before
-                                let time_out_of_range = rgb_net_first_distance_date
-                                    .unwrap_or(SystemTime::now())
-                                    .elapsed()
-                                    .unwrap_or(Duration::from_secs(0));
-                                if user_came_in_range || time_out_of_range.as_millis() > 2000 {
-                                    self.led.biometric_capture_distance(false);
+                            Input::RgbNetEstimate(rgb_net_estimate) => {
+                                if rgb_net_first_distance_date.is_none() {
+                                    rgb_net_first_distance_date = Some(SystemTime::now());
                                 }
-                                focus_range = IR_FOCUS_RANGE_SMALL;
-                                if user_distance < IR_FOCUS_DISTANCE { TOO_CLOSE } else { TOO_FAR }
-                            };
-                            state.store(new_state, Ordering::Relaxed);
-                        }
-                        Input::Reset => {
-                            tracing::debug!("RESETTING DISTANCE AGENT");
-                            continue 'reset;
+                                let Some(user_distance) = rgb_net_estimate
+                                    .primary()
+                                    .map(python::rgb_net::EstimatePredictionOutput::user_distance)
+                                else {
+                                    continue;
+                                };
+                                let zone = if focus_range.contains(&user_distance) {
+                                    focus_range = IR_FOCUS_RANGE;
+                                    user_came_in_range = true;
+                                    rgb_net_first_distance_date = Some(SystemTime::now());
+                                    self.led.biometric_capture_distance(true);
+                                    Zone::Optimal
+                                } else {
+                                    // show "user not in range" only if user was in range before
+                                    let time_out_of_range = rgb_net_first_distance_date
+                                        .unwrap_or(SystemTime::now())
+                                        .elapsed()
+                                        .unwrap_or(Duration::from_secs(0));
+                                    if user_came_in_range || time_out_of_range.as_millis() > 2000 {
+                                        self.led.biometric_capture_distance(false);
+                                    }
+                                    focus_range = IR_FOCUS_RANGE_SMALL;
+                                    if user_distance < IR_FOCUS_DISTANCE {
+                                        Zone::TooClose
+                                    } else {
+                                        Zone::TooFar
+                                    }
+                                };
+                                state.store(zone.into(), Ordering::Relaxed);
+                                // The focus distances are in millimeters.
+                                let meters = user_distance / 1000.0;
+                                port.send(chain(DistanceState { meters, zone })).await?;
+                            }
+                            Input::Reset => {
+                                tracing::debug!("RESETTING DISTANCE AGENT");
+                                continue 'reset;
+                            }
                         }
-                    },
+                    }
                     Either::Left((None, _)) => return Ok(()),
                     Either::Right((x, _)) => match x {},
                 }
@@ -229,3 +260,13 @@ impl super::AgentTask for Agent {
         }
     }
 }
+
+impl From<Zone> for u8 {
+    fn from(zone: Zone) -> Self {
+        match zone {
+            Zone::TooClose => TOO_CLOSE,
+            Zone::Optimal => IN_RANGE,
+            Zone::TooFar => TOO_FAR,
+        }
+    }
+}
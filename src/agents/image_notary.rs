@@ -18,7 +18,7 @@ use crate::{
     time_series::TimeSeries,
     utils::sample_at_fps,
 };
-use eyre::{bail, Result};
+use eyre::{bail, Result, WrapErr};
 use futures::{channel::oneshot, prelude::*};
 use orb_wld_data_id::{ImageId, SignupId};
 use ordered_float::OrderedFloat;
@@ -78,7 +78,7 @@ pub enum Input {
 #[allow(missing_docs)]
 #[derive(Debug)]
 pub struct SaveIdentificationImagesInput {
-    pub tx: oneshot::Sender<Option<IdentificationImages>>,
+    pub tx: oneshot::Sender<Option<(IdentificationImages, Vec<SavedImage>)>>,
     pub left: EyeCapture,
     pub right: EyeCapture,
     pub self_custody_candidate: SelfCustodyCandidate,
@@ -91,6 +91,10 @@ pub struct SaveIrNetEstimateInput {
     pub frame: camera::ir::Frame,
     pub wavelength: IrLed,
     pub target_left_eye: bool,
+    /// IR LED duration in microseconds in effect when the frame was captured.
+    pub exposure: u16,
+    /// IR camera gain in effect when the frame was captured.
+    pub gain: f64,
     /// If not `None`, overrides the target FPS for saving.
     pub fps_override: Option<f32>,
     pub log_metadata_always: bool,
@@ -165,14 +169,17 @@ impl Port for Agent {
     const OUTPUT_CAPACITY: usize = 10;
 }
 
+/// IR Net metadata history entry: image ID, IR Net metadata, wavelength,
+/// target left eye flag, IR LED duration, IR camera gain, and capture timestamp.
+pub type IrNetMetadataEntry =
+    (Option<ImageId>, Option<IrNetMetadata>, IrLed, bool, u16, f64, Duration);
+
 /// History of metadata received by this agent.
 /// These values include image_ids, which are assigned within this agent.
 #[derive(Debug)]
 pub struct Log {
     /// IR Net metadata history
-    #[allow(clippy::type_complexity)]
-    pub ir_net_metadata:
-        TimeSeries<(Option<ImageId>, Option<IrNetMetadata>, IrLed, bool, Duration)>,
+    pub ir_net_metadata: TimeSeries<IrNetMetadataEntry>,
     /// IR Face metadata
     pub ir_face_metadata: TimeSeries<(Option<ImageId>, IrLed, Duration)>,
     /// RGB Net metadata history
@@ -200,6 +207,15 @@ pub struct IdentificationImages {
     pub self_custody_candidate: ImageId,
 }
 
+/// Image file written to disk, used to verify the write afterwards.
+#[derive(Clone, Debug)]
+pub struct SavedImage {
+    /// Path of the written file.
+    pub path: PathBuf,
+    /// Number of bytes written.
+    pub size: u64,
+}
+
 impl Default for Log {
     fn default() -> Self {
         Self {
@@ -230,13 +246,14 @@ impl port::Outer<Agent> {
         Ok(rx.await?)
     }
 
-    /// Saves eye / face identification images and returns their IDs.
+    /// Saves eye / face identification images and returns their IDs along
+    /// with the written files.
     pub async fn save_identification_images(
         &mut self,
         left: EyeCapture,
         right: EyeCapture,
         self_custody_candidate: SelfCustodyCandidate,
-    ) -> Result<Option<IdentificationImages>> {
+    ) -> Result<Option<(IdentificationImages, Vec<SavedImage>)>> {
         let (tx, rx) = oneshot::channel();
         self.send(port::Input::new(Input::SaveIdentificationImages(Box::new(
             SaveIdentificationImagesInput { tx, left, right, self_custody_candidate },
@@ -337,7 +354,8 @@ impl Agent {
             &self_custody_candidate,
         );
         DATADOG.incr("orb.main.count.data_collection.identification_images.saved", NO_TAGS)?;
-        let _ = tx.send(Some(identification_images));
+        // No image files are written in this build.
+        let _ = tx.send(Some((identification_images, Vec::new())));
         Ok(())
     }
 
@@ -353,6 +371,8 @@ impl Agent {
             frame,
             wavelength,
             target_left_eye,
+            exposure,
+            gain,
             fps_override,
             log_metadata_always,
         } = input;
@@ -364,6 +384,8 @@ impl Agent {
                 ir_net_metadata,
                 wavelength,
                 target_left_eye,
+                exposure,
+                gain,
                 frame.timestamp(),
             ));
         };
@@ -525,6 +547,21 @@ impl Agent {
     }
 }
 
+/// Checks that every saved image exists on disk with its full size, to catch
+/// truncated writes, e.g. on a full SSD.
+pub async fn verify_saved_images(saved_images: &[SavedImage]) -> Result<()> {
+    for SavedImage { path, size } in saved_images {
+        let len = tokio::fs::metadata(path)
+            .await
+            .wrap_err_with(|| format!("saved image {} is missing", path.display()))?
+            .len();
+        if len == 0 || len != *size {
+            bail!("saved image {} is truncated: {len} of {size} bytes", path.display());
+        }
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_lines)]
 fn identification_images_ids(
     signup_id: &SignupId,
@@ -562,3 +599,18 @@ fn identification_images_ids(
 fn get_image_id(frame: &impl Frame, signup_id: &SignupId) -> ImageId {
     ImageId::new(signup_id, frame.timestamp())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_verify_saved_images() {
+        let path = std::env::temp_dir().join("orb-core-test-verify-saved-images.png");
+        tokio::fs::write(&path, [0; 16]).await.unwrap();
+        assert!(verify_saved_images(&[SavedImage { path: path.clone(), size: 16 }]).await.is_ok());
+        assert!(verify_saved_images(&[SavedImage { path: path.clone(), size: 32 }]).await.is_err());
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert!(verify_saved_images(&[SavedImage { path, size: 16 }]).await.is_err());
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
         python::iris::NormalizedIris,
     },
     backend::{presigned_url::UrlType, upload_image},
+    ext::mpsc::SenderExt as _,
     inst_elapsed,
     logger::{LogOnError, DATADOG, NO_TAGS},
     port,
@@ -18,14 +19,37 @@ use eyre::Result;
 use futures::{channel::oneshot, prelude::*, select};
 use orb_wld_data_id::{ImageId, SignupId};
 use std::{
-    convert::{Infallible, TryInto},
+    convert::TryInto,
     io::Cursor,
+    sync::atomic::{AtomicU64, Ordering},
     time::{Duration, Instant, SystemTime},
 };
 
+// Shared by all agent instances, so that the revisions keep increasing across
+// agent restarts.
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
 /// Image upload agent
 #[derive(Default, Debug)]
-pub struct Agent;
+pub struct Agent {
+    report: UploadReport,
+}
+
+/// Image upload counters since the agent start. Sent as the agent output on
+/// every change. The outputs can be dropped or arrive late, so a report
+/// supersedes another one only if its `revision` is not lower.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct UploadReport {
+    /// Number of successfully uploaded images.
+    pub succeeded: usize,
+    /// Number of images failed to upload.
+    pub failed: usize,
+    /// Number of images waiting for upload.
+    pub remaining: usize,
+    /// Sequence number of the last counters change, increasing over the
+    /// process lifetime.
+    pub revision: u64,
+}
 
 /// Image upload agent inputs
 #[allow(missing_docs)]
@@ -43,11 +67,14 @@ pub enum Input {
         left: Option<NormalizedIris>,
         right: Option<NormalizedIris>,
     },
+    /// Replies with the upload counters once all previously queued uploads are
+    /// done.
+    Flush(oneshot::Sender<UploadReport>),
 }
 
 impl Port for Agent {
     type Input = Input;
-    type Output = Infallible;
+    type Output = UploadReport;
 
     const INPUT_CAPACITY: usize = 0;
     const OUTPUT_CAPACITY: usize = 0;
@@ -65,7 +92,7 @@ impl super::AgentTask for Agent {
             select! {
                 input = port.next() => {
                     if let Some(input) = input {
-                        self.handle_input(input.value).await?;
+                        self.handle_input(input.value, &mut port).await?;
                     } else {
                         break;
                     }
@@ -81,21 +108,58 @@ impl super::AgentTask for Agent {
 /// only in 2 places. During idle state, and during fraud detection. Both these
 /// Orb states are mutually exclusive with respect to execution.
 impl Agent {
-    #[allow(clippy::unused_async)]
-    async fn handle_input(&mut self, input: Input) -> Result<()> {
+    async fn handle_input(&mut self, input: Input, port: &mut port::Inner<Self>) -> Result<()> {
+        let mut progress = Progress { report: &mut self.report, port };
         match input {
             Input::UploadSelfCustodyThumbnail { tx, signup_id, self_custody_thumbnail } => {
-                let _ = tx
-                    .send(upload_self_custody_thumbnail(signup_id, self_custody_thumbnail).await?);
+                progress.queue(1)?;
+                let _ = tx.send(
+                    upload_self_custody_thumbnail(&mut progress, signup_id, self_custody_thumbnail)
+                        .await?,
+                );
             }
             Input::UploadIrisNormalizedImages { tx, signup_id, left, right } => {
-                let _ = tx.send(upload_iris_normalized_images(signup_id, left, right).await?);
+                progress.queue(2 * (usize::from(left.is_some()) + usize::from(right.is_some())))?;
+                let _ = tx.send(
+                    upload_iris_normalized_images(&mut progress, signup_id, left, right).await?,
+                );
+            }
+            Input::Flush(tx) => {
+                let _ = tx.send(self.report);
             }
         }
         Ok(())
     }
 }
 
+// Keeps the upload counters up to date and reports them to the broker.
+struct Progress<'a> {
+    report: &'a mut UploadReport,
+    port: &'a mut port::Inner<Agent>,
+}
+
+impl Progress<'_> {
+    fn queue(&mut self, count: usize) -> Result<()> {
+        self.report.remaining += count;
+        self.send()
+    }
+
+    fn record(&mut self, success: bool) -> Result<()> {
+        self.report.remaining = self.report.remaining.saturating_sub(1);
+        if success {
+            self.report.succeeded += 1;
+        } else {
+            self.report.failed += 1;
+        }
+        self.send()
+    }
+
+    fn send(&mut self) -> Result<()> {
+        self.report.revision = REVISION.fetch_add(1, Ordering::Relaxed) + 1;
+        self.port.send_now(port::Output::new(*self.report))
+    }
+}
+
 impl port::Outer<Agent> {
     /// Upload the self-custody thumbnail of a specific signup from memory.
     pub async fn upload_self_custody_thumbnail(
@@ -130,9 +194,17 @@ impl port::Outer<Agent> {
         .await?;
         Ok(rx.await?)
     }
+
+    /// Waits for all queued uploads to finish and returns the upload counters.
+    pub async fn flush(&mut self) -> Result<UploadReport> {
+        let (tx, rx) = oneshot::channel();
+        self.send(port::Input::new(Input::Flush(tx))).await?;
+        Ok(rx.await?)
+    }
 }
 
 async fn upload_image(
+    progress: &mut Progress<'_>,
     signup_id: &SignupId,
     image_id: &ImageId,
     presigned_url_type: UrlType,
@@ -171,10 +243,11 @@ async fn upload_image(
             tracing::error!("Uploading image {log_image_path} failed: {e}");
         }
     }
-    Ok(())
+    progress.record(response.is_ok())
 }
 
 async fn upload_self_custody_thumbnail(
+    progress: &mut Progress<'_>,
     signup_id: SignupId,
     self_custody_thumbnail: camera::rgb::Frame,
 ) -> Result<ImageId> {
@@ -182,19 +255,31 @@ async fn upload_self_custody_thumbnail(
 
     let image_id = ImageId::new(&signup_id, self_custody_thumbnail.timestamp());
     let mut data = Cursor::new(Vec::new());
-    self_custody_thumbnail.write_png(&mut data, camera::FrameResolution::MAX)?;
+    if let Err(err) = self_custody_thumbnail.write_png(&mut data, camera::FrameResolution::MAX) {
+        progress.record(false)?;
+        return Err(err.into());
+    }
     let data = data.into_inner();
 
     #[cfg(not(feature = "no-image-encryption"))]
     let data = encrypt_and_seal(&data);
 
-    upload_image(&signup_id, &image_id, UrlType::Rgb, data, "direct.thumbnail", "direct.thumbnail")
-        .await?;
+    upload_image(
+        progress,
+        &signup_id,
+        &image_id,
+        UrlType::Rgb,
+        data,
+        "direct.thumbnail",
+        "direct.thumbnail",
+    )
+    .await?;
 
     Ok(image_id)
 }
 
 async fn upload_iris_normalized_images(
+    progress: &mut Progress<'_>,
     signup_id: SignupId,
     left: Option<NormalizedIris>,
     right: Option<NormalizedIris>,
@@ -229,6 +314,7 @@ async fn upload_iris_normalized_images(
             let data_mask = encrypt_and_seal(&data_mask);
 
             upload_image(
+                progress,
                 &signup_id,
                 image_id,
                 UrlType::NormalizedIrisImage,
@@ -238,6 +324,7 @@ async fn upload_iris_normalized_images(
             )
             .await?;
             upload_image(
+                progress,
                 &signup_id,
                 mask_id,
                 UrlType::NormalizedIrisMask,
@@ -39,6 +39,8 @@ pub enum Input {
     Frame(camera::ir::Frame),
     /// Set exposure range.
     SetExposureRange(RangeInclusive<u16>),
+    /// Set gain range.
+    SetGainRange(RangeInclusive<i64>),
     /// Set target mean value.
     SetTargetMean(f64),
 }
@@ -92,6 +94,10 @@ impl super::AgentTask for Agent {
                     port.send(input.chain(Output { gain, exposure })).await?;
                 }
                 Input::SetExposureRange(new_exposure_range) => exposure_range = new_exposure_range,
+                Input::SetGainRange(gain_range) => {
+                    #[allow(clippy::cast_precision_loss)]
+                    controller.set_gain_range(*gain_range.start() as _..=*gain_range.end() as _);
+                }
                 Input::SetTargetMean(new_target_mean) => {
                     target_mean = new_target_mean;
                     controller.pid.reset();
@@ -122,6 +128,11 @@ impl ExposureController {
         Self { pid, gain, exposure }
     }
 
+    /// Limits the gain to the range supported by the camera.
+    pub fn set_gain_range(&mut self, gain_range: RangeInclusive<f64>) {
+        self.gain = self.gain.max(*gain_range.start()).min(*gain_range.end());
+    }
+
     /// Updates the controller with the current pixel mean and target pixel mean
     /// values. Returns gain and exposure.
     // TODO implement gain
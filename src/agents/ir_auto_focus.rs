@@ -54,6 +54,21 @@ const DISTANCE_TO_FOCUS_PARAMS: (f64, f64) = (-1.603_612_87, 462.536_655);
 #[derive(Default, Debug)]
 pub struct Agent;
 
+/// Strategy for searching the sharpest liquid lens focus setting.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum FocusStrategy {
+    /// Hill-climb within a focus range narrowed by the RGB-Net user distance
+    /// estimate. Falls back to the full focus range when RGB-Net gives no
+    /// estimate.
+    #[default]
+    RgbDistanceSeed,
+    /// Hill-climb across the full focus range.
+    HillClimb,
+    /// Sweep the whole focus range once, then hill-climb from the sharpest
+    /// focus setting seen during the sweep.
+    FullSweep,
+}
+
 /// Agent input.
 #[derive(Debug)]
 pub enum Input {
@@ -65,6 +80,8 @@ pub enum Input {
     UserDistance(f64),
     /// Set minimal viable sharpness.
     SetMinSharpness(f64),
+    /// Set the focus search strategy.
+    SetStrategy(FocusStrategy),
     /// Resets the internal state of the agent.
     Reset,
 }
@@ -90,6 +107,8 @@ impl super::AgentTask for Agent {
             let mut timer = InstantTimer::default();
             let mut controller = LiquidLensController::new(DEFAULT_MIN_SHARPNESS);
             let mut range = FOCUS_RANGE;
+            let mut strategy = FocusStrategy::default();
+            let mut sweep: Option<FocusSweep> = None;
             let mut sharpness;
             while let Some(input) = port.next().await {
                 match input.value {
@@ -118,8 +137,12 @@ impl super::AgentTask for Agent {
                         }
                     }
                     Input::UserDistance(user_distance) => {
-                        if !user_distance.is_nan() {
-                            range = user_focus_range(user_distance);
+                        if strategy == FocusStrategy::RgbDistanceSeed {
+                            range = if user_distance.is_nan() {
+                                FOCUS_RANGE
+                            } else {
+                                user_focus_range(user_distance)
+                            };
                         }
                         continue;
                     }
@@ -127,6 +150,12 @@ impl super::AgentTask for Agent {
                         controller.set_min_sharpness(min_sharpness);
                         continue;
                     }
+                    Input::SetStrategy(new_strategy) => {
+                        strategy = new_strategy;
+                        range = FOCUS_RANGE;
+                        sweep = (strategy == FocusStrategy::FullSweep).then(FocusSweep::default);
+                        continue;
+                    }
                     Input::Reset => {
                         let capture_time = start_timestamp.elapsed().as_secs();
                         let fps = if capture_time > 0 { update_counter / capture_time } else { 0 };
@@ -135,7 +164,16 @@ impl super::AgentTask for Agent {
                     }
                 }
                 let dt = timer.get_dt().unwrap_or(0.0);
-                let focus = controller.update(sharpness, range.clone(), dt);
+                let focus = if let Some(focus) =
+                    sweep.as_mut().and_then(|sweep| sweep.update(sharpness, dt))
+                {
+                    focus
+                } else {
+                    if let Some(sweep) = sweep.take() {
+                        controller.set_focus(sweep.best_focus());
+                    }
+                    controller.update(sharpness, range.clone(), dt)
+                };
                 port.send(port::Output::new(focus)).await?;
                 update_counter += 1;
             }
@@ -175,6 +213,15 @@ pub struct LiquidLensController {
     sharpness_peak_searching: bool,
 }
 
+/// Sweeps the liquid lens once through the full focus range, keeping track of
+/// the sharpest focus setting.
+#[derive(Debug)]
+pub struct FocusSweep {
+    focus: f64,
+    best_focus: i16,
+    best_sharpness: f64,
+}
+
 /// Generates the derived signal for [`LiquidLensController`].
 #[derive(Default)]
 pub struct DerivedSignal {
@@ -205,6 +252,16 @@ impl LiquidLensController {
         self.min_sharpness = min_sharpness;
     }
 
+    /// Moves the liquid lens to the `focus` setting and restarts the peak
+    /// search from there.
+    pub fn set_focus(&mut self, focus: i16) {
+        self.focus_curr = focus.clamp(*FOCUS_RANGE.start(), *FOCUS_RANGE.end());
+        self.sharpness_peak = 0.0;
+        self.sharpness_peak_searching = true;
+        self.derived.reset();
+        self.pid.reset();
+    }
+
     /// Updates the controller with current `sharpness` score and focus `range`
     /// limits. Returns the focus setting for the liquid lens.
     #[allow(clippy::cast_possible_truncation)]
@@ -257,6 +314,39 @@ impl LiquidLensController {
     }
 }
 
+impl Default for FocusSweep {
+    fn default() -> Self {
+        Self {
+            focus: f64::from(*FOCUS_RANGE.start()),
+            best_focus: *FOCUS_RANGE.start(),
+            best_sharpness: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl FocusSweep {
+    /// Updates the sweep with the `sharpness` score for the last focus setting.
+    /// Returns the next focus setting for the liquid lens, or `None` when the
+    /// sweep has reached the end of the focus range.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn update(&mut self, sharpness: f64, dt: f64) -> Option<i16> {
+        let focus = self.focus.round() as i16;
+        if sharpness.is_finite() && sharpness > self.best_sharpness {
+            self.best_sharpness = sharpness;
+            self.best_focus = focus;
+        }
+        self.focus += MAX_DF_DT * dt;
+        let next = self.focus.round();
+        (next <= f64::from(*FOCUS_RANGE.end())).then_some(next as i16)
+    }
+
+    /// Returns the sharpest focus setting seen so far.
+    #[must_use]
+    pub fn best_focus(&self) -> i16 {
+        self.best_focus
+    }
+}
+
 impl DerivedSignal {
     /// Adds a new partition of the target function. Returns the derived value.
     pub fn add(&mut self, sharpness: f64, dt: f64) -> Option<f64> {
@@ -23,6 +23,13 @@ const VERTICAL_MIN: f64 = -35.0;
 const VERTICAL_NEUTRAL: f64 = 0.0;
 const VERTICAL_MAX: f64 = 35.0;
 
+/// Maximal distance of the scan pattern points from the neutral point.
+const SCAN_RADIUS: f64 = 10.0;
+/// Number of spiral turns in the scan pattern.
+const SCAN_TURNS: f64 = 3.0;
+/// Radius decrement when a scan pattern point is out of the safe range.
+const SCAN_RADIUS_STEP: f64 = 0.5;
+
 /// Movable mirrors.
 ///
 /// See [the module-level documentation](self) for details.
@@ -41,6 +48,16 @@ pub struct Log {
     pub vertical: TimeSeries<f64>,
 }
 
+/// Backlash compensation state. Remembers the last commanded direction along
+/// each axis, and shifts the points by half of the backlash towards it, so that
+/// a direction reversal jumps over the whole backlash.
+#[derive(Debug)]
+struct Backlash {
+    backlash: Point,
+    last: Option<Point>,
+    direction: Point,
+}
+
 /// Actuator input.
 #[derive(Debug)]
 pub enum Command {
@@ -77,17 +94,20 @@ impl super::Agent for Actuator {
 impl super::AgentTask for Actuator {
     async fn run(self, mut port: port::Inner<Self>) -> Result<()> {
         let mut calibration = Point::from(&self.calibration.mirror);
+        let mut backlash = Backlash::new(self.calibration.mirror.backlash);
         let mut log = Log::default();
         while let Some(command) = port.rx.next().await {
             let chain = command.chain_fn();
             match command.value {
                 Command::SetPoint(point) => {
-                    port.send_now(chain(convert_mirror_point(point + calibration)))?;
+                    let compensated = backlash.compensate(point);
+                    port.send_now(chain(convert_mirror_point(compensated + calibration)))?;
                     log.horizontal.push(point.horizontal);
                     log.vertical.push(point.vertical);
                 }
                 Command::Recalibrate(new_calibration) => {
                     calibration = Point::from(&new_calibration.mirror);
+                    backlash.backlash = new_calibration.mirror.backlash;
                 }
                 Command::TakeLog(log_tx) => {
                     #[allow(let_underscore_drop)]
@@ -125,6 +145,29 @@ impl Point {
     }
 }
 
+impl Backlash {
+    fn new(backlash: Point) -> Self {
+        Self { backlash, last: None, direction: Point::default() }
+    }
+
+    fn compensate(&mut self, point: Point) -> Point {
+        if let Some(last) = self.last.replace(point) {
+            let delta = point - last;
+            if delta.horizontal != 0.0 {
+                self.direction.horizontal = delta.horizontal.signum();
+            }
+            if delta.vertical != 0.0 {
+                self.direction.vertical = delta.vertical.signum();
+            }
+        }
+        Point {
+            horizontal: point.horizontal
+                + self.direction.horizontal * self.backlash.horizontal / 2.0,
+            vertical: point.vertical + self.direction.vertical * self.backlash.vertical / 2.0,
+        }
+    }
+}
+
 impl From<&calibration::Mirror> for Point {
     fn from(calibration: &calibration::Mirror) -> Self {
         Self { horizontal: calibration.horizontal_offset, vertical: calibration.vertical_offset }
@@ -153,6 +196,42 @@ impl Sub for Point {
     }
 }
 
+/// Generates a spiral sweep of `steps` mirror points, starting from the neutral
+/// point and winding outwards. It's used to sweep the eyes into view when the
+/// eye tracking doesn't lock.
+///
+/// Points are pulled towards the neutral point when needed to stay within the
+/// safe servo range with the `calibration` offsets applied.
+#[allow(clippy::cast_precision_loss)]
+#[must_use]
+pub fn scan_pattern(calibration: &Calibration, steps: usize) -> Vec<Point> {
+    let offset = Point::from(&calibration.mirror);
+    let neutral = Point::neutral();
+    (0..steps)
+        .map(|i| {
+            let t = if steps > 1 { i as f64 / (steps - 1) as f64 } else { 0.0 };
+            let angle = t * SCAN_TURNS * 2.0 * PI;
+            let mut radius = t * SCAN_RADIUS;
+            loop {
+                let point = Point {
+                    horizontal: neutral.horizontal + radius * angle.cos(),
+                    vertical: neutral.vertical + radius * angle.sin(),
+                };
+                if radius <= 0.0 || is_within_range(point + offset) {
+                    break point;
+                }
+                radius = (radius - SCAN_RADIUS_STEP).max(0.0);
+            }
+        })
+        .collect()
+}
+
+fn is_within_range(point: Point) -> bool {
+    let (horizontal, vertical) = calc_servo_angles(point);
+    (HORIZONTAL_MIN..=HORIZONTAL_MAX).contains(&horizontal)
+        && (VERTICAL_MIN..=VERTICAL_MAX).contains(&vertical)
+}
+
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 fn convert_mirror_point(point: Point) -> (u32, i32) {
     let (horizontal, vertical) = calc_servo_angles(point);
@@ -239,3 +318,42 @@ fn to_rad(degrees: f64) -> f64 {
 fn to_degree(rad: f64) -> f64 {
     (rad * 180.0) / PI
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_pattern_within_range() {
+        let calibration = Calibration::default();
+        let offset = Point::from(&calibration.mirror);
+        let points = scan_pattern(&calibration, 50);
+        assert_eq!(points.len(), 50);
+        assert!(points.iter().all(|&point| is_within_range(point + offset)));
+        let first = points[0];
+        assert!((first.horizontal - HORIZONTAL_NEUTRAL).abs() < f64::EPSILON);
+        assert!((first.vertical - VERTICAL_NEUTRAL).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_backlash() {
+        let point = |horizontal, vertical| Point { horizontal, vertical };
+        let mut backlash = Backlash::new(point(1.0, 0.5));
+        let compensated = backlash.compensate(point(45.0, 0.0));
+        assert!((compensated.horizontal - 45.0).abs() < f64::EPSILON);
+        assert!(compensated.vertical.abs() < f64::EPSILON);
+        let compensated = backlash.compensate(point(46.0, 1.0));
+        assert!((compensated.horizontal - 46.5).abs() < f64::EPSILON);
+        assert!((compensated.vertical - 1.25).abs() < f64::EPSILON);
+        // Only the horizontal direction reverses.
+        let compensated = backlash.compensate(point(45.0, 1.0));
+        assert!((compensated.horizontal - 44.5).abs() < f64::EPSILON);
+        assert!((compensated.vertical - 1.25).abs() < f64::EPSILON);
+
+        let mut backlash = Backlash::new(Point::default());
+        backlash.compensate(point(45.0, 0.0));
+        let compensated = backlash.compensate(point(40.0, -3.0));
+        assert!((compensated.horizontal - 40.0).abs() < f64::EPSILON);
+        assert!((compensated.vertical + 3.0).abs() < f64::EPSILON);
+    }
+}
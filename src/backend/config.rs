@@ -6,6 +6,7 @@ use crate::{
     agents::python::face_identifier,
     backend::endpoints::MANAGEMENT_BACKEND_URL,
     identification::{get_orb_token, ORB_ID},
+    led::LedPalette,
     plans::fraud_check,
 };
 use eyre::Result;
@@ -44,9 +45,37 @@ pub struct Config {
     pub upload_self_custody_images: Option<bool>,
     pub upload_self_custody_thumbnail: Option<bool>,
     pub upload_iris_normalized_images: Option<bool>,
+    pub rgb_frame_skip_cpu_load_threshold: Option<f64>,
+    pub rgb_frame_skip_ratio: Option<u32>,
+    pub ir_net_min_interval_ms: Option<u64>,
+    pub rgb_net_min_interval_ms: Option<u64>,
+    #[serde(default)]
+    pub biometric_capture: BiometricCaptureConfig,
+    pub led_palette: Option<LedPalette>,
     pub last_updated: u64,
 }
 
+/// See [`BiometricCaptureConfig`](crate::config::BiometricCaptureConfig) for
+/// individual field docs.
+#[allow(missing_docs)]
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct BiometricCaptureConfig {
+    pub timeout_ms: Option<u64>,
+    pub min_sharpness: Option<f64>,
+    pub ir_target_mean: Option<f64>,
+    pub adaptive_gate_fraction: Option<f64>,
+    pub dual_eye_cameras: Option<bool>,
+    pub sharpness_filter_rc: Option<f64>,
+    pub overheat_temperature: Option<f64>,
+    pub overheat_hysteresis: Option<f64>,
+    pub face_illumination: Option<u8>,
+    pub occlusion_30_threshold: Option<f64>,
+    pub occlusion_30_invalid_factor: Option<f64>,
+    pub occlusion_30_on_factor: Option<f64>,
+    pub occlusion_30_off_factor: Option<f64>,
+}
+
 /// Makes an orb config request.
 pub async fn request() -> Result<Response> {
     let request = super::client()?
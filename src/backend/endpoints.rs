@@ -2,11 +2,27 @@
 
 use once_cell::sync::Lazy;
 use orb_endpoints::Backend;
+use std::env;
 
 /// The backend that orb-core will talk to. Based on env vars.
 pub static BACKEND: Lazy<Backend> =
     Lazy::new(Backend::from_env_or_build_type::<{ cfg!(feature = "stage") }>);
 
+/// Environment variable with a base URL, e.g. `http://localhost:8080`, which
+/// replaces all backend URLs below. Used to test against a fake backend, and
+/// ignored in production builds.
+pub const BACKEND_URL_OVERRIDE_ENV: &str = "ORB_BACKEND_URL_OVERRIDE";
+
+/// Base URL override from [`BACKEND_URL_OVERRIDE_ENV`].
+pub static BACKEND_URL_OVERRIDE: Lazy<Option<String>> = Lazy::new(|| {
+    if !cfg!(any(test, feature = "stage")) {
+        return None;
+    }
+    let url = env::var(BACKEND_URL_OVERRIDE_ENV).ok().filter(|url| !url.is_empty())?;
+    tracing::warn!("Backend URLs are overridden with {url}");
+    Some(url.trim_end_matches('/').to_owned())
+});
+
 // TODO: Consolidate all of this in orb_endpoints crate
 
 macro_rules! make_urls {
@@ -16,6 +32,9 @@ macro_rules! make_urls {
     )+) => {$(
         $(#[$($attrs)*])*
         $vis static $ident: Lazy<String> = Lazy::new(|| {
+            if let Some(url) = &*BACKEND_URL_OVERRIDE {
+                return with_override($s, url);
+            }
             let backend_channel = match *BACKEND {
                 Backend::Prod => "orb",
                 Backend::Staging => "stage.orb",
@@ -38,3 +57,25 @@ make_urls! {
     /// Host for network monitoring purposes.
     pub static NETWORK_MONITOR_HOST="signup.{}.worldcoin.org";
 }
+
+// Host-only templates get the host name of the override URL, without the port.
+fn with_override(template: &str, url: &str) -> String {
+    if template.contains("://") {
+        return url.to_owned();
+    }
+    let host = url.split_once("://").map_or(url, |(_, host)| host);
+    host.split(['/', ':']).next().unwrap_or(host).to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_override() {
+        let url = "http://localhost:8080";
+        assert_eq!(with_override("https://management.{}.worldcoin.org", url), url);
+        assert_eq!(with_override("signup.{}.worldcoin.org", url), "localhost");
+        assert_eq!(with_override("signup.{}.worldcoin.org", "localhost/api"), "localhost");
+    }
+}
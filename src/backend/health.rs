@@ -0,0 +1,61 @@
+//! Backend liveness probe.
+
+use crate::{
+    backend::endpoints::MANAGEMENT_BACKEND_URL,
+    identification::{get_orb_token, ORB_ID},
+};
+use eyre::Result;
+use serde::Deserialize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Result of a successful health check.
+#[derive(Clone, Copy, Debug)]
+pub struct HealthResult {
+    /// Round-trip latency of the request.
+    pub latency: Duration,
+    /// Absolute difference between the server clock and the Orb clock, or
+    /// `None` if the server didn't report its time.
+    pub clock_skew: Option<Duration>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    /// Server time in milliseconds since the UNIX epoch.
+    server_time: Option<u64>,
+}
+
+/// Makes a minimal authenticated request to check the backend connectivity.
+pub async fn ping() -> Result<HealthResult> {
+    let orb_id = &*ORB_ID;
+    let request = super::client()?
+        .get(format!("{}/api/v1/orbs/{}/health", *MANAGEMENT_BACKEND_URL, orb_id))
+        .basic_auth(orb_id, Some(get_orb_token()?));
+    let sent_at = SystemTime::now();
+    let t = Instant::now();
+    let response = request.send().await?.error_for_status()?;
+    let Response { server_time } = response.json().await?;
+    let latency = t.elapsed();
+    // Assume the server time was sampled halfway through the round trip.
+    let clock_skew = server_time
+        .map(|server_time| clock_skew(Duration::from_millis(server_time), sent_at + latency / 2));
+    tracing::debug!("Backend health check: latency {latency:?}, clock skew {clock_skew:?}");
+    Ok(HealthResult { latency, clock_skew })
+}
+
+fn clock_skew(server_time: Duration, local_time: SystemTime) -> Duration {
+    let local_time = local_time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    server_time.max(local_time) - server_time.min(local_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew() {
+        let local_time = UNIX_EPOCH + Duration::from_secs(1000);
+        assert_eq!(clock_skew(Duration::from_secs(1002), local_time), Duration::from_secs(2));
+        assert_eq!(clock_skew(Duration::from_secs(997), local_time), Duration::from_secs(3));
+    }
+}
@@ -2,6 +2,7 @@
 
 pub mod config;
 pub mod endpoints;
+pub mod health;
 pub mod operator_status;
 pub mod presigned_url;
 pub mod s3_region;
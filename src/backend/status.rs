@@ -2,14 +2,70 @@
 
 use crate::{
     backend::endpoints::MANAGEMENT_BACKEND_URL,
+    consts::CONFIG_DIR,
     identification::{get_orb_token, ORB_ID},
 };
 use eyre::Result;
-use serde::Serialize;
+use futures::prelude::*;
+use once_cell::sync::Lazy;
+use rand::random;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    fs,
+    sync::{Mutex, OnceCell},
+    task,
+    task::JoinHandle,
+    time,
+};
+
+/// Maximum number of undelivered status requests kept in the offline queue.
+pub const STATUS_QUEUE_CAPACITY: usize = 32;
+
+const STATUS_QUEUE_FILE: &str = "status_queue.json";
+
+/// Battery level in percents below which the battery is reported as low.
+pub const BATTERY_LOW_LEVEL: f64 = 15.0;
+
+// Temperature limits in degree Celsius above which the orb is reported as
+// thermal throttling.
+const CPU_TEMPERATURE_LIMIT: f64 = 85.0;
+const GPU_TEMPERATURE_LIMIT: f64 = 85.0;
+const FRONT_UNIT_TEMPERATURE_LIMIT: f64 = 75.0;
+const FRONT_PCB_TEMPERATURE_LIMIT: f64 = 75.0;
+const BACKUP_BATTERY_TEMPERATURE_LIMIT: f64 = 75.0;
+const BATTERY_PCB_TEMPERATURE_LIMIT: f64 = 60.0;
+const BATTERY_CELL_TEMPERATURE_LIMIT: f64 = 55.0;
+const LIQUID_LENS_TEMPERATURE_LIMIT: f64 = 85.0;
+const MAIN_ACCELEROMETER_TEMPERATURE_LIMIT: f64 = 80.0;
+const MAIN_MCU_TEMPERATURE_LIMIT: f64 = 70.0;
+const MAINBOARD_TEMPERATURE_LIMIT: f64 = 85.0;
+const SECURITY_ACCELEROMETER_TEMPERATURE_LIMIT: f64 = 80.0;
+const SECURITY_MCU_TEMPERATURE_LIMIT: f64 = 85.0;
+const BATTERY_PACK_TEMPERATURE_LIMIT: f64 = 60.0;
+const SSD_TEMPERATURE_LIMIT: f64 = 70.0;
+
+const RETRY_INITIAL_DELAY: Duration = Duration::from_millis(500);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Shortest period of [`StatusReporter`].
+pub const MIN_REPORT_PERIOD: Duration = Duration::from_secs(1);
+
+/// The last status request delivered to the backend in this session.
+static LAST_REQUEST: Lazy<Mutex<Option<Request>>> = Lazy::new(|| Mutex::new(None));
+
+/// The offline queue, loaded from disk on the first access.
+static STATUS_QUEUE: OnceCell<Mutex<StatusQueue>> = OnceCell::const_new();
 
 /// The JSON structure of the orb status request.
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Request {
     pub battery: Battery,
@@ -17,10 +73,73 @@ pub struct Request {
     pub temperature: Temperature,
     pub location: Location,
     pub version: OrbVersion,
+    #[serde(default)]
+    pub battery_low: bool,
+    #[serde(default)]
+    pub thermal_throttling: bool,
+}
+
+impl Request {
+    /// Sets the `battery_low` and `thermal_throttling` flags derived from the
+    /// battery level and the temperatures.
+    #[must_use]
+    pub fn with_derived_flags(mut self) -> Self {
+        self.battery_low = self.battery.level < BATTERY_LOW_LEVEL;
+        self.thermal_throttling = self.temperature.exceeds_limits();
+        self
+    }
+
+    /// Returns a partial request containing only the fields which differ from
+    /// `previous`.
+    #[must_use]
+    pub fn diff(&self, previous: &Request) -> PartialRequest {
+        fn changed<T: Clone + PartialEq>(current: &T, previous: &T) -> Option<T> {
+            (current != previous).then(|| current.clone())
+        }
+        PartialRequest {
+            battery: changed(&self.battery, &previous.battery),
+            wifi: changed(&self.wifi, &previous.wifi),
+            temperature: changed(&self.temperature, &previous.temperature),
+            location: changed(&self.location, &previous.location),
+            version: changed(&self.version, &previous.version),
+            battery_low: changed(&self.battery_low, &previous.battery_low),
+            thermal_throttling: changed(&self.thermal_throttling, &previous.thermal_throttling),
+        }
+    }
+}
+
+/// The JSON structure of the incremental orb status request. Unchanged fields
+/// are omitted.
+#[allow(missing_docs)]
+#[derive(Serialize, Clone, Default, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery: Option<Battery>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wifi: Option<Wifi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<Temperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<OrbVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_low: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thermal_throttling: Option<bool>,
+}
+
+impl PartialRequest {
+    /// Returns `true` if no field has changed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Battery {
     pub level: f64,
@@ -35,7 +154,7 @@ impl Default for Battery {
 }
 
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Wifi {
     #[serde(rename = "SSID")]
@@ -44,7 +163,7 @@ pub struct Wifi {
 }
 
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WifiQuality {
     pub bit_rate: f64,
@@ -54,7 +173,7 @@ pub struct WifiQuality {
 }
 
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Temperature {
     pub cpu: f64,
@@ -79,8 +198,32 @@ pub struct Temperature {
     pub ssd: f64,
 }
 
+impl Temperature {
+    fn exceeds_limits(&self) -> bool {
+        [
+            (self.cpu, CPU_TEMPERATURE_LIMIT),
+            (self.gpu, GPU_TEMPERATURE_LIMIT),
+            (self.front_unit, FRONT_UNIT_TEMPERATURE_LIMIT),
+            (self.front_pcb, FRONT_PCB_TEMPERATURE_LIMIT),
+            (self.backup_battery, BACKUP_BATTERY_TEMPERATURE_LIMIT),
+            (self.battery_pcb, BATTERY_PCB_TEMPERATURE_LIMIT),
+            (self.battery_cell, BATTERY_CELL_TEMPERATURE_LIMIT),
+            (self.liquid_lens, LIQUID_LENS_TEMPERATURE_LIMIT),
+            (self.main_accelerometer, MAIN_ACCELEROMETER_TEMPERATURE_LIMIT),
+            (self.main_mcu, MAIN_MCU_TEMPERATURE_LIMIT),
+            (self.mainboard, MAINBOARD_TEMPERATURE_LIMIT),
+            (self.security_accelerometer, SECURITY_ACCELEROMETER_TEMPERATURE_LIMIT),
+            (self.security_mcu, SECURITY_MCU_TEMPERATURE_LIMIT),
+            (self.battery_pack, BATTERY_PACK_TEMPERATURE_LIMIT),
+            (self.ssd, SSD_TEMPERATURE_LIMIT),
+        ]
+        .into_iter()
+        .any(|(temperature, limit)| temperature > limit)
+    }
+}
+
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Location {
     pub latitude: f64,
@@ -88,20 +231,441 @@ pub struct Location {
 }
 
 #[allow(missing_docs)]
-#[derive(Serialize, Clone, Default, Debug)]
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct OrbVersion {
     pub current_release: String,
 }
 
-/// Makes an orb status request.
+/// Bounded on-disk queue of status requests which couldn't be delivered to the
+/// backend.
+///
+/// Only the latest request is kept for each orb, as a newer status supersedes
+/// the older ones. When the queue is full, the oldest request is dropped.
+pub struct StatusQueue {
+    path: PathBuf,
+    capacity: usize,
+    entries: VecDeque<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    orb_id: String,
+    request: Request,
+}
+
+impl StatusQueue {
+    /// Opens the queue stored at `path`. Starts with an empty queue if the file
+    /// doesn't exist or can't be parsed.
+    pub async fn open(path: PathBuf, capacity: usize) -> Self {
+        let entries = match fs::read_to_string(&path).await {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| tracing::error!("Status queue parsing error: {err:?}"))
+                .unwrap_or_default(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => VecDeque::new(),
+            Err(err) => {
+                tracing::error!("Status queue loading error: {err:?}");
+                VecDeque::new()
+            }
+        };
+        let mut queue = Self { path, capacity, entries };
+        queue.drop_exceeding();
+        queue
+    }
+
+    /// Returns the number of queued requests.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the queue is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends a request for `orb_id`, replacing the previously queued request
+    /// for the same orb.
+    pub async fn push(&mut self, orb_id: &str, request: Request) -> Result<()> {
+        self.entries.retain(|entry| entry.orb_id != orb_id);
+        self.entries.push_back(Entry { orb_id: orb_id.to_owned(), request });
+        self.drop_exceeding();
+        self.store().await
+    }
+
+    /// Sends the queued requests oldest-first using `send`, stopping at the
+    /// first failure. Requests rejected by the backend with a client error are
+    /// dropped, because re-sending them wouldn't succeed. Returns the number of
+    /// delivered requests.
+    pub async fn flush_with<F, Fut>(&mut self, mut send: F) -> Result<usize>
+    where
+        F: FnMut(String, Request) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut delivered = 0;
+        let mut rejected = 0;
+        let result = loop {
+            let Some(Entry { orb_id, request }) = self.entries.front() else {
+                break Ok(delivered);
+            };
+            match send(orb_id.clone(), request.clone()).await {
+                Ok(()) => delivered += 1,
+                Err(err) if is_client_error(&err) => {
+                    tracing::error!("Dropping a status request rejected by the backend: {err:?}");
+                    rejected += 1;
+                }
+                Err(err) => break Err(err),
+            }
+            self.entries.pop_front();
+        };
+        if delivered + rejected > 0 {
+            self.store().await?;
+        }
+        result
+    }
+
+    /// Sends the queued requests to the backend oldest-first.
+    pub async fn flush(&mut self) -> Result<usize> {
+        self.flush_with(post).await
+    }
+
+    fn drop_exceeding(&mut self) {
+        while self.entries.len() > self.capacity {
+            if let Some(entry) = self.entries.pop_front() {
+                tracing::warn!("Status queue is full, dropping a request for {}", entry.orb_id);
+            }
+        }
+    }
+
+    async fn store(&self) -> Result<()> {
+        let json = serde_json::to_string(&self.entries)?;
+        fs::write(&self.path, json).await?;
+        Ok(())
+    }
+}
+
+/// Makes an orb status request. If the backend is unreachable or fails with a
+/// server error, the request is stored in the offline queue and delivered by a
+/// later call.
 pub async fn request(request: &Request) -> Result<()> {
+    request_with_retry(request, 1).await
+}
+
+/// Makes an orb status request, making up to `max_attempts` attempts on server
+/// errors and transport failures with a jittered exponential backoff. Client
+/// errors, e.g. failed authorization, are neither retried nor queued. If all
+/// attempts fail, the request is stored in the offline queue and the last error
+/// is returned.
+///
+/// The offline queue is locked only while it's flushed or appended, not during
+/// the backoff.
+pub async fn request_with_retry(request: &Request, max_attempts: usize) -> Result<()> {
+    let orb_id = ORB_ID.to_string();
+    {
+        let mut queue = status_queue().await.lock().await;
+        if !queue.is_empty() {
+            if let Err(err) = queue.flush().await {
+                queue.push(&orb_id, request.clone()).await?;
+                return Err(err);
+            }
+        }
+    }
+    let mut delay = RETRY_INITIAL_DELAY;
+    let mut attempt = 1;
+    loop {
+        match post(orb_id.clone(), request.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt < max_attempts && is_retryable(&err) => {
+                let jittered = delay.mul_f64(0.5 + random::<f64>());
+                tracing::warn!(
+                    "Status request attempt {attempt}/{max_attempts} failed, retrying in \
+                     {jittered:?}: {err:?}"
+                );
+                time::sleep(jittered).await;
+                delay = (delay * 2).min(RETRY_MAX_DELAY);
+                attempt += 1;
+            }
+            Err(err) => {
+                if !is_client_error(&err) {
+                    status_queue().await.lock().await.push(&orb_id, request.clone()).await?;
+                }
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Makes an incremental orb status request, sending only the fields which
+/// changed since the last delivered request. The first request of each session
+/// sends the full state. If the incremental request fails, the next call sends
+/// the full state again, and unless the backend rejected the request with a
+/// client error, the full request is stored in the offline queue.
+///
+/// Incremental requests are sent with `PATCH`, which requires support from the
+/// management backend. The orb reports its status with [`request`] unless
+/// explicitly opted in.
+pub async fn request_partial(request: &Request) -> Result<()> {
+    let mut last_request = LAST_REQUEST.lock().await;
+    let Some(previous) = last_request.take() else {
+        request_with_retry(request, 1).await?;
+        *last_request = Some(request.clone());
+        return Ok(());
+    };
+    let partial = request.diff(&previous);
+    if partial.is_empty() {
+        *last_request = Some(previous);
+        return Ok(());
+    }
+    let orb_id = ORB_ID.to_string();
+    if let Err(err) = post_partial(&orb_id, &partial).await {
+        if !is_client_error(&err) {
+            status_queue().await.lock().await.push(&orb_id, request.clone()).await?;
+        }
+        return Err(err);
+    }
+    *last_request = Some(request.clone());
+    Ok(())
+}
+
+/// Periodic status reporter. Each orb reports with a fixed offset within the
+/// period derived from its ID, so that the fleet doesn't hit the backend on the
+/// same boundary.
+pub struct StatusReporter {
+    period: Duration,
+    task: Option<JoinHandle<()>>,
+}
+
+impl StatusReporter {
+    /// Creates a new stopped reporter posting the status every `period`.
+    /// Periods shorter than [`MIN_REPORT_PERIOD`] are clamped to it.
+    #[must_use]
+    pub fn new(period: Duration) -> Self {
+        Self { period: period.max(MIN_REPORT_PERIOD), task: None }
+    }
+
+    /// Starts reporting the status assembled by `collect`, restarting the
+    /// reporter if it's already running. Requests are sent with [`request`], so
+    /// failed requests end up in the offline queue.
+    pub fn start<F>(&mut self, mut collect: F)
+    where
+        F: FnMut() -> Request + Send + 'static,
+    {
+        self.stop();
+        let period = self.period;
+        let offset = jitter(&ORB_ID, period);
+        tracing::info!("Starting status reporter every {period:?} with offset {offset:?}");
+        self.task = Some(task::spawn(async move {
+            time::sleep(offset).await;
+            let mut interval = time::interval(period);
+            interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(err) = request(&collect()).await {
+                    tracing::warn!("Periodic status request failed: {err:?}");
+                }
+            }
+        }));
+    }
+
+    /// Stops reporting the status.
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// Returns `true` if the reporter is running.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+}
+
+impl Drop for StatusReporter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// Derives a stable offset within `period` from the orb ID.
+#[allow(clippy::cast_possible_truncation)]
+fn jitter(orb_id: &str, period: Duration) -> Duration {
+    let hash = digest(&SHA256, orb_id.as_bytes());
+    let hash = u64::from_be_bytes(hash.as_ref()[..8].try_into().expect("digest is too short"));
+    let period_ms = (period.as_millis() as u64).max(1);
+    Duration::from_millis(hash % period_ms)
+}
+
+async fn status_queue() -> &'static Mutex<StatusQueue> {
+    STATUS_QUEUE
+        .get_or_init(|| async {
+            Mutex::new(
+                StatusQueue::open(
+                    Path::new(CONFIG_DIR).join(STATUS_QUEUE_FILE),
+                    STATUS_QUEUE_CAPACITY,
+                )
+                .await,
+            )
+        })
+        .await
+}
+
+async fn post_partial(orb_id: &str, partial: &PartialRequest) -> Result<()> {
+    let response = super::client()?
+        .patch(format!("{}/api/v1/orbs/{}/status", *MANAGEMENT_BACKEND_URL, orb_id))
+        .basic_auth(orb_id, Some(get_orb_token()?))
+        .json(partial)
+        .send()
+        .await?;
+    response.error_for_status_ref()?;
+    Ok(())
+}
+
+async fn post(orb_id: String, request: Request) -> Result<()> {
     let response = super::client()?
-        .post(format!("{}/api/v1/orbs/{}/status", *MANAGEMENT_BACKEND_URL, *ORB_ID))
-        .basic_auth(&*ORB_ID, Some(get_orb_token()?))
-        .json(request)
+        .post(format!("{}/api/v1/orbs/{}/status", *MANAGEMENT_BACKEND_URL, orb_id))
+        .basic_auth(&orb_id, Some(get_orb_token()?))
+        .json(&request)
         .send()
         .await?;
     response.error_for_status_ref()?;
     Ok(())
 }
+
+fn is_client_error(err: &eyre::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        .is_some_and(|status| status.is_client_error())
+}
+
+fn is_retryable(err: &eyre::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>().map_or(false, |err| match err.status() {
+        Some(status) => status.is_server_error(),
+        None => err.is_connect() || err.is_timeout() || err.is_request(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::eyre;
+    use std::process;
+
+    #[test]
+    fn test_jitter() {
+        let period = Duration::from_secs(60);
+        let offset = jitter("ea2ea744", period);
+        assert!(offset < period);
+        assert_eq!(offset, jitter("ea2ea744", period));
+        assert_ne!(offset, jitter("ea2ea745", period));
+        assert_eq!(jitter("ea2ea744", Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_reporter_period() {
+        assert_eq!(StatusReporter::new(Duration::ZERO).period, MIN_REPORT_PERIOD);
+        assert_eq!(StatusReporter::new(Duration::from_secs(60)).period, Duration::from_secs(60));
+    }
+
+    fn queue_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}-{}.json", process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn battery_request(level: f64) -> Request {
+        let mut request = Request::default();
+        request.battery.level = level;
+        request
+    }
+
+    #[tokio::test]
+    async fn test_flush_after_failure() {
+        let path = queue_path("status-queue-flush");
+        let mut queue = StatusQueue::open(path.clone(), 8).await;
+        queue.push("orb-a", battery_request(10.0)).await.unwrap();
+        queue.push("orb-b", battery_request(20.0)).await.unwrap();
+        queue.push("orb-a", battery_request(30.0)).await.unwrap();
+        assert_eq!(queue.len(), 2);
+
+        let result = queue.flush_with(|_, _| future::ready(Err(eyre!("offline")))).await;
+        assert!(result.is_err());
+        assert_eq!(StatusQueue::open(path.clone(), 8).await.len(), 2);
+
+        let mut sent = Vec::new();
+        let delivered = queue
+            .flush_with(|orb_id, request| {
+                sent.push((orb_id, request.battery.level));
+                future::ready(Ok(()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(delivered, 2);
+        assert_eq!(sent, [("orb-b".to_owned(), 20.0), ("orb-a".to_owned(), 30.0)]);
+        assert!(queue.is_empty());
+        assert!(StatusQueue::open(path.clone(), 8).await.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_derived_flags() {
+        let request = battery_request(50.0).with_derived_flags();
+        assert!(!request.battery_low);
+        assert!(!request.thermal_throttling);
+
+        let mut request = battery_request(10.0);
+        request.temperature.ssd = SSD_TEMPERATURE_LIMIT + 1.0;
+        let request = request.with_derived_flags();
+        assert!(request.battery_low);
+        assert!(request.thermal_throttling);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["batteryLow"], true);
+        assert_eq!(json["thermalThrottling"], true);
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = battery_request(50.0);
+        assert!(previous.diff(&previous).is_empty());
+
+        let mut request = battery_request(40.0);
+        request.battery.is_charging = false;
+        let partial = request.diff(&previous);
+        assert!(!partial.is_empty());
+        let json = serde_json::to_value(&partial).unwrap();
+        assert_eq!(json, serde_json::json!({ "battery": { "level": 40.0, "isCharging": false } }));
+    }
+
+    #[tokio::test]
+    async fn test_latest_per_orb() {
+        let path = queue_path("status-queue-latest");
+        let mut queue = StatusQueue::open(path.clone(), 8).await;
+        queue.push("orb-a", battery_request(10.0)).await.unwrap();
+        queue.push("orb-a", battery_request(20.0)).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        let reopened = StatusQueue::open(path.clone(), 8).await;
+        assert_eq!(reopened.entries[0].request, battery_request(20.0));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_capacity() {
+        let path = queue_path("status-queue-capacity");
+        let mut queue = StatusQueue::open(path.clone(), 2).await;
+        queue.push("orb-a", battery_request(10.0)).await.unwrap();
+        queue.push("orb-b", battery_request(20.0)).await.unwrap();
+        queue.push("orb-c", battery_request(30.0)).await.unwrap();
+        assert_eq!(queue.len(), 2);
+        let mut sent = Vec::new();
+        queue
+            .flush_with(|orb_id, _| {
+                sent.push(orb_id);
+                future::ready(Ok(()))
+            })
+            .await
+            .unwrap();
+        assert_eq!(sent, ["orb-b", "orb-c"]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -4,7 +4,7 @@
 use eyre::{bail, Result};
 use futures::{channel::mpsc, prelude::*};
 use orb::{
-    agents::{camera, mirror},
+    agents::{camera, ir_auto_focus::FocusStrategy, mirror},
     async_main,
     backend::init_cert,
     brokers::{BrokerFlow, Orb, OrbPlan},
@@ -77,7 +77,7 @@ impl Plan {
         orb.start_rgb_camera().await?;
         orb.start_thermal_camera().await?;
         orb.start_ir_auto_exposure(IR_TARGET_MEAN).await?;
-        orb.start_ir_auto_focus(MIN_SHARPNESS, false).await?;
+        orb.start_ir_auto_focus(MIN_SHARPNESS, FocusStrategy::HillClimb).await?;
         orb.enable_eye_tracker()?;
         orb.enable_mirror()?;
         orb.set_fisheye(RGB_REDUCED_WIDTH, RGB_REDUCED_HEIGHT, false).await?;
@@ -91,6 +91,7 @@ async fn run(cli: Cli) -> Result<()> {
     // from the backend and then store it locally. We don't fall back to the
     // stored version, since it's untrusted (in a writable partition).
     Config::download_and_store(Arc::clone(&config)).await?;
+    led.palette(config.lock().await.led_palette);
     warmup::Plan::default().run(&mut orb).await?;
 
     led.boot_complete();
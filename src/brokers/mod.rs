@@ -10,7 +10,10 @@ pub use self::{
         Builder as ObserverBuilder, DefaultPlan as DefaultObserverPlan, Observer,
         Plan as ObserverPlan,
     },
-    orb::{Builder, Orb, Plan as OrbPlan, StateRx as OrbStateRx},
+    orb::{
+        Builder, Orb, Plan as OrbPlan, SelfTestCheck, SelfTestReport, ShutdownHook,
+        StateRx as OrbStateRx,
+    },
 };
 
 use futures::prelude::*;
@@ -158,7 +158,7 @@ impl Plan for DefaultPlan {
         if observer.status_update.is_terminated()
             && observer.status_update_interval.next().poll_unpin(cx).is_ready()
         {
-            let request = observer.status_request.clone();
+            let request = observer.status_request.clone().with_derived_flags();
             let future = async move {
                 match status::request(&request).await {
                     Ok(()) => {
@@ -186,10 +186,12 @@ impl Plan for DefaultPlan {
             }
             let config = Arc::clone(&observer.config);
             let sound = observer.sound.clone();
+            let led = observer.led.clone();
             observer.config_update = Some(tokio::spawn(async move {
                 let old_lang = config.lock().await.language().clone();
                 if let Ok(new_config) = Config::download().await {
                     let new_lang = new_config.language().clone();
+                    led.palette(new_config.led_palette);
                     *config.lock().await = new_config;
                     if old_lang != new_lang {
                         let sound_files_fut = sound.load_sound_files(new_lang.as_deref(), true);
@@ -4,51 +4,92 @@ use crate::{
         camera, distance, eye_pid_controller, eye_tracker, image_notary, image_uploader,
         ir_auto_exposure, ir_auto_focus, mirror,
         python::{
-            face_identifier, ir_net, mega_agent_one,
+            face_identifier, ir_net, iris, mega_agent_one,
             mega_agent_two::{self, FusionErrors},
             rgb_net,
         },
         qr_code,
     },
+    backend::status,
     calibration::Calibration,
     config::Config,
     consts::{
         DBUS_SIGNUP_OBJECT_PATH, DBUS_WELL_KNOWN_BUS_NAME, DEFAULT_IR_LED_DURATION,
-        DEFAULT_IR_LED_WAVELENGTH, GRACEFUL_SHUTDOWN_MAX_DELAY_SECONDS, IR_CAMERA_FRAME_RATE,
-        IR_LED_MAX_DURATION, IR_LED_MAX_DURATION_740NM, IR_LED_MIN_DURATION,
+        DEFAULT_IR_LED_WAVELENGTH, EYE_LOCK_TIMEOUT, GRACEFUL_SHUTDOWN_MAX_DELAY_SECONDS,
+        IR_CAMERA_DEFAULT_GAIN, IR_CAMERA_FRAME_RATE, IR_LED_MAX_DURATION,
+        IR_LED_MAX_DURATION_740NM, IR_LED_MIN_DURATION, RGB_FPS, STANDBY_TIMEOUT,
+        UPLOAD_FLUSH_TIMEOUT,
     },
     dbus::SupervisorProxy,
-    ext::mpsc::SenderExt as _,
+    ext::mpsc::{ReceiverExt as _, SenderExt as _},
     fisheye, led,
-    logger::{DATADOG, NO_TAGS},
+    logger::{LogOnError, DATADOG, NO_TAGS},
     mcu,
     mcu::{main::IrLed, Mcu},
     monitor,
-    plans::biometric_capture::{EyeCapture, SelfCustodyCandidate},
+    plans::{
+        biometric_capture::{EyeCapture, SelfCustodyCandidate},
+        warmup,
+    },
     port, sound,
     sound::Melody,
 };
-use eyre::{bail, Result, WrapErr};
+use eyre::{bail, eyre, Result, WrapErr};
 use futures::{channel::mpsc, prelude::*};
 use nix::unistd::sync;
 use orb_macros::Broker;
 use orb_wld_data_id::SignupId;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     convert::Infallible,
+    mem,
     ops::RangeInclusive,
+    pin::Pin,
     process,
     sync::Arc,
     task::{Context, Poll},
     time::{Duration, Instant},
 };
-use tokio::{sync::Mutex, time::sleep};
+use tokio::{
+    sync::Mutex,
+    time::{self, sleep, Sleep},
+};
 
 // Give the IR camera enough time to fetch the last frame before external_trigger stops.
 // Give it time to take 1-2 frames.
 const IR_CAMERA_STOP_DELAY: Duration =
     Duration::from_millis(2 * 1000 / IR_CAMERA_FRAME_RATE as u64);
 
+// Bound the frames waiting for a net output to about two seconds of frames, in
+// case the nets drop outputs.
+const IR_NET_FRAMES_CAPACITY: usize = 2 * IR_CAMERA_FRAME_RATE as usize;
+const RGB_NET_FRAMES_CAPACITY: usize = 2 * RGB_FPS as usize;
+
+// Maximum duration of waiting for the frames in `Orb::grab_frame_pair`.
+const FRAME_PAIR_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Maximum duration of each self-test subsystem check.
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(5);
+const SELF_TEST_PULSE_DURATION: Duration = Duration::from_millis(500);
+
+// Retries of an MCU command while the MCU queue is full, see
+// `Orb::send_now_retry`.
+const MCU_SEND_RETRY_COUNT: usize = 3;
+const MCU_SEND_RETRY_DELAY: Duration = Duration::from_millis(2);
+
+/// Hook to persist in-flight data right before the Orb shuts down, see
+/// [`Orb::register_shutdown_hook`].
+pub type ShutdownHook =
+    Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send>;
+
+// Broker state to update once an MCU command is delivered.
+#[derive(Clone, Copy, Debug)]
+enum McuUpdate {
+    None,
+    IrLedDuration(u16),
+    IrFocus(i16),
+}
+
 /// Abstract Orb broker plan.
 #[allow(missing_docs)]
 pub trait Plan {
@@ -126,6 +167,14 @@ pub trait Plan {
         Ok(BrokerFlow::Continue)
     }
 
+    fn handle_iris(
+        &mut self,
+        _orb: &mut Orb,
+        _iris_template: &iris::EstimateOutput,
+    ) -> Result<BrokerFlow> {
+        Ok(BrokerFlow::Continue)
+    }
+
     fn handle_face_identifier(
         &mut self,
         _orb: &mut Orb,
@@ -151,6 +200,18 @@ pub trait Plan {
         Ok(BrokerFlow::Continue)
     }
 
+    fn handle_distance(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<distance::Agent>,
+    ) -> Result<BrokerFlow> {
+        Ok(BrokerFlow::Continue)
+    }
+
+    fn on_eye_lock_changed(&mut self, _orb: &mut Orb, _locked: bool) -> Result<BrokerFlow> {
+        Ok(BrokerFlow::Continue)
+    }
+
     fn poll_extra(&mut self, _orb: &mut Orb, _cx: &mut Context<'_>) -> Result<BrokerFlow> {
         Ok(BrokerFlow::Continue)
     }
@@ -195,6 +256,13 @@ pub struct Orb {
     pub sound: Box<dyn sound::Player>,
     pub led: Box<dyn led::Engine>,
     pub main_mcu: Box<dyn Mcu<mcu::Main>>,
+    mcu_monitor: Box<dyn Mcu<mcu::Main>>,
+    latest_gps: Option<(f64, f64)>,
+    main_mcu_temperature: Option<f64>,
+    liquid_lens_temperature: Option<f64>,
+    ambient_light: Option<f64>,
+    user_distance: Option<f64>,
+    front_unit_temperature: Option<f64>,
     pub net_monitor: Box<dyn monitor::net::Monitor>,
     pub cpu_monitor: Box<dyn monitor::cpu::Monitor>,
     pub dbus_conn: Option<zbus::Connection>,
@@ -203,26 +271,100 @@ pub struct Orb {
     pub ir_eye_save_fps_override: Option<f32>,
     pub ir_face_save_fps_override: Option<f32>,
     pub thermal_save_fps_override: Option<f32>,
+    pub rgb_frame_skip_cpu_load_threshold: f64,
+    pub rgb_frame_skip_ratio: u32,
+    /// Minimum interval between IR frames forwarded to IR-Net.
+    pub ir_net_min_interval: Duration,
+    /// Minimum interval between RGB frames forwarded to RGB-Net.
+    pub rgb_net_min_interval: Duration,
+    /// IR auto-exposure target means per IR LED wavelength, applied on each
+    /// wavelength switch. Wavelengths without an entry use the target mean
+    /// IR auto-exposure was started with, e.g. from the backend config.
+    pub ir_target_means: HashMap<IrLed, f64>,
     pub mirror_point: Option<mirror::Point>,
     pub mirror_offset: Option<mirror::Point>,
+    /// Duration without eye tracker outputs after which the eye lock is
+    /// considered lost.
+    pub eye_lock_timeout: Duration,
     pub trigger_shutdown_idle: bool,
+    /// Inactivity duration after which the LEDs switch to the standby
+    /// animation. `None` disables the standby mode.
+    pub standby_timeout: Option<Duration>,
     /// Used to control if RGB camera should forward frames to the RGB-Net model exclusively, so to some other models
     /// too. e.g. the Face Identifier model.
     pub only_rgb_net_frames: bool,
     ir_net_enabled: bool,
+    ir_net_warm: bool,
     ir_net_frames: VecDeque<(camera::ir::Frame, Instant)>,
     rgb_net_enabled: bool,
     rgb_net_frames: VecDeque<(camera::rgb::Frame, Instant)>,
+    rgb_frame_skip_counter: u32,
+    ir_net_last_timestamp: Option<Duration>,
+    rgb_net_last_timestamp: Option<Duration>,
+    last_activity: Instant,
+    standby: bool,
+    standby_timer: Pin<Box<Sleep>>,
+    eye_lock: bool,
+    eye_lock_timer: Pin<Box<Sleep>>,
+    upload_report: image_uploader::UploadReport,
+    shutdown_hooks: Vec<ShutdownHook>,
 
     state_tx: StateTx,
     calibration: Calibration,
+    calibration_staged: bool,
     target_left_eye: bool,
     ir_led_wavelength: IrLed,
     ir_led_duration: u16,
-    ir_auto_focus_use_rgb_net_estimate: bool,
+    ir_led_paused: bool,
+    mcu_pending: VecDeque<(mcu::main::Input, McuUpdate)>,
+    mcu_retry_timer: Pin<Box<Sleep>>,
+    mcu_retry_attempts: usize,
+    ir_target_mean: f64,
+    ir_exposure: (u16, i64),
+    ir_eye_gain_range: Option<RangeInclusive<i64>>,
+    ir_face_gain_range: Option<RangeInclusive<i64>>,
+    ir_focus: Option<i16>,
+    ir_auto_focus_strategy: ir_auto_focus::FocusStrategy,
+    ir_eye_camera_fake_port: Option<port::Outer<camera::ir::Sensor>>,
     rgb_camera_fake_port: Option<port::Outer<camera::rgb::Sensor>>,
 }
 
+/// Plan collecting the latest IR eye and RGB frames until it has both.
+#[derive(Default)]
+struct FramePairPlan {
+    ir_frame: Option<camera::ir::Frame>,
+    rgb_frame: Option<camera::rgb::Frame>,
+}
+
+/// Plan waiting for the first output of the self-tested subsystem.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SelfTestPlan {
+    IrEyeCamera,
+    IrFaceCamera,
+    RgbCamera,
+    ThermalCamera,
+    Mirror,
+}
+
+/// Result of a hardware self-test, see [`Orb::self_test`].
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    /// Checked subsystems in the order of checking.
+    pub checks: Vec<SelfTestCheck>,
+}
+
+/// Result of a self-test of one subsystem.
+#[derive(Debug)]
+pub struct SelfTestCheck {
+    /// Subsystem name.
+    pub subsystem: &'static str,
+    /// Failure description, or `None` if the check passed.
+    pub error: Option<String>,
+    /// `false` if the subsystem was exercised, but the Orb can't observe
+    /// whether it worked, e.g. the LED ring.
+    pub verified: bool,
+}
+
 /// [`Orb`] builder.
 #[derive(Default)]
 pub struct Builder {
@@ -233,6 +375,7 @@ pub struct Builder {
     net_monitor: Option<Box<dyn monitor::net::Monitor>>,
     cpu_monitor: Option<Box<dyn monitor::cpu::Monitor>>,
     enable_state_rx: bool,
+    ir_eye_camera_fake_port: Option<port::Outer<camera::ir::Sensor>>,
     rgb_camera_fake_port: Option<port::Outer<camera::rgb::Sensor>>,
 }
 
@@ -275,9 +418,12 @@ impl Builder {
             net_monitor,
             cpu_monitor,
             enable_state_rx,
+            ir_eye_camera_fake_port,
             rgb_camera_fake_port,
         } = self;
         let calibration = Calibration::load_or_default().await;
+        let main_mcu = main_mcu.unwrap_or_else(|| Box::<mcu::main::Fake>::default());
+        let mcu_monitor = main_mcu.clone();
         let (state_tx, state_rx) = if enable_state_rx {
             let (ir_eye_camera_state_tx, ir_eye_camera_state_rx) = mpsc::channel(1);
             let (ir_face_camera_state_tx, ir_face_camera_state_rx) = mpsc::channel(1);
@@ -309,33 +455,75 @@ impl Builder {
         let ir_eye_save_fps_override = config.lock().await.ir_eye_save_fps_override;
         let ir_face_save_fps_override = config.lock().await.ir_face_save_fps_override;
         let thermal_save_fps_override = config.lock().await.thermal_save_fps_override;
+        let rgb_frame_skip_cpu_load_threshold =
+            config.lock().await.rgb_frame_skip_cpu_load_threshold;
+        let rgb_frame_skip_ratio = config.lock().await.rgb_frame_skip_ratio;
+        let ir_net_min_interval = config.lock().await.ir_net_min_interval;
+        let rgb_net_min_interval = config.lock().await.rgb_net_min_interval;
+        let ir_target_mean = config.lock().await.biometric_capture.ir_target_mean;
         Ok(new_orb!(
             config,
             sound: sound.unwrap_or_else(|| Box::new(sound::Fake)),
             led: led.unwrap_or_else(|| Box::new(led::Fake)),
-            main_mcu: main_mcu.unwrap_or_else(|| Box::<mcu::main::Fake>::default()),
+            main_mcu,
+            mcu_monitor,
+            latest_gps: None,
+            main_mcu_temperature: None,
+            liquid_lens_temperature: None,
+            ambient_light: None,
+            user_distance: None,
+            front_unit_temperature: None,
             net_monitor: net_monitor.unwrap_or_else(|| Box::new(monitor::net::Fake)),
             cpu_monitor: cpu_monitor.unwrap_or_else(|| Box::new(monitor::cpu::Fake)),
             dbus_conn,
             calibration,
+            calibration_staged: false,
             target_left_eye: false,
             focus_matrix_code: false,
             ir_eye_save_fps_override,
             ir_face_save_fps_override,
             thermal_save_fps_override,
+            rgb_frame_skip_cpu_load_threshold,
+            rgb_frame_skip_ratio,
+            rgb_frame_skip_counter: 0,
+            ir_net_min_interval,
+            rgb_net_min_interval,
+            ir_net_last_timestamp: None,
+            rgb_net_last_timestamp: None,
+            ir_target_means: HashMap::new(),
             mirror_point: None,
+            eye_lock_timeout: EYE_LOCK_TIMEOUT,
             mirror_offset: None,
             trigger_shutdown_idle: false,
+            standby_timeout: Some(STANDBY_TIMEOUT),
+            last_activity: Instant::now(),
+            standby: false,
+            standby_timer: Box::pin(sleep(STANDBY_TIMEOUT)),
+            eye_lock: false,
+            eye_lock_timer: Box::pin(sleep(EYE_LOCK_TIMEOUT)),
+            upload_report: image_uploader::UploadReport::default(),
+            shutdown_hooks: Vec::new(),
             only_rgb_net_frames: true,
             ir_net_enabled: false,
+            ir_net_warm: false,
             ir_net_frames: VecDeque::new(),
             rgb_net_enabled: false,
             rgb_net_frames: VecDeque::new(),
             ir_led_wavelength: DEFAULT_IR_LED_WAVELENGTH,
             ir_led_duration: DEFAULT_IR_LED_DURATION,
-            ir_auto_focus_use_rgb_net_estimate: true,
+            ir_led_paused: false,
+            mcu_pending: VecDeque::new(),
+            mcu_retry_timer: Box::pin(sleep(Duration::ZERO)),
+            mcu_retry_attempts: 0,
+            ir_target_mean,
+            ir_exposure: (DEFAULT_IR_LED_DURATION, IR_CAMERA_DEFAULT_GAIN),
+            ir_eye_gain_range: None,
+            ir_face_gain_range: None,
+            ir_focus: None,
+            ir_auto_focus_strategy: ir_auto_focus::FocusStrategy::default(),
             state_tx,
             state_rx,
+            ir_eye_camera_fake_port,
             rgb_camera_fake_port,
         ))
     }
@@ -354,7 +542,8 @@ impl Builder {
         self
     }
 
-    /// Sets the LED engine.
+    /// Sets the LED engine. Defaults to the silent [`led::Fake`], tests can
+    /// pass a [`led::Recording`] to check the triggered animations.
     #[must_use]
     pub fn led(mut self, led: Box<dyn led::Engine>) -> Self {
         self.led = Some(led);
@@ -389,6 +578,16 @@ impl Builder {
         self
     }
 
+    /// Sets `ir_eye_camera_fake_port`.
+    #[must_use]
+    pub fn ir_eye_camera_fake_port(
+        mut self,
+        ir_eye_camera_fake_port: port::Outer<camera::ir::Sensor>,
+    ) -> Self {
+        self.ir_eye_camera_fake_port = Some(ir_eye_camera_fake_port);
+        self
+    }
+
     /// Sets `rgb_camera_fake_port`.
     #[must_use]
     pub fn rgb_camera_fake_port(
@@ -432,35 +631,144 @@ impl Orb {
         Ok(())
     }
 
-    /// Sets active IR LED wavelength.
+    /// Sets active IR LED wavelength. If IR auto-exposure is running, also
+    /// applies the wavelength target mean from [`Orb::ir_target_means`].
     pub async fn set_ir_wavelength(&mut self, ir_led_wavelength: IrLed) -> Result<()> {
         self.main_mcu.send(mcu::main::Input::IrLed(ir_led_wavelength)).await?;
         self.ir_led_wavelength = ir_led_wavelength;
         let exposure_range = self.exposure_range();
+        let target_mean =
+            self.ir_target_means.get(&ir_led_wavelength).copied().unwrap_or(self.ir_target_mean);
         if let Some(ir_auto_exposure) = self.ir_auto_exposure.enabled() {
             ir_auto_exposure
                 .send_unjam(port::Input::new(ir_auto_exposure::Input::SetExposureRange(
                     exposure_range,
                 )))
                 .await?;
+            ir_auto_exposure
+                .send_unjam(port::Input::new(ir_auto_exposure::Input::SetTargetMean(target_mean)))
+                .await?;
         }
         Ok(())
     }
 
-    /// Sets active IR LED PWM duration.
+    /// Sets active IR LED PWM duration. If the MCU queue is full, the command
+    /// is retried by the broker, and the tracked duration changes only once
+    /// the command is delivered.
     pub fn set_ir_duration(&mut self, ir_led_duration: u16) -> Result<()> {
-        match self.ir_led_wavelength {
-            IrLed::L740 => {
-                self.main_mcu.send_now(mcu::main::Input::IrLedDuration740nm(ir_led_duration))?;
+        self.send_now_retry(
+            self.ir_duration_input(ir_led_duration),
+            McuUpdate::IrLedDuration(ir_led_duration),
+        )
+    }
+
+    /// Gradually changes active IR LED PWM duration from the current value to
+    /// `target` over `steps` MCU messages, to avoid a perceivable brightness
+    /// step.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub async fn set_ir_duration_ramped(&mut self, target: u16, steps: usize) -> Result<()> {
+        let steps = steps.max(1) as i64;
+        let start = i64::from(self.ir_led_duration);
+        let delta = i64::from(target) - start;
+        let range = self.exposure_range();
+        for step in 1..=steps {
+            let ir_led_duration =
+                ((start + delta * step / steps) as u16).clamp(*range.start(), *range.end());
+            self.main_mcu.send(self.ir_duration_input(ir_led_duration)).await?;
+            self.ir_led_duration = ir_led_duration;
+        }
+        Ok(())
+    }
+
+    /// Pauses IR LED firing. The MCU receives a zero PWM duration until
+    /// [`Orb::resume_ir_led`] is called, while the requested duration is still
+    /// tracked, e.g. from IR auto-exposure.
+    pub fn pause_ir_led(&mut self) -> Result<()> {
+        self.ir_led_paused = true;
+        self.send_now_retry(self.ir_duration_input(self.ir_led_duration), McuUpdate::None)
+    }
+
+    /// Resumes IR LED firing with the latest requested PWM duration.
+    pub fn resume_ir_led(&mut self) -> Result<()> {
+        self.ir_led_paused = false;
+        self.send_now_retry(self.ir_duration_input(self.ir_led_duration), McuUpdate::None)
+    }
+
+    /// Returns `true` if IR LED firing is paused.
+    #[must_use]
+    pub fn ir_led_paused(&self) -> bool {
+        self.ir_led_paused
+    }
+
+    // Like `Mcu::send_now`, but if the MCU queue is full, leaves the command
+    // to be retried by `poll_mcu_retry`. `update` is applied once the command
+    // is delivered. Commands queued behind a pending one keep their order.
+    fn send_now_retry(&mut self, input: mcu::main::Input, update: McuUpdate) -> Result<()> {
+        if let Some(log) = self.main_mcu.log_mut() {
+            <mcu::Main as mcu::Interface>::log_input(log, &input);
+        }
+        if !self.mcu_pending.is_empty() {
+            self.mcu_pending.push_back((input, update));
+            return Ok(());
+        }
+        match try_send(self.main_mcu.tx_mut(), (input, None))? {
+            None => self.apply_mcu_update(update),
+            Some((input, _)) => {
+                tracing::debug!("MCU queue is full, retrying command: {input:?}");
+                self.mcu_pending.push_back((input, update));
+                self.mcu_retry_attempts = 0;
+                self.mcu_retry_timer.as_mut().reset(time::Instant::now() + MCU_SEND_RETRY_DELAY);
             }
-            _ => {
-                self.main_mcu.send_now(mcu::main::Input::IrLedDuration(ir_led_duration))?;
+        }
+        Ok(())
+    }
+
+    // Retries the MCU commands left by `send_now_retry` in order. Fails if a
+    // command couldn't be delivered after `MCU_SEND_RETRY_COUNT` retries.
+    fn poll_mcu_retry(&mut self, cx: &mut Context<'_>) -> Result<()> {
+        while !self.mcu_pending.is_empty() {
+            if self.mcu_retry_timer.as_mut().poll(cx).is_pending() {
+                return Ok(());
+            }
+            let (input, update) = self.mcu_pending.pop_front().unwrap();
+            match try_send(self.main_mcu.tx_mut(), (input, None))? {
+                None => {
+                    self.apply_mcu_update(update);
+                    self.mcu_retry_attempts = 0;
+                }
+                Some((input, _)) => {
+                    self.mcu_retry_attempts += 1;
+                    if self.mcu_retry_attempts >= MCU_SEND_RETRY_COUNT {
+                        self.mcu_pending.clear();
+                        DATADOG.incr("orb.main.count.global.mcu_command_dropped", NO_TAGS).or_log();
+                        bail!("MCU queue stayed full, dropping command: {input:?}");
+                    }
+                    self.mcu_pending.push_front((input, update));
+                    self.mcu_retry_timer
+                        .as_mut()
+                        .reset(time::Instant::now() + MCU_SEND_RETRY_DELAY);
+                }
             }
         }
-        self.ir_led_duration = ir_led_duration;
         Ok(())
     }
 
+    fn apply_mcu_update(&mut self, update: McuUpdate) {
+        match update {
+            McuUpdate::None => {}
+            McuUpdate::IrLedDuration(ir_led_duration) => self.ir_led_duration = ir_led_duration,
+            McuUpdate::IrFocus(ir_focus) => self.ir_focus = Some(ir_focus),
+        }
+    }
+
+    fn ir_duration_input(&self, ir_led_duration: u16) -> mcu::main::Input {
+        let ir_led_duration = if self.ir_led_paused { 0 } else { ir_led_duration };
+        match self.ir_led_wavelength {
+            IrLed::L740 => mcu::main::Input::IrLedDuration740nm(ir_led_duration),
+            _ => mcu::main::Input::IrLedDuration(ir_led_duration),
+        }
+    }
+
     /// Returns `true` if the Orb currently targets the left eye.
     #[must_use]
     pub fn target_left_eye(&self) -> bool {
@@ -479,6 +787,222 @@ impl Orb {
         Ok(())
     }
 
+    /// Returns the last IR `(exposure, gain)` applied by the IR auto-exposure,
+    /// or the defaults if the auto-exposure hasn't run yet.
+    #[must_use]
+    pub fn current_ir_exposure(&self) -> (u16, i64) {
+        self.ir_exposure
+    }
+
+    /// Returns the last liquid lens focus value commanded by the IR auto-focus,
+    /// or `None` if the auto-focus is stopped.
+    #[must_use]
+    pub fn current_focus(&self) -> Option<i16> {
+        self.ir_focus
+    }
+
+    /// Returns the IR camera gain in effect for the current frames.
+    #[allow(clippy::cast_precision_loss)]
+    fn ir_gain(&self) -> f64 {
+        self.ir_exposure.1 as f64
+    }
+
+    /// Returns the user distance estimated from the latest RGB-Net estimate, or
+    /// `None` if no face was detected.
+    #[must_use]
+    pub fn user_distance(&self) -> Option<f64> {
+        self.user_distance
+    }
+
+    /// Returns the front-unit temperature in degrees Celsius estimated from the
+    /// latest thermal camera frame.
+    #[must_use]
+    pub fn front_unit_temperature(&self) -> Option<f64> {
+        self.front_unit_temperature
+    }
+
+    /// Returns `true` if the dbus connection is established.
+    #[must_use]
+    pub fn dbus_available(&self) -> bool {
+        self.dbus_conn.is_some()
+    }
+
+    /// Retries to establish the dbus connection if it failed before, e.g. due
+    /// to a race with the session bus startup. Returns `true` if the
+    /// connection is available afterwards.
+    pub async fn retry_dbus(&mut self) -> bool {
+        if self.dbus_conn.is_none() {
+            tracing::info!("Retrying to initialize dbus connection");
+            match init_dbus().await {
+                Ok(dbus_conn) => {
+                    tracing::info!("dbus connection initialized");
+                    self.dbus_conn = Some(dbus_conn);
+                }
+                Err(err) => {
+                    tracing::error!(
+                        "failed to initialize dbus connection, leaving disabled; error: {err}"
+                    );
+                }
+            }
+        }
+        self.dbus_available()
+    }
+
+    /// Returns the most recent GPS `(latitude, longitude)` fix.
+    #[must_use]
+    pub fn latest_gps(&self) -> Option<(f64, f64)> {
+        self.latest_gps
+    }
+
+    /// Returns the latest main MCU temperature in degrees Celsius.
+    #[must_use]
+    pub fn main_mcu_temperature(&self) -> Option<f64> {
+        self.main_mcu_temperature
+    }
+
+    /// Returns the latest liquid lens temperature in degrees Celsius.
+    #[must_use]
+    pub fn liquid_lens_temperature(&self) -> Option<f64> {
+        self.liquid_lens_temperature
+    }
+
+    /// Returns the latest ambient light level in lux, or `None` if the board
+    /// has no ambient light sensor.
+    #[must_use]
+    pub fn ambient_light(&self) -> Option<f64> {
+        self.ambient_light
+    }
+
+    // Tracks GPS fixes, board temperatures and the ambient light on a dedicated
+    // subscription, so the plans still receive all main MCU messages.
+    fn poll_main_mcu(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Some(output)) = self.mcu_monitor.rx_mut().poll_next_unpin(cx) {
+            match output {
+                Ok(mcu::main::Output::Gps(message)) => {
+                    if let Some(fix) = mcu::main::gps_fix(&message) {
+                        self.latest_gps = Some(fix);
+                    }
+                }
+                Ok(mcu::main::Output::Temperature(temperature)) => {
+                    let value = Some(f64::from(temperature.temperature_c));
+                    if temperature.source
+                        == orb_messages::mcu_main::temperature::TemperatureSource::MainMcu as i32
+                    {
+                        self.main_mcu_temperature = value;
+                    } else if temperature.source
+                        == orb_messages::mcu_main::temperature::TemperatureSource::LiquidLens as i32
+                    {
+                        self.liquid_lens_temperature = value;
+                    }
+                }
+                Ok(mcu::main::Output::AmbientLight(als)) => {
+                    self.ambient_light = Some(f64::from(als.ambient_light_lux));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Records user-facing activity, e.g. a button press, a scanned QR code,
+    /// or an acquired eye lock. Wakes the LEDs up if they are in standby mode.
+    pub fn mark_activity(&mut self) {
+        self.last_activity = Instant::now();
+        if self.standby {
+            self.standby = false;
+            self.led.wake();
+        }
+    }
+
+    /// Switches the LEDs to standby mode once there was no activity for
+    /// [`Self::standby_timeout`].
+    fn poll_standby(&mut self, cx: &mut Context<'_>) {
+        let Some(timeout) = self.standby_timeout else {
+            return;
+        };
+        if self.standby {
+            return;
+        }
+        let deadline = self.last_activity + timeout;
+        if Instant::now() >= deadline {
+            tracing::info!("No activity for {timeout:?}, entering standby");
+            self.standby = true;
+            self.led.standby();
+            return;
+        }
+        // Re-arm the timer, as the activity might have happened since it was
+        // last set.
+        self.standby_timer.as_mut().reset(time::Instant::from_std(deadline));
+        let _ = self.standby_timer.as_mut().poll(cx);
+    }
+
+    /// Returns `true` if the eye tracker currently has a lock on the eye.
+    #[must_use]
+    pub fn eye_lock(&self) -> bool {
+        self.eye_lock
+    }
+
+    // Notifies the plan about an eye lock transition.
+    fn set_eye_lock(&mut self, plan: &mut dyn Plan, locked: bool) -> Result<BrokerFlow> {
+        self.eye_lock = locked;
+        if locked {
+            self.mark_activity();
+        }
+        let state = if locked { "acquired" } else { "lost" };
+        tracing::debug!("Eye tracker lock {state}");
+        DATADOG.incr("orb.main.count.global.eye_tracker_lock", [format!("state:{state}")]).or_log();
+        plan.on_eye_lock_changed(self, locked)
+    }
+
+    /// Reports the eye lock as lost once the eye tracker had no outputs for
+    /// [`Self::eye_lock_timeout`].
+    fn poll_eye_lock(&mut self, plan: &mut dyn Plan, cx: &mut Context<'_>) -> Result<BrokerFlow> {
+        if self.eye_lock && self.eye_lock_timer.as_mut().poll(cx).is_ready() {
+            return self.set_eye_lock(plan, false);
+        }
+        Ok(BrokerFlow::Continue)
+    }
+
+    /// Waits up to `timeout` for the queued image uploads to finish. Returns the
+    /// latest known upload counters if the timeout expires, or if the image
+    /// uploader is not enabled.
+    pub async fn flush_uploads(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<image_uploader::UploadReport> {
+        let Some(image_uploader) = self.image_uploader.enabled() else {
+            return Ok(self.upload_report);
+        };
+        if let Ok(report) = time::timeout(timeout, image_uploader.flush()).await {
+            self.update_upload_report(report?);
+        } else {
+            while let Some(output) = image_uploader.try_recv()? {
+                self.update_upload_report(output.value);
+            }
+            tracing::warn!(
+                "Image uploads didn't finish in {timeout:?}, {} remaining",
+                self.upload_report.remaining
+            );
+        }
+        Ok(self.upload_report)
+    }
+
+    /// Collects the WiFi status from the network monitor. Returns the default
+    /// status if there is no WiFi connection information.
+    pub fn collect_wifi(&mut self) -> status::Wifi {
+        let Some(snapshot) = self.net_monitor.wifi_quality() else {
+            return status::Wifi::default();
+        };
+        status::Wifi {
+            ssid: snapshot.ssid,
+            quality: status::WifiQuality {
+                bit_rate: snapshot.bit_rate.unwrap_or_default(),
+                link_quality: snapshot.link_quality.unwrap_or_default(),
+                signal_level: snapshot.signal_level,
+                noise_level: snapshot.noise_level.unwrap_or_default(),
+            },
+        }
+    }
+
     /// Returns a reference to the mirror calibration.
     #[must_use]
     pub fn calibration(&self) -> &Calibration {
@@ -486,8 +1010,15 @@ impl Orb {
     }
 
     /// Updates the mirror calibration.
+    ///
+    /// Rejects calibrations with mirror offsets out of the mechanical range.
     pub async fn recalibrate(&mut self, calibration: Calibration) -> Result<()> {
+        if let Err(err) = calibration.validate() {
+            tracing::error!("Rejecting invalid calibration {calibration:?}: {err}");
+            return Err(err.into());
+        }
         self.calibration = calibration;
+        self.calibration_staged = false;
         self.mirror
             .enabled()
             .unwrap()
@@ -495,17 +1026,34 @@ impl Orb {
             .await
     }
 
+    /// Stages a new mirror calibration without waiting for the mirror agent.
+    ///
+    /// The staged calibration only takes effect on the next mirror command,
+    /// when it's sent to the mirror agent and the current mirror point is
+    /// re-applied with it. Use [`Self::recalibrate`] to apply the calibration
+    /// synchronously.
+    ///
+    /// Rejects calibrations with mirror offsets out of the mechanical range.
+    pub fn stage_calibration(&mut self, calibration: Calibration) -> Result<()> {
+        if let Err(err) = calibration.validate() {
+            tracing::error!("Rejecting invalid calibration {calibration:?}: {err}");
+            return Err(err.into());
+        }
+        self.calibration = calibration;
+        self.calibration_staged = true;
+        Ok(())
+    }
+
     /// Starts eye IR camera.
     pub async fn start_ir_eye_camera(&mut self) -> Result<()> {
         self.main_mcu.send(mcu::main::Input::TriggeringIrEyeCamera(true)).await?;
         self.main_mcu.send(mcu::main::Input::FrameRate(IR_CAMERA_FRAME_RATE)).await?;
         self.enable_ir_eye_camera()?;
         self.enable_ir_led().await?;
-        self.ir_eye_camera
-            .enabled()
-            .unwrap()
-            .send(port::Input::new(camera::ir::Command::Start))
-            .await?;
+        let ir_eye_camera = self.ir_eye_camera.enabled().unwrap();
+        ir_eye_camera.send(port::Input::new(camera::ir::Command::Start)).await?;
+        self.ir_eye_gain_range = Some(ir_eye_camera.gain_range().await?);
+        self.send_ir_gain_range().await?;
         Ok(())
     }
 
@@ -532,11 +1080,10 @@ impl Orb {
         self.main_mcu.send(mcu::main::Input::FrameRate(IR_CAMERA_FRAME_RATE)).await?;
         self.enable_ir_face_camera()?;
         self.enable_ir_led().await?;
-        self.ir_face_camera
-            .enabled()
-            .unwrap()
-            .send(port::Input::new(camera::ir::Command::Start))
-            .await?;
+        let ir_face_camera = self.ir_face_camera.enabled().unwrap();
+        ir_face_camera.send(port::Input::new(camera::ir::Command::Start)).await?;
+        self.ir_face_gain_range = Some(ir_face_camera.gain_range().await?);
+        self.send_ir_gain_range().await?;
         Ok(())
     }
 
@@ -599,6 +1146,41 @@ impl Orb {
         Ok(())
     }
 
+    /// Grabs the next pair of IR eye and RGB frames. The cameras are started if
+    /// needed and stopped afterwards if they were not running before, also if
+    /// the grab fails. Fails if the frames don't arrive within a timeout. No
+    /// nets or mirror agents are enabled for this.
+    pub async fn grab_frame_pair(&mut self) -> Result<(camera::ir::Frame, camera::rgb::Frame)> {
+        let ir_eye_camera_started = self.ir_eye_camera.is_enabled();
+        let rgb_camera_started = self.rgb_camera.is_enabled();
+        let mut plan = FramePairPlan::default();
+        let result = self.grab_frame_pair_run(&mut plan).await;
+        let mut teardown = Ok(());
+        if !rgb_camera_started && self.rgb_camera.is_enabled() {
+            teardown = teardown.and(self.stop_rgb_camera().await);
+        }
+        if !ir_eye_camera_started && self.ir_eye_camera.is_enabled() {
+            teardown = teardown.and(self.stop_ir_eye_camera().await.map(drop));
+        }
+        result.and(teardown)?;
+        let FramePairPlan { ir_frame: Some(ir_frame), rgb_frame: Some(rgb_frame) } = plan else {
+            bail!("frame pair is incomplete");
+        };
+        Ok((ir_frame, rgb_frame))
+    }
+
+    async fn grab_frame_pair_run(&mut self, plan: &mut FramePairPlan) -> Result<()> {
+        if !self.ir_eye_camera.is_enabled() {
+            self.start_ir_eye_camera().await?;
+        }
+        if !self.rgb_camera.is_enabled() {
+            self.start_rgb_camera().await?;
+        }
+        time::timeout(FRAME_PAIR_TIMEOUT, self.run(plan))
+            .await
+            .map_err(|_| eyre!("no frame pair in {FRAME_PAIR_TIMEOUT:?}"))?
+    }
+
     /// Starts the thermal camera
     ///
     /// # Panics
@@ -632,6 +1214,7 @@ impl Orb {
     /// Starts IR auto-exposure agent.
     pub async fn start_ir_auto_exposure(&mut self, target_mean: f64) -> Result<()> {
         self.enable_ir_auto_exposure()?;
+        self.ir_target_mean = target_mean;
         let exposure_range = self.exposure_range();
         let ir_auto_exposure = self.ir_auto_exposure.enabled().unwrap();
         ir_auto_exposure
@@ -640,6 +1223,24 @@ impl Orb {
         ir_auto_exposure
             .send_unjam(port::Input::new(ir_auto_exposure::Input::SetExposureRange(exposure_range)))
             .await?;
+        self.send_ir_gain_range().await?;
+        Ok(())
+    }
+
+    // Feeds the gain range supported by all the started IR cameras to the IR
+    // auto-exposure agent, if it's running.
+    async fn send_ir_gain_range(&mut self) -> Result<()> {
+        let gain_range = [&self.ir_eye_gain_range, &self.ir_face_gain_range]
+            .into_iter()
+            .flatten()
+            .fold(i64::MIN..=i64::MAX, |acc, range| {
+                *acc.start().max(range.start())..=*acc.end().min(range.end())
+            });
+        if let Some(ir_auto_exposure) = self.ir_auto_exposure.enabled() {
+            ir_auto_exposure
+                .send_unjam(port::Input::new(ir_auto_exposure::Input::SetGainRange(gain_range)))
+                .await?;
+        }
         Ok(())
     }
 
@@ -658,18 +1259,21 @@ impl Orb {
         Ok(())
     }
 
-    /// Starts IR auto-focus agent.
+    /// Starts IR auto-focus agent with the given focus search `strategy`.
     pub async fn start_ir_auto_focus(
         &mut self,
         min_sharpness: f64,
-        use_rgb_estimate: bool,
+        strategy: ir_auto_focus::FocusStrategy,
     ) -> Result<()> {
-        self.ir_auto_focus_use_rgb_net_estimate = use_rgb_estimate;
+        self.ir_auto_focus_strategy = strategy;
         self.enable_ir_auto_focus()?;
         if let Some(ir_auto_focus) = self.ir_auto_focus.enabled() {
             ir_auto_focus
                 .send_unjam(port::Input::new(ir_auto_focus::Input::SetMinSharpness(min_sharpness)))
                 .await?;
+            ir_auto_focus
+                .send_unjam(port::Input::new(ir_auto_focus::Input::SetStrategy(strategy)))
+                .await?;
         }
         Ok(())
     }
@@ -697,6 +1301,7 @@ impl Orb {
             .send_unjam(port::Input::new(ir_auto_focus::Input::Reset))
             .await?;
         self.main_mcu.send(mcu::main::Input::LiquidLens(None)).await?;
+        self.ir_focus = None;
         self.disable_ir_auto_focus();
         Ok(())
     }
@@ -713,6 +1318,7 @@ impl Orb {
             .send_unjam(port::Input::new(eye_tracker::Input::Reset))
             .await?;
         self.disable_eye_tracker();
+        self.eye_lock = false;
         Ok(self.mirror_point.take())
     }
 
@@ -744,6 +1350,9 @@ impl Orb {
 
     /// Stops the image saver agent.
     ///
+    /// Fails if any of the saved identification images is missing or
+    /// truncated on disk.
+    ///
     /// # Panics
     ///
     /// If the agent is not enabled.
@@ -755,10 +1364,14 @@ impl Orb {
         image_notary.send(port::Input::new(image_notary::Input::FinalizeSignup)).await?;
         let mut identification_images = None;
         if let Some((left, right, self_custody_candidate)) = eyes {
-            identification_images = image_notary
+            if let Some((images, saved_images)) = image_notary
                 .save_identification_images(left, right, self_custody_candidate)
                 .await
-                .unwrap_or_default();
+                .unwrap_or_default()
+            {
+                image_notary::verify_saved_images(&saved_images).await?;
+                identification_images = Some(images);
+            }
         }
         let image_notary_log = image_notary.take_log().await?;
         self.disable_image_notary();
@@ -772,6 +1385,28 @@ impl Orb {
         Ok(())
     }
 
+    /// Enables IR-Net model and waits until it responds to a warmup request,
+    /// so the first frames are not wasted on a cold model. Callers managing the
+    /// warmup themselves should use [`Orb::enable_ir_net`] instead.
+    ///
+    /// Returns right away if IR-Net already warmed up since the model agent
+    /// was started. If the warmup doesn't complete within `timeout`, continues
+    /// with a cold model.
+    pub async fn enable_ir_net_awaiting_warmup(&mut self, timeout: Duration) -> Result<()> {
+        let warm = self.ir_net_warm && self.mega_agent_one.is_enabled();
+        self.enable_ir_net().await?;
+        if warm {
+            return Ok(());
+        }
+        let t = Instant::now();
+        if warmup::Plan::run_ir_net(self, timeout).await? {
+            tracing::info!("IR-Net warmed up in {:?}", t.elapsed());
+        } else {
+            tracing::warn!("IR-Net warmup didn't complete in {timeout:?}, continuing cold");
+        }
+        Ok(())
+    }
+
     /// Enables RGB-Net model.
     pub async fn enable_rgb_net(&mut self, only_rgb_net_frames: bool) -> Result<()> {
         self.enable_mega_agent_two().await?;
@@ -828,46 +1463,67 @@ impl Orb {
         Ok(())
     }
 
-    fn send_ir_net_estimate(&mut self, input: ir_net::Input) -> Result<()> {
-        let frame = if let ir_net::Input::Estimate { frame, .. } = &input {
-            frame.clone()
-        } else {
+    // Under high CPU load, uniformly skips every Nth RGB frame before it gets
+    // cloned and queued for RGB-Net.
+    fn skip_rgb_net_frame(&mut self) -> Result<bool> {
+        let overloaded = self
+            .cpu_monitor
+            .last_report()?
+            .is_some_and(|report| report.cpu_load > self.rgb_frame_skip_cpu_load_threshold);
+        if !overloaded || self.rgb_frame_skip_ratio == 0 {
+            self.rgb_frame_skip_counter = 0;
+            return Ok(false);
+        }
+        self.rgb_frame_skip_counter = (self.rgb_frame_skip_counter + 1) % self.rgb_frame_skip_ratio;
+        Ok(self.rgb_frame_skip_counter == 0)
+    }
+
+    fn send_ir_net_estimate(
+        &mut self,
+        output: &port::Output<camera::ir::Sensor>,
+        input: ir_net::Input,
+    ) -> Result<()> {
+        let ir_net::Input::Estimate { frame, .. } = &input else {
             panic!("not an Input::Estimate");
         };
-        let input = port::Input::new(mega_agent_one::Input::IRNet(input));
-        let source_ts = input.source_ts;
-        match self.mega_agent_one.enabled().unwrap().tx.try_send(input) {
-            Ok(()) => self.ir_net_frames.push_back((frame, source_ts)),
-            Err(err) if err.is_full() => {}
-            Err(err) => bail!("message pass failed: {}", err),
-        }
-        Ok(())
+        let frame = frame.clone();
+        send_net_frame(
+            &mut self.mega_agent_one.enabled().unwrap().tx,
+            &mut self.ir_net_frames,
+            output.chain(mega_agent_one::Input::IRNet(input)),
+            frame,
+            IR_NET_FRAMES_CAPACITY,
+            "ir_net",
+        )
     }
 
-    fn send_rgb_net_estimate(&mut self, frame: &camera::rgb::Frame) -> Result<()> {
-        let input = port::Input::new(mega_agent_two::Input::RgbNet(rgb_net::Input::Estimate {
-            frame: frame.clone(),
-        }));
-        let source_ts = input.source_ts;
-        match self.mega_agent_two.enabled().unwrap().tx.try_send(input) {
-            Ok(()) => self.rgb_net_frames.push_back((frame.clone(), source_ts)),
-            Err(err) if err.is_full() => {}
-            Err(err) => bail!("message pass failed: {}", err),
-        }
-        Ok(())
+    fn send_rgb_net_estimate(&mut self, output: &port::Output<camera::rgb::Sensor>) -> Result<()> {
+        send_net_frame(
+            &mut self.mega_agent_two.enabled().unwrap().tx,
+            &mut self.rgb_net_frames,
+            output.chain(mega_agent_two::Input::RgbNet(rgb_net::Input::Estimate {
+                frame: output.value.clone(),
+            })),
+            output.value.clone(),
+            RGB_NET_FRAMES_CAPACITY,
+            "rgb_net",
+        )
     }
 
-    fn send_rgb_net_face_identifier_input(&mut self, frame: &camera::rgb::Frame) -> Result<()> {
-        let input = port::Input::new(mega_agent_two::Input::FusionRgbNetFaceIdentifier {
-            frame: frame.clone(),
-        });
-        let source_ts = input.source_ts;
-        match self.mega_agent_two.enabled().unwrap().tx.try_send(input) {
-            Ok(()) => self.rgb_net_frames.push_back((frame.clone(), source_ts)),
-            Err(err) if err.is_full() => {}
-            Err(err) => bail!("message pass failed: {}", err),
-        }
-        Ok(())
+    fn send_rgb_net_face_identifier_input(
+        &mut self,
+        output: &port::Output<camera::rgb::Sensor>,
+    ) -> Result<()> {
+        send_net_frame(
+            &mut self.mega_agent_two.enabled().unwrap().tx,
+            &mut self.rgb_net_frames,
+            output.chain(mega_agent_two::Input::FusionRgbNetFaceIdentifier {
+                frame: output.value.clone(),
+            }),
+            output.value.clone(),
+            RGB_NET_FRAMES_CAPACITY,
+            "rgb_net",
+        )
     }
 
     async fn init_mega_agent_one(&mut self) -> Result<mega_agent_one::MegaAgentOne> {
@@ -879,7 +1535,10 @@ impl Orb {
     }
 
     fn init_ir_eye_camera(&mut self) -> camera::ir::Sensor {
-        camera::ir::Sensor::eye(self.state_tx.ir_eye_camera_state.take())
+        camera::ir::Sensor::eye(
+            self.state_tx.ir_eye_camera_state.take(),
+            self.ir_eye_camera_fake_port.take(),
+        )
     }
 
     fn init_ir_face_camera(&mut self) -> camera::ir::Sensor {
@@ -915,11 +1574,18 @@ impl Orb {
                 .send_now(output.chain(ir_auto_exposure::Input::Frame(output.value.clone())))?;
         }
         if self.is_ir_net_enabled() {
-            self.send_ir_net_estimate(ir_net::Input::Estimate {
-                frame: output.value.clone(),
-                target_left_eye: self.target_left_eye,
-                focus_matrix_code: self.focus_matrix_code,
-            })?;
+            if !throttle_net_frame(
+                &mut self.ir_net_last_timestamp,
+                camera::Frame::timestamp(&output.value),
+                self.ir_net_min_interval,
+            ) {
+                let input = ir_net::Input::Estimate {
+                    frame: output.value.clone(),
+                    target_left_eye: self.target_left_eye,
+                    focus_matrix_code: self.focus_matrix_code,
+                };
+                self.send_ir_net_estimate(&output, input)?;
+            }
         } else {
             if let Some(ir_auto_focus) = self.ir_auto_focus.enabled() {
                 // forward frame to IR auto focus if IR net is not enabled for internal sharpness calculation.
@@ -934,6 +1600,8 @@ impl Orb {
                         frame: output.value.clone(),
                         wavelength: self.ir_led_wavelength,
                         target_left_eye: self.target_left_eye,
+                        exposure: self.ir_led_duration,
+                        gain: self.ir_gain(),
                         fps_override: self.ir_eye_save_fps_override,
                         log_metadata_always: true,
                     },
@@ -969,11 +1637,18 @@ impl Orb {
         if let Some(qr_code) = self.qr_code.enabled() {
             qr_code.send_now(output.chain(qr_code::Input::Frame(output.value.clone())))?;
         }
-        if self.is_rgb_net_enabled() {
+        if self.is_rgb_net_enabled()
+            && !self.skip_rgb_net_frame()?
+            && !throttle_net_frame(
+                &mut self.rgb_net_last_timestamp,
+                camera::Frame::timestamp(&output.value),
+                self.rgb_net_min_interval,
+            )
+        {
             if self.only_rgb_net_frames {
-                self.send_rgb_net_estimate(&output.value)?;
+                self.send_rgb_net_estimate(&output)?;
             } else {
-                self.send_rgb_net_face_identifier_input(&output.value)?;
+                self.send_rgb_net_face_identifier_input(&output)?;
             }
         }
         plan.handle_rgb_camera(self, output)
@@ -984,13 +1659,15 @@ impl Orb {
         output: &port::Output<T>,
         estimate: &rgb_net::EstimateOutput,
     ) -> Result<()> {
+        self.user_distance =
+            estimate.primary().map(rgb_net::EstimatePredictionOutput::user_distance);
         if let Some(eye_tracker) = self.eye_tracker.enabled() {
             if let Some(input) = eye_tracker::Input::track(self.target_left_eye, estimate) {
                 eye_tracker.send_now(output.chain(input))?;
             }
         }
         if let Some(ir_auto_focus) = self.ir_auto_focus.enabled() {
-            if self.ir_auto_focus_use_rgb_net_estimate {
+            if self.ir_auto_focus_strategy == ir_auto_focus::FocusStrategy::RgbDistanceSeed {
                 ir_auto_focus.send_now(output.chain(estimate.into()))?;
             }
         }
@@ -1007,15 +1684,9 @@ impl Orb {
     ) -> Result<BrokerFlow> {
         macro_rules! restore_frame {
             () => {
-                loop {
-                    if let Some((frame, source_ts)) = self.rgb_net_frames.pop_front() {
-                        if source_ts == output.source_ts {
-                            break frame;
-                        }
-                    } else {
-                        tracing::error!("RGB-Net frame not found");
-                        return Ok(BrokerFlow::Continue);
-                    }
+                match restore_net_frame(&mut self.rgb_net_frames, output.source_ts, "rgb_net") {
+                    Some(frame) => frame,
+                    None => return Ok(BrokerFlow::Continue),
                 }
             };
         }
@@ -1067,14 +1738,9 @@ impl Orb {
     ) -> Result<BrokerFlow> {
         macro_rules! restore_frame {
             () => {
-                loop {
-                    if let Some((frame, source_ts)) = self.rgb_net_frames.pop_front() {
-                        if source_ts == source_ts {
-                            break frame;
-                        }
-                    } else {
-                        unreachable!("Fusion RGB-Net and Face Identifier frame not found");
-                    }
+                match restore_net_frame(&mut self.rgb_net_frames, source_ts, "fusion_rgb_net_fi") {
+                    Some(frame) => frame,
+                    None => return Ok(BrokerFlow::Continue),
                 }
             };
         }
@@ -1115,6 +1781,9 @@ impl Orb {
         plan: &mut dyn Plan,
         output: port::Output<camera::thermal::Sensor>,
     ) -> Result<BrokerFlow> {
+        if let Some(temperature) = output.value.roi_max_temperature() {
+            self.front_unit_temperature = Some(temperature);
+        }
         if let Some(image_notary) = self.image_notary.enabled() {
             image_notary.send_now(port::Input::new(image_notary::Input::SaveThermalData(
                 image_notary::SaveThermalDataInput {
@@ -1135,20 +1804,17 @@ impl Orb {
     ) -> Result<BrokerFlow> {
         macro_rules! restore_frame {
             () => {
-                loop {
-                    if let Some((frame, source_ts)) = self.ir_net_frames.pop_front() {
-                        if source_ts == output.source_ts {
-                            break frame;
-                        }
-                    } else {
-                        tracing::error!("IR-Net frame not found");
-                        return Ok(BrokerFlow::Continue);
-                    }
+                match restore_net_frame(&mut self.ir_net_frames, output.source_ts, "ir_net") {
+                    Some(frame) => frame,
+                    None => return Ok(BrokerFlow::Continue),
                 }
             };
         }
 
         let mut frame = None;
+        if let ir_net::Output::Warmup = output.value {
+            self.ir_net_warm = true;
+        }
         if let ir_net::Output::Estimate(estimate) = &output.value {
             let frame = frame.insert(restore_frame!());
             if let Some(image_notary) = self.image_notary.enabled() {
@@ -1159,6 +1825,8 @@ impl Orb {
                         frame: frame.clone(),
                         wavelength: self.ir_led_wavelength,
                         target_left_eye: self.target_left_eye,
+                        exposure: self.ir_led_duration,
+                        gain: self.ir_gain(),
                         fps_override: self.ir_eye_save_fps_override,
                         log_metadata_always: true,
                     },
@@ -1230,9 +1898,15 @@ impl Orb {
                     Ok(BrokerFlow::Continue)
                 }
             },
-            mega_agent_two::Output::Iris(_) | mega_agent_two::Output::Config(_) => {
+            mega_agent_two::Output::Iris(ref iris_output) => {
+                if let iris::Output::Estimate(iris_template) = iris_output {
+                    if let BrokerFlow::Break = plan.handle_iris(self, iris_template)? {
+                        return Ok(BrokerFlow::Break);
+                    }
+                }
                 plan.handle_mega_agent_two(self, output)
             }
+            mega_agent_two::Output::Config(_) => plan.handle_mega_agent_two(self, output),
         }
     }
 
@@ -1243,7 +1917,7 @@ impl Orb {
         output: port::Output<ir_auto_focus::Agent>,
     ) -> Result<BrokerFlow> {
         let value = output.value;
-        self.main_mcu.send_now(mcu::main::Input::LiquidLens(Some(value)))?;
+        self.send_now_retry(mcu::main::Input::LiquidLens(Some(value)), McuUpdate::IrFocus(value))?;
         plan.handle_ir_auto_focus(self, output)
     }
 
@@ -1253,25 +1927,28 @@ impl Orb {
         _plan: &mut dyn Plan,
         output: port::Output<ir_auto_exposure::Agent>,
     ) -> Result<BrokerFlow> {
-        let ir_auto_exposure::Output { gain, exposure } = output.value;
+        let ir_auto_exposure::Output { mut gain, exposure } = output.value;
         if let Some(ir_eye_camera) = self.ir_eye_camera.enabled() {
+            gain = clamp_ir_gain(gain, self.ir_eye_gain_range.as_ref(), "eye");
             ir_eye_camera.send_now(output.chain(camera::ir::Command::SetGain(gain)))?;
             ir_eye_camera
                 .send_now(output.chain(camera::ir::Command::SetExposure(exposure.into())))?;
         }
         if let Some(ir_face_camera) = self.ir_face_camera.enabled() {
+            let gain = clamp_ir_gain(gain, self.ir_face_gain_range.as_ref(), "face");
             ir_face_camera.send_now(output.chain(camera::ir::Command::SetGain(gain)))?;
             ir_face_camera
                 .send_now(output.chain(camera::ir::Command::SetExposure(exposure.into())))?;
         }
         self.set_ir_duration(exposure)?;
+        self.ir_exposure = (exposure, gain);
         Ok(BrokerFlow::Continue)
     }
 
     #[allow(clippy::needless_pass_by_value)]
     fn handle_eye_tracker(
         &mut self,
-        _plan: &mut dyn Plan,
+        plan: &mut dyn Plan,
         output: port::Output<eye_tracker::Agent>,
     ) -> Result<BrokerFlow> {
         let mirror_point = output.value;
@@ -1281,7 +1958,11 @@ impl Orb {
                 mirror_point + self.mirror_offset.unwrap_or_default(),
             )))?;
         }
-        Ok(BrokerFlow::Continue)
+        self.eye_lock_timer.as_mut().reset(time::Instant::now() + self.eye_lock_timeout);
+        if self.eye_lock {
+            return Ok(BrokerFlow::Continue);
+        }
+        self.set_eye_lock(plan, true)
     }
 
     #[allow(clippy::needless_pass_by_value)]
@@ -1309,17 +1990,44 @@ impl Orb {
         output: port::Output<mirror::Actuator>,
     ) -> Result<BrokerFlow> {
         let (x, y) = output.value;
-        self.main_mcu.send_now(mcu::main::Input::Mirror(x, y))?;
+        self.send_now_retry(mcu::main::Input::Mirror(x, y), McuUpdate::None)?;
+        if self.calibration_staged {
+            self.apply_staged_calibration(&output)?;
+        }
         plan.handle_mirror(self, output)
     }
 
-    #[allow(clippy::unused_self, clippy::needless_pass_by_value, clippy::unnecessary_wraps)]
+    fn apply_staged_calibration(&mut self, output: &port::Output<mirror::Actuator>) -> Result<()> {
+        let Some(mirror) = self.mirror.enabled() else {
+            return Ok(());
+        };
+        if let Err(err) = self.calibration.validate() {
+            tracing::error!("Dropping invalid staged calibration {:?}: {err}", self.calibration);
+            self.calibration_staged = false;
+            return Ok(());
+        }
+        let recalibrate = output.chain(mirror::Command::Recalibrate(self.calibration.clone()));
+        match mirror.tx.try_send(recalibrate) {
+            Ok(()) => self.calibration_staged = false,
+            // Try again on the next mirror command.
+            Err(err) if err.is_full() => return Ok(()),
+            Err(err) => bail!("message pass failed: {}", err),
+        }
+        if let Some(mirror_point) = self.mirror_point {
+            mirror.send_now(output.chain(mirror::Command::SetPoint(
+                mirror_point + self.mirror_offset.unwrap_or_default(),
+            )))?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::needless_pass_by_value, clippy::unnecessary_wraps)]
     fn handle_distance(
         &mut self,
-        _plan: &mut dyn Plan,
+        plan: &mut dyn Plan,
         output: port::Output<distance::Agent>,
     ) -> Result<BrokerFlow> {
-        match output.value {}
+        plan.handle_distance(self, output)
     }
 
     #[allow(clippy::unused_self, clippy::needless_pass_by_value, clippy::unnecessary_wraps)]
@@ -1328,16 +2036,25 @@ impl Orb {
         plan: &mut dyn Plan,
         output: port::Output<qr_code::Agent>,
     ) -> Result<BrokerFlow> {
+        self.mark_activity();
         plan.handle_qr_code(self, output)
     }
 
-    #[allow(clippy::unused_self, clippy::needless_pass_by_value)]
+    #[allow(clippy::needless_pass_by_value, clippy::unnecessary_wraps)]
     fn handle_image_uploader(
         &mut self,
         _plan: &mut dyn Plan,
         output: port::Output<image_uploader::Agent>,
     ) -> Result<BrokerFlow> {
-        match output.value {}
+        self.update_upload_report(output.value);
+        Ok(BrokerFlow::Continue)
+    }
+
+    // Ignores reports older than the stored one.
+    fn update_upload_report(&mut self, report: image_uploader::UploadReport) {
+        if report.revision >= self.upload_report.revision {
+            self.upload_report = report;
+        }
     }
 
     #[cfg_attr(test, allow(unused_variables))]
@@ -1360,11 +2077,141 @@ impl Orb {
         }
     }
 
+    /// Runs a hardware bring-up check of each subsystem: the cameras should
+    /// deliver a frame, the IR LEDs are pulsed, the mirror is moved to the
+    /// neutral point and back, a short sound is played, and the LED ring is
+    /// cycled. Each check is limited by a timeout. The LED ring has no feedback,
+    /// so it's reported as not verified. All agents are disabled afterwards.
+    pub async fn self_test(&mut self) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+        for (subsystem, plan) in [
+            ("ir_eye_camera", SelfTestPlan::IrEyeCamera),
+            ("ir_face_camera", SelfTestPlan::IrFaceCamera),
+            ("rgb_camera", SelfTestPlan::RgbCamera),
+            ("thermal_camera", SelfTestPlan::ThermalCamera),
+            ("mirror", SelfTestPlan::Mirror),
+        ] {
+            let result = self.self_test_agent(plan).await;
+            report.push(subsystem, result);
+        }
+        let result = time::timeout(SELF_TEST_TIMEOUT, self.self_test_ir_led()).await;
+        report.push("ir_led", result.unwrap_or_else(|_| Err(eyre!("timed out"))));
+        let result = time::timeout(SELF_TEST_TIMEOUT, self.self_test_sound()).await;
+        report.push("sound", result.unwrap_or_else(|_| Err(eyre!("timed out"))));
+        self.led.standby();
+        sleep(SELF_TEST_PULSE_DURATION).await;
+        self.led.wake();
+        report.push_unverified("led");
+        self.disable_agents();
+        report
+    }
+
+    // The start is under the timeout too, and the teardown runs even if the
+    // start failed.
+    async fn self_test_agent(&mut self, mut plan: SelfTestPlan) -> Result<()> {
+        let result = time::timeout(SELF_TEST_TIMEOUT, self.self_test_agent_run(&mut plan))
+            .await
+            .unwrap_or_else(|_| Err(eyre!("no output in {SELF_TEST_TIMEOUT:?}")));
+        let teardown = time::timeout(SELF_TEST_TIMEOUT, self.self_test_agent_teardown(plan))
+            .await
+            .unwrap_or_else(|_| Err(eyre!("teardown timed out in {SELF_TEST_TIMEOUT:?}")));
+        result.and(teardown)
+    }
+
+    async fn self_test_agent_run(&mut self, plan: &mut SelfTestPlan) -> Result<()> {
+        match plan {
+            SelfTestPlan::IrEyeCamera => self.start_ir_eye_camera().await?,
+            SelfTestPlan::IrFaceCamera => self.start_ir_face_camera().await?,
+            SelfTestPlan::RgbCamera => self.start_rgb_camera().await?,
+            SelfTestPlan::ThermalCamera => self.start_thermal_camera().await?,
+            SelfTestPlan::Mirror => {
+                self.enable_mirror()?;
+                self.mirror
+                    .enabled()
+                    .unwrap()
+                    .send(port::Input::new(mirror::Command::SetPoint(mirror::Point::neutral())))
+                    .await?;
+            }
+        }
+        self.run(plan).await
+    }
+
+    // Stops what the self-test started, skipping the agents which didn't start.
+    async fn self_test_agent_teardown(&mut self, plan: SelfTestPlan) -> Result<()> {
+        match plan {
+            SelfTestPlan::IrEyeCamera if self.ir_eye_camera.is_enabled() => {
+                self.stop_ir_eye_camera().await?;
+            }
+            SelfTestPlan::IrFaceCamera if self.ir_face_camera.is_enabled() => {
+                self.stop_ir_face_camera().await?;
+            }
+            SelfTestPlan::RgbCamera if self.rgb_camera.is_enabled() => {
+                self.stop_rgb_camera().await?;
+            }
+            SelfTestPlan::ThermalCamera if self.thermal_camera.is_enabled() => {
+                self.stop_thermal_camera().await?;
+            }
+            SelfTestPlan::Mirror if self.mirror.is_enabled() => {
+                let point = self.mirror_point.unwrap_or_else(mirror::Point::neutral);
+                let restored = self
+                    .mirror
+                    .enabled()
+                    .unwrap()
+                    .send(port::Input::new(mirror::Command::SetPoint(point)))
+                    .await;
+                self.stop_mirror().await?;
+                restored?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn self_test_ir_led(&mut self) -> Result<()> {
+        self.enable_ir_led().await?;
+        sleep(SELF_TEST_PULSE_DURATION).await;
+        self.disable_ir_led().await
+    }
+
+    async fn self_test_sound(&mut self) -> Result<()> {
+        let played = self.sound.build(sound::Type::Melody(Melody::BootUp))?.push()?.await;
+        if !played {
+            bail!("the sound was not played");
+        }
+        Ok(())
+    }
+
+    /// Registers a `hook` to be awaited by [`Orb::shutdown`] right after the
+    /// shutdown sound. Hooks run in the registration order. If a hook fails,
+    /// the error is logged and the shutdown continues.
+    pub fn register_shutdown_hook(&mut self, hook: ShutdownHook) {
+        self.shutdown_hooks.push(hook);
+    }
+
     /// Shuts down the orb.
     pub async fn shutdown(&mut self) -> Result<Infallible> {
         DATADOG.incr("orb.main.count.global.shutting_down", NO_TAGS)?;
         tracing::info!("Shutting down the Orb");
-        self.sound.build(sound::Type::Melody(Melody::PoweringDown))?.priority(3).push()?.await;
+        self.sound
+            .build(sound::Type::Melody(Melody::PoweringDown))?
+            .priority(3)
+            .preempt()
+            .push()?
+            .await;
+
+        // last chance to persist in-flight data, the shutdown proceeds anyway
+        for hook in mem::take(&mut self.shutdown_hooks) {
+            tracing::info!("Running a shutdown hook");
+            if let Err(err) = hook().await {
+                tracing::error!("Shutdown hook failed: {err:?}");
+            }
+        }
+
+        // don't lose the queued images
+        match self.flush_uploads(UPLOAD_FLUSH_TIMEOUT).await {
+            Ok(report) => tracing::info!("Image uploads flushed: {report:?}"),
+            Err(err) => tracing::error!("Flushing image uploads failed: {err:?}"),
+        }
 
         // save latest config to disk
         tracing::info!("Starting to write config to disk");
@@ -1396,9 +2243,280 @@ impl Orb {
         cx: &mut Context<'_>,
         _fence: Instant,
     ) -> Result<Option<Poll<()>>> {
+        self.poll_main_mcu(cx);
+        self.poll_mcu_retry(cx)?;
+        self.poll_standby(cx);
+        if matches!(self.poll_eye_lock(plan, cx)?, BrokerFlow::Break) {
+            return Ok(Some(Poll::Ready(())));
+        }
         if matches!(plan.poll_extra(self, cx)?, BrokerFlow::Break) {
             return Ok(Some(Poll::Ready(())));
         }
         Ok(Some(Poll::Pending))
     }
 }
+
+// Clamps a gain commanded by IR auto-exposure to the range supported by the
+// camera, which would otherwise silently ignore it.
+fn clamp_ir_gain(gain: i64, range: Option<&RangeInclusive<i64>>, camera: &str) -> i64 {
+    let Some(range) = range.filter(|range| !range.contains(&gain)) else {
+        return gain;
+    };
+    DATADOG
+        .incr("orb.main.count.global.ir_auto_exposure.gain_saturated", [format!("camera:{camera}")])
+        .or_log();
+    gain.clamp(*range.start(), *range.end())
+}
+
+// Tries to send a message. Returns the message back if the channel is full.
+fn try_send<T>(tx: &mut mpsc::Sender<T>, message: T) -> Result<Option<T>> {
+    match tx.try_send(message) {
+        Ok(()) => Ok(None),
+        Err(err) if err.is_full() => Ok(Some(err.into_inner())),
+        Err(err) => bail!("message pass failed: {}", err),
+    }
+}
+
+// Sends `input` chained from a camera output to a net. The frame is kept under
+// the camera `source_ts`, which the net output carries, until the output
+// arrives. The frame is skipped if the net is busy.
+fn send_net_frame<T: port::Port, F>(
+    tx: &mut port::OuterTx<T>,
+    frames: &mut VecDeque<(F, Instant)>,
+    input: port::Input<T>,
+    frame: F,
+    capacity: usize,
+    net: &str,
+) -> Result<()> {
+    let source_ts = input.source_ts;
+    if try_send(tx, input)?.is_none() {
+        push_net_frame(frames, (frame, source_ts), capacity, net);
+    }
+    Ok(())
+}
+
+// Pushes a frame sent to a net, evicting the oldest frames past `capacity`.
+fn push_net_frame<T>(
+    frames: &mut VecDeque<(T, Instant)>,
+    frame: (T, Instant),
+    capacity: usize,
+    net: &str,
+) {
+    while frames.len() >= capacity {
+        frames.pop_front();
+        DATADOG.incr("orb.main.count.global.net_frame_evicted", [format!("net:{net}")]).or_log();
+    }
+    frames.push_back(frame);
+}
+
+// Returns `true` if a frame with `timestamp` comes sooner than `min_interval`
+// after the last forwarded frame and should be dropped. Only the camera
+// timestamps are considered, so the dropping doesn't depend on the net queue
+// state. A timestamp going backwards starts over, e.g. after a camera restart.
+fn throttle_net_frame(
+    last_timestamp: &mut Option<Duration>,
+    timestamp: Duration,
+    min_interval: Duration,
+) -> bool {
+    if last_timestamp.is_some_and(|last| timestamp >= last && timestamp - last < min_interval) {
+        return true;
+    }
+    *last_timestamp = Some(timestamp);
+    false
+}
+
+// Pops frames from a net frame queue until the one sent with `source_ts` is
+// found. On a miss the whole queue is drained, and the mismatch is reported.
+fn restore_net_frame<T>(
+    frames: &mut VecDeque<(T, Instant)>,
+    source_ts: Instant,
+    net: &str,
+) -> Option<T> {
+    let front_ts = frames.front().map(|&(_, front_ts)| front_ts);
+    let mut popped = 0;
+    while let Some((frame, frame_ts)) = frames.pop_front() {
+        if frame_ts == source_ts {
+            return Some(frame);
+        }
+        popped += 1;
+    }
+    tracing::error!(
+        "{net} frame not found: expected source_ts: {source_ts:?}, queue front source_ts: \
+         {front_ts:?} ({:?} before expected), popped frames: {popped}",
+        front_ts.map(|front_ts| source_ts.saturating_duration_since(front_ts)),
+    );
+    DATADOG.incr("orb.main.count.global.net_frame_not_found", [format!("net:{net}")]).or_log();
+    None
+}
+
+impl Plan for FramePairPlan {
+    fn handle_ir_eye_camera(
+        &mut self,
+        _orb: &mut Orb,
+        output: port::Output<camera::ir::Sensor>,
+    ) -> Result<BrokerFlow> {
+        self.ir_frame = Some(output.value);
+        Ok(self.flow())
+    }
+
+    fn handle_rgb_camera(
+        &mut self,
+        _orb: &mut Orb,
+        output: port::Output<camera::rgb::Sensor>,
+    ) -> Result<BrokerFlow> {
+        self.rgb_frame = Some(output.value);
+        Ok(self.flow())
+    }
+}
+
+impl FramePairPlan {
+    fn flow(&self) -> BrokerFlow {
+        if self.ir_frame.is_some() && self.rgb_frame.is_some() {
+            BrokerFlow::Break
+        } else {
+            BrokerFlow::Continue
+        }
+    }
+}
+
+impl Plan for SelfTestPlan {
+    fn handle_ir_eye_camera(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<camera::ir::Sensor>,
+    ) -> Result<BrokerFlow> {
+        Ok(self.flow(SelfTestPlan::IrEyeCamera))
+    }
+
+    fn handle_ir_face_camera(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<camera::ir::Sensor>,
+    ) -> Result<BrokerFlow> {
+        Ok(self.flow(SelfTestPlan::IrFaceCamera))
+    }
+
+    fn handle_rgb_camera(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<camera::rgb::Sensor>,
+    ) -> Result<BrokerFlow> {
+        Ok(self.flow(SelfTestPlan::RgbCamera))
+    }
+
+    fn handle_thermal_camera(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<camera::thermal::Sensor>,
+    ) -> Result<BrokerFlow> {
+        Ok(self.flow(SelfTestPlan::ThermalCamera))
+    }
+
+    fn handle_mirror(
+        &mut self,
+        _orb: &mut Orb,
+        _output: port::Output<mirror::Actuator>,
+    ) -> Result<BrokerFlow> {
+        Ok(self.flow(SelfTestPlan::Mirror))
+    }
+}
+
+impl SelfTestPlan {
+    fn flow(self, output: SelfTestPlan) -> BrokerFlow {
+        if self == output {
+            BrokerFlow::Break
+        } else {
+            BrokerFlow::Continue
+        }
+    }
+}
+
+impl SelfTestReport {
+    /// Returns `true` if no subsystem check failed. Subsystems which can't be
+    /// verified don't fail the report.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+
+    fn push(&mut self, subsystem: &'static str, result: Result<()>) {
+        let error = result.err().map(|err| format!("{err:#}"));
+        match &error {
+            Some(error) => tracing::error!("Self-test of {subsystem} failed: {error}"),
+            None => tracing::info!("Self-test of {subsystem} passed"),
+        }
+        self.checks.push(SelfTestCheck { subsystem, error, verified: true });
+    }
+
+    fn push_unverified(&mut self, subsystem: &'static str) {
+        tracing::warn!("Self-test of {subsystem} can't be verified");
+        self.checks.push(SelfTestCheck { subsystem, error: None, verified: false });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_net_frame() {
+        let t0 = Instant::now();
+        let t1 = t0 + Duration::from_millis(50);
+        let t2 = t0 + Duration::from_millis(100);
+        let mut frames = VecDeque::from([(2, t2), (0, t0), (1, t1)]);
+        assert_eq!(restore_net_frame(&mut frames, t0, "test"), Some(0));
+        assert_eq!(frames, [(1, t1)]);
+        assert_eq!(restore_net_frame(&mut frames, t2, "test"), None);
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn test_push_net_frame() {
+        let t0 = Instant::now();
+        let mut frames = VecDeque::new();
+        for i in 0..5 {
+            push_net_frame(&mut frames, (i, t0 + Duration::from_millis(i)), 3, "test");
+        }
+        assert_eq!(frames.iter().map(|&(i, _)| i).collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_send_net_frame() {
+        let camera = port::Output::<eye_pid_controller::Agent>::new(mirror::Point::default());
+        let (mut tx, mut rx) = mpsc::channel(0);
+        let mut frames = VecDeque::new();
+        let input = camera.chain::<eye_pid_controller::Agent>(eye_pid_controller::Input::Reset);
+        send_net_frame(&mut tx, &mut frames, input, 1, 3, "test").unwrap();
+        let input = rx.try_next().unwrap().unwrap();
+        assert_eq!(input.source_ts, camera.source_ts);
+        assert_eq!(restore_net_frame(&mut frames, input.source_ts, "test"), Some(1));
+    }
+
+    #[test]
+    fn test_try_send() {
+        let (mut tx, mut rx) = mpsc::channel(0);
+        assert_eq!(try_send(&mut tx, 1).unwrap(), None);
+        assert_eq!(try_send(&mut tx, 2).unwrap(), Some(2));
+        assert_eq!(rx.try_next().unwrap(), Some(1));
+        assert_eq!(try_send(&mut tx, 3).unwrap(), None);
+        drop(rx);
+        assert!(try_send(&mut tx, 4).is_err());
+    }
+
+    #[test]
+    fn test_throttle_net_frame() {
+        let min_interval = Duration::from_millis(100);
+        let mut last = None;
+        let sent = [0, 30, 60, 90, 120, 150, 180, 210, 240, 20]
+            .into_iter()
+            .filter(|&ms| !throttle_net_frame(&mut last, Duration::from_millis(ms), min_interval))
+            .collect::<Vec<_>>();
+        assert_eq!(sent, [0, 120, 240, 20]);
+        let mut last = None;
+        assert!((0..5).all(|ms| !throttle_net_frame(
+            &mut last,
+            Duration::from_millis(ms),
+            Duration::ZERO
+        )));
+    }
+}
@@ -1,10 +1,20 @@
 //! Calibration data.
-use crate::{consts::CONFIG_DIR, logger::DATADOG};
+use crate::{agents::mirror, consts::CONFIG_DIR, logger::DATADOG};
 use eyre::Result;
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
 use tokio::fs;
 
+/// Allowed range of the horizontal mirror offset in degrees.
+pub const HORIZONTAL_OFFSET_RANGE: RangeInclusive<f64> = -15.0..=15.0;
+
+/// Allowed range of the vertical mirror offset in degrees.
+pub const VERTICAL_OFFSET_RANGE: RangeInclusive<f64> = -25.0..=25.0;
+
 /// Calibration data.
 #[allow(missing_docs)]
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -27,23 +37,82 @@ pub struct Mirror {
     // TODO: remove default attribute
     #[serde(default)]
     pub vertical_offset: f64,
+    /// Mechanical backlash of the mirror gimbals in degrees, compensated when
+    /// the commanded direction reverses. Zero disables the compensation.
+    #[serde(default)]
+    pub backlash: mirror::Point,
+}
+
+/// Error returned by [`Calibration::validate`].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CalibrationError {
+    /// The horizontal mirror offset is outside of [`HORIZONTAL_OFFSET_RANGE`].
+    #[error("horizontal mirror offset out of range: {0}")]
+    HorizontalOffset(f64),
+    /// The vertical mirror offset is outside of [`VERTICAL_OFFSET_RANGE`].
+    #[error("vertical mirror offset out of range: {0}")]
+    VerticalOffset(f64),
 }
 
 impl Calibration {
+    /// Checks that the mirror offsets are within the mechanical range of the
+    /// mirror.
+    pub fn validate(&self) -> Result<(), CalibrationError> {
+        let Mirror { horizontal_offset, vertical_offset, .. } = self.mirror;
+        if !HORIZONTAL_OFFSET_RANGE.contains(&horizontal_offset) {
+            return Err(CalibrationError::HorizontalOffset(horizontal_offset));
+        }
+        if !VERTICAL_OFFSET_RANGE.contains(&vertical_offset) {
+            return Err(CalibrationError::VerticalOffset(vertical_offset));
+        }
+        Ok(())
+    }
+
+    /// Clamps the mirror offsets to the mechanical range of the mirror.
+    pub fn clamp(&mut self) {
+        self.mirror.horizontal_offset = self
+            .mirror
+            .horizontal_offset
+            .clamp(*HORIZONTAL_OFFSET_RANGE.start(), *HORIZONTAL_OFFSET_RANGE.end());
+        self.mirror.vertical_offset = self
+            .mirror
+            .vertical_offset
+            .clamp(*VERTICAL_OFFSET_RANGE.start(), *VERTICAL_OFFSET_RANGE.end());
+    }
+
+    /// Serializes the calibration data to JSON, in the same format as stored on
+    /// the file system.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Deserializes the calibration data from JSON produced by
+    /// [`Calibration::to_json`] or read from the calibration file.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
     /// Tries to load calibration from the file system, or constructs a default
-    /// config on failure.
+    /// config on failure or if the stored calibration doesn't pass
+    /// [`Calibration::validate`].
     pub async fn load_or_default() -> Self {
         Self::load()
             .await
             .map_err(|err| tracing::error!("Calibration loading error: {err:#?}"))
             .ok()
             .flatten()
+            .filter(|calibration| {
+                calibration
+                    .validate()
+                    .map_err(|err| tracing::error!("Ignoring invalid stored calibration: {err}"))
+                    .is_ok()
+            })
             .unwrap_or_default()
     }
 
     /// Stores the calibration data to the file system.
     pub async fn store(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(self)?;
+        let json = self.to_json()?;
         DATADOG.gauge(
             "orb.main.gauge.system.calibration.mirror",
             self.mirror.horizontal_offset.to_string(),
@@ -69,7 +138,7 @@ impl Calibration {
         tracing::info!("Loading calibration from {}", path.display());
         let contents = fs::read_to_string(path).await?;
         tracing::debug!("Calibration file contents: {contents:#?}");
-        Ok(Some(serde_json::from_str(&contents)?))
+        Ok(Some(Self::from_json(&contents)?))
     }
 }
 
@@ -80,6 +149,7 @@ impl Default for Mirror {
             vertical_neutral_angle: None,
             horizontal_offset: -1.0,
             vertical_offset: -6.0,
+            backlash: mirror::Point::default(),
         }
     }
 }
@@ -87,3 +157,45 @@ impl Default for Mirror {
 fn calibration_file_path() -> PathBuf {
     Path::new(CONFIG_DIR).join("calibration.json")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut calibration = Calibration::default();
+        calibration.mirror.horizontal_offset = 2.5;
+        calibration.mirror.backlash.vertical = 0.3;
+        let restored = Calibration::from_json(&calibration.to_json().unwrap()).unwrap();
+        assert!((restored.mirror.horizontal_offset - 2.5).abs() < f64::EPSILON);
+        assert!((restored.mirror.backlash.vertical - 0.3).abs() < f64::EPSILON);
+
+        let legacy = r#"{"mirror": {"horizontal_offset": 1.0, "vertical_offset": -4.0}}"#;
+        let calibration = Calibration::from_json(legacy).unwrap();
+        assert!((calibration.mirror.vertical_offset + 4.0).abs() < f64::EPSILON);
+        assert!(calibration.mirror.backlash.horizontal.abs() < f64::EPSILON);
+        assert!(Calibration::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut calibration = Calibration::default();
+        assert_eq!(calibration.validate(), Ok(()));
+        calibration.mirror.horizontal_offset = 40.0;
+        assert_eq!(calibration.validate(), Err(CalibrationError::HorizontalOffset(40.0)));
+        calibration.clamp();
+        assert!(
+            (calibration.mirror.horizontal_offset - HORIZONTAL_OFFSET_RANGE.end()).abs()
+                < f64::EPSILON
+        );
+        calibration.mirror.vertical_offset = -40.0;
+        assert_eq!(calibration.validate(), Err(CalibrationError::VerticalOffset(-40.0)));
+        calibration.clamp();
+        assert!(
+            (calibration.mirror.vertical_offset - VERTICAL_OFFSET_RANGE.start()).abs()
+                < f64::EPSILON
+        );
+        assert_eq!(calibration.validate(), Ok(()));
+    }
+}
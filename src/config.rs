@@ -4,12 +4,22 @@ use crate::{
     agents::python::face_identifier,
     backend,
     consts::{
-        CONFIG_DIR, DEFAULT_BLOCK_SIGNUPS_WHEN_NO_INTERNET, DEFAULT_MAX_FAN_SPEED,
-        DEFAULT_SLOW_INTERNET_PING_THRESHOLD, DEFAULT_SOUND_VOLUME,
-        DEFAULT_THERMAL_CAMERA_PAIRING_STATUS_TIMEOUT, MAX_SOUND_VOLUME,
+        BIOMETRIC_CAPTURE_TIMEOUT, CONFIG_DIR, DEFAULT_BLOCK_SIGNUPS_WHEN_NO_INTERNET,
+        DEFAULT_IR_NET_MIN_INTERVAL, DEFAULT_MAX_FAN_SPEED, DEFAULT_OCCLUSION_30_INVALID_FACTOR,
+        DEFAULT_OCCLUSION_30_OFF_FACTOR, DEFAULT_OCCLUSION_30_ON_FACTOR,
+        DEFAULT_RGB_FRAME_SKIP_CPU_LOAD_THRESHOLD, DEFAULT_RGB_FRAME_SKIP_RATIO,
+        DEFAULT_RGB_NET_MIN_INTERVAL, DEFAULT_SLOW_INTERNET_PING_THRESHOLD, DEFAULT_SOUND_VOLUME,
+        DEFAULT_THERMAL_CAMERA_PAIRING_STATUS_TIMEOUT, MAX_SOUND_VOLUME, THRESHOLD_OCCLUSION_30,
     },
+    led::LedPalette,
     logger::{LogOnError, DATADOG, NO_TAGS},
-    plans::fraud_check,
+    plans::{
+        biometric_capture::{
+            IR_TARGET_MEAN, MIN_SHARPNESS, OVERHEAT_HYSTERESIS, OVERHEAT_TEMPERATURE,
+            SHARPNESS_LOW_PASS_FILTER_RC,
+        },
+        fraud_check,
+    },
 };
 use eyre::{eyre, Context, Result};
 use schemars::JsonSchema;
@@ -77,6 +87,75 @@ pub struct Config {
     pub upload_self_custody_thumbnail: bool,
     /// Upload Iris' normalized images to backend.
     pub upload_iris_normalized_images: bool,
+    /// CPU load fraction above which RGB frames are skipped before RGB-Net.
+    pub rgb_frame_skip_cpu_load_threshold: f64,
+    /// Skip every Nth RGB frame when the CPU load is above the threshold. `0`
+    /// disables the skipping, `1` is rejected as it would skip every frame.
+    pub rgb_frame_skip_ratio: u32,
+    /// Minimum interval between IR frames forwarded to IR-Net. Frames in
+    /// between are dropped.
+    pub ir_net_min_interval: Duration,
+    /// Minimum interval between RGB frames forwarded to RGB-Net. Frames in
+    /// between are dropped.
+    pub rgb_net_min_interval: Duration,
+    /// Biometric capture settings.
+    pub biometric_capture: BiometricCaptureConfig,
+    /// Colors of the biometric capture LED animations.
+    pub led_palette: LedPalette,
+}
+
+/// Biometric capture settings, see
+/// [`biometric_capture::Plan`](crate::plans::biometric_capture::Plan).
+#[derive(Clone, Serialize, JsonSchema, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct BiometricCaptureConfig {
+    /// Capture timeout. Backend key `TimeoutMs`, defaults to
+    /// [`BIOMETRIC_CAPTURE_TIMEOUT`].
+    pub timeout: Duration,
+    /// Minimal viable sharpness for the IR auto-focus. Backend key
+    /// `MinSharpness`, defaults to [`MIN_SHARPNESS`].
+    pub min_sharpness: f64,
+    /// Target mean brightness for the IR auto-exposure. Backend key
+    /// `IrTargetMean`, defaults to [`IR_TARGET_MEAN`].
+    pub ir_target_mean: f64,
+    /// Fraction of the objective's maximum score a frame must reach to be
+    /// stored. Backend key `AdaptiveGateFraction`, disabled by default.
+    pub adaptive_gate_fraction: Option<f64>,
+    /// Whether the Orb has a dedicated eye camera for each eye. Backend key
+    /// `DualEyeCameras`, defaults to `false`.
+    pub dual_eye_cameras: bool,
+    /// RC time constant in seconds of the low-pass filter smoothing the
+    /// sharpness for the progress bar. Backend key `SharpnessFilterRc`,
+    /// defaults to [`SHARPNESS_LOW_PASS_FILTER_RC`].
+    pub sharpness_filter_rc: f64,
+    /// Main MCU or liquid lens temperature in degrees Celsius above which the
+    /// IR LEDs are paused. Backend key `OverheatTemperature`, defaults to
+    /// [`OVERHEAT_TEMPERATURE`].
+    pub overheat_temperature: f64,
+    /// How many degrees Celsius below [`Self::overheat_temperature`] the board
+    /// must cool down to resume the IR LEDs. Backend key
+    /// `OverheatHysteresis`, defaults to [`OVERHEAT_HYSTERESIS`].
+    pub overheat_hysteresis: f64,
+    /// Brightness of the warm-white ring illuminating the face during the
+    /// self-custody capture in low light. Backend key `FaceIllumination`,
+    /// defaults to `0`, which disables the illumination.
+    pub face_illumination: u8,
+    /// Occlusion 30 threshold. Backend key `Occlusion30Threshold`, defaults to
+    /// [`THRESHOLD_OCCLUSION_30`].
+    pub occlusion_30_threshold: f64,
+    /// Multiplier of [`Self::occlusion_30_threshold`] substituted for frames
+    /// without a valid occlusion estimate. Backend key
+    /// `Occlusion30InvalidFactor`, defaults to
+    /// [`DEFAULT_OCCLUSION_30_INVALID_FACTOR`].
+    pub occlusion_30_invalid_factor: f64,
+    /// Multiplier of [`Self::occlusion_30_threshold`] below which the
+    /// occlusion indicator turns on. Backend key `Occlusion30OnFactor`,
+    /// defaults to [`DEFAULT_OCCLUSION_30_ON_FACTOR`].
+    pub occlusion_30_on_factor: f64,
+    /// Multiplier of [`Self::occlusion_30_threshold`] above which the
+    /// occlusion indicator turns off. Backend key `Occlusion30OffFactor`,
+    /// defaults to [`DEFAULT_OCCLUSION_30_OFF_FACTOR`].
+    pub occlusion_30_off_factor: f64,
 }
 
 #[cfg(not(feature = "stage"))]
@@ -114,6 +193,12 @@ impl Config {
                     upload_self_custody_images,
                     upload_self_custody_thumbnail,
                     upload_iris_normalized_images,
+                    rgb_frame_skip_cpu_load_threshold,
+                    rgb_frame_skip_ratio,
+                    ir_net_min_interval_ms,
+                    rgb_net_min_interval_ms,
+                    biometric_capture,
+                    led_palette,
                     last_updated: _,
                 },
         } = status;
@@ -146,6 +231,16 @@ impl Config {
                 .unwrap_or(Self::default().upload_self_custody_thumbnail),
             upload_iris_normalized_images: upload_iris_normalized_images
                 .unwrap_or(Self::default().upload_iris_normalized_images),
+            rgb_frame_skip_cpu_load_threshold: rgb_frame_skip_cpu_load_threshold
+                .unwrap_or(Self::default().rgb_frame_skip_cpu_load_threshold),
+            rgb_frame_skip_ratio: rgb_frame_skip_ratio
+                .unwrap_or(Self::default().rgb_frame_skip_ratio),
+            ir_net_min_interval: ir_net_min_interval_ms
+                .map_or(Self::default().ir_net_min_interval, Duration::from_millis),
+            rgb_net_min_interval: rgb_net_min_interval_ms
+                .map_or(Self::default().rgb_net_min_interval, Duration::from_millis),
+            biometric_capture: BiometricCaptureConfig::from_backend(biometric_capture),
+            led_palette: led_palette.unwrap_or_default(),
         })
         .filter(Self::validate)
     }
@@ -181,6 +276,11 @@ impl Config {
     #[must_use]
     pub fn validate(&self) -> bool {
         self.basic_config.sound_volume <= MAX_SOUND_VOLUME
+            && self.biometric_capture.occlusion_30_on_factor
+                <= self.biometric_capture.occlusion_30_off_factor
+            && self.biometric_capture.overheat_hysteresis >= 0.0
+            // `1` would skip every RGB frame, `0` disables the skipping
+            && self.rgb_frame_skip_ratio != 1
     }
 
     async fn load() -> Result<Self> {
@@ -257,6 +357,72 @@ impl Default for Config {
             upload_self_custody_images: false,
             upload_self_custody_thumbnail: true,
             upload_iris_normalized_images: true,
+            rgb_frame_skip_cpu_load_threshold: DEFAULT_RGB_FRAME_SKIP_CPU_LOAD_THRESHOLD,
+            rgb_frame_skip_ratio: DEFAULT_RGB_FRAME_SKIP_RATIO,
+            ir_net_min_interval: DEFAULT_IR_NET_MIN_INTERVAL,
+            rgb_net_min_interval: DEFAULT_RGB_NET_MIN_INTERVAL,
+            biometric_capture: BiometricCaptureConfig::default(),
+            led_palette: LedPalette::default(),
+        }
+    }
+}
+
+impl BiometricCaptureConfig {
+    fn from_backend(config: backend::config::BiometricCaptureConfig) -> Self {
+        let backend::config::BiometricCaptureConfig {
+            timeout_ms,
+            min_sharpness,
+            ir_target_mean,
+            adaptive_gate_fraction,
+            dual_eye_cameras,
+            sharpness_filter_rc,
+            overheat_temperature,
+            overheat_hysteresis,
+            face_illumination,
+            occlusion_30_threshold,
+            occlusion_30_invalid_factor,
+            occlusion_30_on_factor,
+            occlusion_30_off_factor,
+        } = config;
+        Self {
+            timeout: timeout_ms.map_or(Self::default().timeout, Duration::from_millis),
+            min_sharpness: min_sharpness.unwrap_or(Self::default().min_sharpness),
+            ir_target_mean: ir_target_mean.unwrap_or(Self::default().ir_target_mean),
+            adaptive_gate_fraction,
+            dual_eye_cameras: dual_eye_cameras.unwrap_or(Self::default().dual_eye_cameras),
+            sharpness_filter_rc: sharpness_filter_rc.unwrap_or(Self::default().sharpness_filter_rc),
+            overheat_temperature: overheat_temperature
+                .unwrap_or(Self::default().overheat_temperature),
+            overheat_hysteresis: overheat_hysteresis.unwrap_or(Self::default().overheat_hysteresis),
+            face_illumination: face_illumination.unwrap_or(Self::default().face_illumination),
+            occlusion_30_threshold: occlusion_30_threshold
+                .unwrap_or(Self::default().occlusion_30_threshold),
+            occlusion_30_invalid_factor: occlusion_30_invalid_factor
+                .unwrap_or(Self::default().occlusion_30_invalid_factor),
+            occlusion_30_on_factor: occlusion_30_on_factor
+                .unwrap_or(Self::default().occlusion_30_on_factor),
+            occlusion_30_off_factor: occlusion_30_off_factor
+                .unwrap_or(Self::default().occlusion_30_off_factor),
+        }
+    }
+}
+
+impl Default for BiometricCaptureConfig {
+    fn default() -> Self {
+        Self {
+            timeout: BIOMETRIC_CAPTURE_TIMEOUT,
+            min_sharpness: MIN_SHARPNESS,
+            ir_target_mean: IR_TARGET_MEAN,
+            adaptive_gate_fraction: None,
+            dual_eye_cameras: false,
+            sharpness_filter_rc: SHARPNESS_LOW_PASS_FILTER_RC,
+            overheat_temperature: OVERHEAT_TEMPERATURE,
+            overheat_hysteresis: OVERHEAT_HYSTERESIS,
+            face_illumination: 0,
+            occlusion_30_threshold: THRESHOLD_OCCLUSION_30,
+            occlusion_30_invalid_factor: DEFAULT_OCCLUSION_30_INVALID_FACTOR,
+            occlusion_30_on_factor: DEFAULT_OCCLUSION_30_ON_FACTOR,
+            occlusion_30_off_factor: DEFAULT_OCCLUSION_30_OFF_FACTOR,
         }
     }
 }
@@ -62,6 +62,18 @@ pub const QR_SCAN_INTERVAL: Duration = Duration::from_millis(1500);
 /// Face detection timeout.
 pub const DETECT_FACE_TIMEOUT: Duration = Duration::from_secs(20);
 
+/// Inactivity duration after which the LEDs switch to the standby animation.
+pub const STANDBY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Maximum duration to wait for the queued image uploads on shutdown.
+pub const UPLOAD_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum duration to wait for IR-Net to warm up before a biometric capture.
+pub const IR_NET_WARMUP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Duration without eye tracker outputs after which the eye lock is lost.
+pub const EYE_LOCK_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// Default IR (infrared) LED duration in microseconds.
 pub const DEFAULT_IR_LED_DURATION: u16 = 350;
 
@@ -273,3 +285,27 @@ pub const DEFAULT_BLOCK_SIGNUPS_WHEN_NO_INTERNET: bool = true;
 
 /// Default amount of time to wait until we assume the camera is stuck pairing.
 pub const DEFAULT_THERMAL_CAMERA_PAIRING_STATUS_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Default CPU load above which RGB frames are skipped before RGB-Net.
+pub const DEFAULT_RGB_FRAME_SKIP_CPU_LOAD_THRESHOLD: f64 = 0.9;
+
+/// By default skip every second RGB frame when the CPU is overloaded.
+pub const DEFAULT_RGB_FRAME_SKIP_RATIO: u32 = 2;
+
+/// By default every IR frame is forwarded to IR-Net.
+pub const DEFAULT_IR_NET_MIN_INTERVAL: Duration = Duration::ZERO;
+
+/// By default every RGB frame is forwarded to RGB-Net.
+pub const DEFAULT_RGB_NET_MIN_INTERVAL: Duration = Duration::ZERO;
+
+/// Multiplier of the occlusion 30 threshold substituted for frames without a
+/// valid occlusion estimate.
+pub const DEFAULT_OCCLUSION_30_INVALID_FACTOR: f64 = 1.05;
+
+/// Multiplier of the occlusion 30 threshold below which the occlusion
+/// indicator turns on.
+pub const DEFAULT_OCCLUSION_30_ON_FACTOR: f64 = 0.975;
+
+/// Multiplier of the occlusion 30 threshold above which the occlusion
+/// indicator turns off.
+pub const DEFAULT_OCCLUSION_30_OFF_FACTOR: f64 = 1.025;
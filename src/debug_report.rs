@@ -577,24 +577,22 @@ fn load_cache(cache_file_name: &str) -> Option<String> {
 }
 
 // TODO: Consider implementing the Serialize trait for TimeSeries<T> instead
-impl From<&'_ mut TimeSeries<(Option<ImageId>, Option<IrNetMetadata>, IrLed, bool, Duration)>>
-    for Vec<IrCameraMetadata>
-{
-    fn from(
-        ir_net_metadata_history: &mut TimeSeries<(
-            Option<ImageId>,
-            Option<IrNetMetadata>,
-            IrLed,
-            bool,
-            Duration,
-        )>,
-    ) -> Self {
+impl From<&'_ mut TimeSeries<image_notary::IrNetMetadataEntry>> for Vec<IrCameraMetadata> {
+    fn from(ir_net_metadata_history: &mut TimeSeries<image_notary::IrNetMetadataEntry>) -> Self {
         ir_net_metadata_history
             .iter()
             .map(|value| {
                 let Timestamped {
                     value:
-                        (image_id, ir_net_metadata, wavelength, target_left_eye, capture_timestamp),
+                        (
+                            image_id,
+                            ir_net_metadata,
+                            wavelength,
+                            target_left_eye,
+                            exposure,
+                            gain,
+                            capture_timestamp,
+                        ),
                     timestamp,
                 } = value;
                 IrCameraMetadata {
@@ -606,6 +604,8 @@ impl From<&'_ mut TimeSeries<(Option<ImageId>, Option<IrNetMetadata>, IrLed, boo
                     // TODO: Check this is the correct convention!
                     side: if *target_left_eye { Some(1) } else { Some(0) },
                     wavelength: *wavelength,
+                    exposure: *exposure,
+                    gain: *gain,
                     irnet: ir_net_metadata.clone(),
                 }
             })
@@ -1061,6 +1061,8 @@ pub struct IrCameraMetadata {
     common_camera_metadata: CommonImageMetadata,
     side: Option<u8>,
     wavelength: IrLed,
+    exposure: u16,
+    gain: f64,
     irnet: Option<IrNetMetadata>,
 }
 
@@ -1,10 +1,13 @@
 //! Fisheye compensation.
 
-use crate::consts::{
-    CONFIG_DIR, RGB_CALIBRATION_FILE, RGB_CALIBRATION_HEIGHT, RGB_CALIBRATION_WIDTH,
-    RGB_REDUCED_HEIGHT, RGB_REDUCED_WIDTH,
+use crate::{
+    agents::{camera, camera::Frame as _},
+    consts::{
+        CONFIG_DIR, RGB_CALIBRATION_FILE, RGB_CALIBRATION_HEIGHT, RGB_CALIBRATION_WIDTH,
+        RGB_REDUCED_HEIGHT, RGB_REDUCED_WIDTH,
+    },
 };
-use eyre::{Error, Result};
+use eyre::{ensure, Error, Result};
 use opencv::{
     calib3d::{get_optimal_new_camera_matrix, init_undistort_rectify_map, undistort_points},
     core::{no_array, Mat_AUTO_STEP, Point2f, Size, BORDER_CONSTANT, CV_16SC2, CV_8UC3},
@@ -185,6 +188,22 @@ impl Fisheye {
     }
 }
 
+/// Returns an undistorted copy of an RGB frame. The frame dimensions must match
+/// the fisheye configuration.
+pub fn undistort_frame(frame: &camera::rgb::Frame, config: &Config) -> Result<camera::rgb::Frame> {
+    ensure!(
+        frame.width() == config.rgb_width && frame.height() == config.rgb_height,
+        "frame size {}x{} doesn't match fisheye configuration {}x{}",
+        frame.width(),
+        frame.height(),
+        config.rgb_width,
+        config.rgb_height
+    );
+    let fisheye = Fisheye::try_from(*config)?;
+    let data = fisheye.undistort_image(frame, frame.width(), frame.height())?;
+    Ok(camera::rgb::Frame::from_vec(data, frame.timestamp(), frame.width(), frame.height()))
+}
+
 #[allow(clippy::cast_precision_loss)]
 fn scale_camera_matrix(camera_matrix: &mut CameraMatrix, rgb_width: u32, rgb_height: u32) {
     check_aspect_ratio(rgb_width, rgb_height);
@@ -17,7 +17,15 @@ use eyre::Result;
 use futures::{future, future::Either, prelude::*};
 #[cfg(feature = "ui-test")]
 use log::debug;
-use std::{any::Any, collections::BTreeMap, f64::consts::PI, time::Duration};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    f64::consts::PI,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio::{sync::mpsc, task, time};
 use tokio_stream::wrappers::{IntervalStream, UnboundedReceiverStream};
 
@@ -43,16 +51,29 @@ impl Rgb {
     const USER_QR_SCAN: Rgb = Rgb(24, 24, 24);
     const USER_RED: Rgb = Rgb(30, 2, 0);
     const USER_SIGNUP: Rgb = Rgb(31, 31, 31);
+    const USER_STANDBY: Rgb = Rgb(8, 8, 8);
 }
 
-const GAMMA: f64 = 2.5;
+// Gamma correction applied to the ring LEDs on the way to the MCU. Animations
+// work in linear space.
+const RING_GAMMA: f64 = 2.2;
 
 const LEVEL_BACKGROUND: u8 = 0;
 const LEVEL_FOREGROUND: u8 = 10;
+// Below the notices, so that e.g. the recovery spinner stays visible.
+const LEVEL_STANDBY: u8 = 15;
 const LEVEL_NOTICE: u8 = 20;
+const LEVEL_ILLUMINATION: u8 = 25;
 
 const BIOMETRIC_PIPELINE_MAX_PROGRESS: f64 = 0.875;
 
+// Duration in seconds of the cross-fade between ring animations changed with
+// `Runner::transition_ring`.
+const RING_TRANSITION_DURATION: f64 = 0.5;
+
+// Warm-white color of the ring illuminating the face.
+const FACE_ILLUMINATION_TINT: Rgb = Rgb(255, 180, 110);
+
 macro_rules! event_enum {
     (
         $(#[$($enum_attrs:tt)*])*
@@ -111,11 +132,25 @@ macro_rules! event_enum {
                 Box::new(Fake)
             }
         }
+
+        impl Engine for Recording {
+            $(
+                $(#[doc = $doc])?
+                fn $method(&self, $($($field: $ty,)*)?) {
+                    let event = $name::$event $({$($field,)*})?;
+                    self.events.lock().expect("LED recording is poisoned").push(event);
+                }
+            )*
+
+            fn clone(&self) -> Box<dyn Engine> {
+                Box::new(Recording { events: Arc::clone(&self.events) })
+            }
+        }
     };
 }
 
 /// QR-code scanning schema.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum QrScanSchema {
     /// Operator QR-code scanning.
     Operator,
@@ -126,8 +161,10 @@ pub enum QrScanSchema {
 }
 
 event_enum! {
-    #[allow(dead_code)]
-    enum Event {
+    /// LED engine event, as recorded by [`Recording`].
+    #[allow(missing_docs)]
+    #[derive(Clone, PartialEq)]
+    pub enum LedEvent {
         /// Orb boot up.
         #[event_enum(method = bootup)]
         Bootup,
@@ -173,6 +210,23 @@ event_enum! {
         BiometricCaptureProgress {
             progress: f64,
         },
+        /// Biometric capture objectives, marked on the progress ring.
+        #[event_enum(method = biometric_capture_objective_ticks)]
+        BiometricCaptureObjectiveTicks {
+            completed: usize,
+            total: usize,
+        },
+        /// Illuminate the face with the warm-white ring at the given brightness. `0` turns the
+        /// illumination off.
+        #[event_enum(method = face_illumination)]
+        FaceIllumination {
+            level: u8,
+        },
+        /// Biometric capture remaining time as a fraction of the timeout.
+        #[event_enum(method = biometric_capture_time_remaining)]
+        BiometricCaptureTimeRemaining {
+            fraction: f64,
+        },
         /// Biometric capture occlusion.
         #[event_enum(method = biometric_capture_occlusion)]
         BiometricCaptureOcclusion {
@@ -186,6 +240,9 @@ event_enum! {
         /// Biometric capture succeeded.
         #[event_enum(method = biometric_capture_success)]
         BiometricCaptureSuccess,
+        /// Biometric capture failed, e.g. timed out.
+        #[event_enum(method = biometric_capture_failed)]
+        BiometricCaptureFailed,
         /// Starting enrollment.
         #[event_enum(method = starting_enrollment)]
         StartingEnrollment,
@@ -212,6 +269,12 @@ event_enum! {
         /// Idle mode.
         #[event_enum(method = idle)]
         Idle,
+        /// Low-power standby mode after a period of inactivity.
+        #[event_enum(method = standby)]
+        Standby,
+        /// Wake up from standby mode.
+        #[event_enum(method = wake)]
+        Wake,
         /// Orb shutdown.
         #[event_enum(method = shutdown)]
         Shutdown {
@@ -254,6 +317,21 @@ event_enum! {
         /// Resume sending messages to the MCU.
         #[event_enum(method = resume)]
         Resume,
+        /// Set the gamma correction of the ring LEDs. `1.0` disables the correction.
+        #[event_enum(method = ring_gamma)]
+        RingGamma {
+            gamma: f64,
+        },
+        /// Set the colors of the user-facing biometric capture animations.
+        #[event_enum(method = palette)]
+        Palette {
+            palette: LedPalette,
+        },
+        /// Set the rate of the frames sent to the MCU. Animations look the same at any rate.
+        #[event_enum(method = set_frame_rate)]
+        FrameRate {
+            fps: u32,
+        },
 
         /// In recovery image
         #[event_enum(method = recovery)]
@@ -304,12 +382,49 @@ pub trait Animation: Send + 'static {
 
 /// LED engine for the Orb hardware.
 pub struct Jetson {
-    tx: mpsc::UnboundedSender<Event>,
+    tx: mpsc::UnboundedSender<LedEvent>,
 }
 
 /// LED engine interface which does nothing.
 pub struct Fake;
 
+/// LED engine interface which records the timeline of the calls, e.g. to
+/// check the animations triggered by a plan in tests. Clones share the same
+/// timeline.
+#[derive(Clone, Default)]
+pub struct Recording {
+    events: Arc<Mutex<Vec<LedEvent>>>,
+}
+
+/// Colors of the user-facing biometric capture animations. Deployments can
+/// rebrand the ring through the `LedPalette` config key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct LedPalette {
+    /// Ring color while no user is interacting with the Orb.
+    pub idle: Rgb,
+    /// Ring color of the biometric capture progress.
+    pub capturing: Rgb,
+    /// Ring color after a successful signup.
+    pub success: Rgb,
+    /// Ring color after a failed biometric capture.
+    pub error: Rgb,
+    /// Center color while the user's eyes are occluded.
+    pub occlusion: Rgb,
+}
+
+impl Default for LedPalette {
+    fn default() -> Self {
+        Self {
+            idle: Rgb::OFF,
+            capturing: Rgb::USER_SIGNUP,
+            success: Rgb::USER_SIGNUP,
+            error: Rgb::USER_RED,
+            occlusion: Rgb::OFF,
+        }
+    }
+}
+
 /// Frame for the front LED ring.
 pub type RingFrame = [Rgb; RING_LED_COUNT];
 
@@ -333,6 +448,8 @@ struct Runner {
     operator_action: operator::Bar,
     operator_signup_phase: operator::SignupPhase,
     paused: bool,
+    ring_gamma: f64,
+    palette: LedPalette,
 }
 
 struct AnimationsStack<Frame: 'static> {
@@ -344,6 +461,18 @@ struct RunningAnimation<Frame> {
     kill: bool,
 }
 
+impl Recording {
+    /// Returns the recorded events in call order.
+    ///
+    /// # Panics
+    ///
+    /// If a thread panicked while recording an event.
+    #[must_use]
+    pub fn events(&self) -> Vec<LedEvent> {
+        self.events.lock().expect("LED recording is poisoned").clone()
+    }
+}
+
 impl Jetson {
     /// Creates a new LED engine.
     #[must_use]
@@ -357,11 +486,9 @@ impl Jetson {
 #[allow(clippy::too_many_lines)]
 async fn event_loop(
     main_mcu: Box<dyn Mcu<mcu::Main>>,
-    rx: mpsc::UnboundedReceiver<Event>,
+    rx: mpsc::UnboundedReceiver<LedEvent>,
 ) -> Result<()> {
-    let mut interval = time::interval(Duration::from_millis(1000 / LED_ENGINE_FPS));
-    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
-    let mut interval = IntervalStream::new(interval);
+    let mut interval = frame_interval(LED_ENGINE_FPS);
     let mut rx = UnboundedReceiverStream::new(rx);
     let mut runner = Runner::new(main_mcu);
     loop {
@@ -369,6 +496,9 @@ async fn event_loop(
             Either::Left((None, _)) => {
                 break;
             }
+            Either::Left((Some(LedEvent::FrameRate { fps }), _)) => {
+                interval = frame_interval(fps.into());
+            }
             Either::Left((Some(event), _)) => {
                 runner.event(&event);
             }
@@ -380,6 +510,12 @@ async fn event_loop(
     Ok(())
 }
 
+fn frame_interval(fps: u64) -> IntervalStream {
+    let mut interval = time::interval(Duration::from_millis(1000 / fps.max(1)));
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+    IntervalStream::new(interval)
+}
+
 impl Runner {
     fn new(main_mcu: Box<dyn Mcu<mcu::Main>>) -> Self {
         Self {
@@ -397,23 +533,28 @@ impl Runner {
             operator_action: operator::Bar::default(),
             operator_signup_phase: operator::SignupPhase::default(),
             paused: false,
+            ring_gamma: RING_GAMMA,
+            palette: LedPalette::default(),
         }
     }
 
     #[allow(clippy::too_many_lines)]
-    fn event(&mut self, event: &Event) {
+    fn event(&mut self, event: &LedEvent) {
         #[cfg(feature = "ui-test")]
         tracing::debug!("LED event: {:?}", event);
 
         match event {
-            Event::Bootup => {
+            LedEvent::Bootup => {
                 self.stop_ring(LEVEL_NOTICE, true);
                 self.stop_center(LEVEL_NOTICE, true);
-                self.set_ring(LEVEL_BACKGROUND, ring::Idle::default());
+                self.transition_ring(
+                    LEVEL_BACKGROUND,
+                    ring::Idle::new(Some(self.palette.idle), None),
+                );
                 self.operator_pulse.trigger(2048.0, 1., 1., false);
             }
-            Event::BootComplete => self.operator_pulse.stop(),
-            Event::Shutdown { requested } => {
+            LedEvent::BootComplete => self.operator_pulse.stop(),
+            LedEvent::Shutdown { requested } => {
                 self.set_center(
                     LEVEL_NOTICE,
                     center::Alert::new(
@@ -424,7 +565,7 @@ impl Runner {
                 );
                 self.operator_action.trigger(1.0, Rgb::OFF, true, false, true);
             }
-            Event::SignupStart => {
+            LedEvent::SignupStart => {
                 // starting signup sequence, operator LEDs in blue
                 // animate from left to right (`operator_action`)
                 // and then keep first LED on as a background (`operator_signup_phase`)
@@ -437,7 +578,7 @@ impl Runner {
                 self.stop_ring(LEVEL_NOTICE, true);
                 self.stop_center(LEVEL_NOTICE, true);
             }
-            Event::QrScanStart { schema } => {
+            LedEvent::QrScanStart { schema } => {
                 self.set_center(
                     LEVEL_FOREGROUND,
                     center::Wave::new(Rgb::USER_QR_SCAN, 5.0, 0.5, true),
@@ -457,14 +598,14 @@ impl Runner {
                     }
                 };
             }
-            Event::QrScanCompleted { schema: _ } => {
+            LedEvent::QrScanCompleted { schema: _ } => {
                 self.set_center(
                     LEVEL_NOTICE,
                     center::Alert::new(Rgb::USER_QR_SCAN, vec![0.0, 0.3, 0.45, 0.46], false),
                 );
                 self.stop_center(LEVEL_FOREGROUND, true);
             }
-            Event::QrScanUnexpected { schema } => {
+            LedEvent::QrScanUnexpected { schema } => {
                 match schema {
                     QrScanSchema::User => {
                         self.operator_signup_phase.user_qr_code_issue();
@@ -476,7 +617,7 @@ impl Runner {
                 }
                 self.stop_center(LEVEL_FOREGROUND, true);
             }
-            Event::QrScanFail { schema } => {
+            LedEvent::QrScanFail { schema } => {
                 match schema {
                     QrScanSchema::User | QrScanSchema::Operator => {
                         self.stop_center(LEVEL_FOREGROUND, true);
@@ -487,7 +628,7 @@ impl Runner {
                 }
                 self.stop_ring(LEVEL_FOREGROUND, true);
             }
-            Event::QrScanSuccess { schema } => {
+            LedEvent::QrScanSuccess { schema } => {
                 if matches!(schema, QrScanSchema::Operator) {
                     self.operator_signup_phase.operator_qr_captured();
                 } else if matches!(schema, QrScanSchema::User) {
@@ -495,7 +636,7 @@ impl Runner {
                     // initialize ring with short segment to invite user to start iris capture
                     self.set_ring(
                         LEVEL_NOTICE,
-                        ring::Slider::new(0.0, Rgb::USER_SIGNUP).pulse_remaining(),
+                        ring::Slider::new(0.0, self.palette.capturing).pulse_remaining(),
                     );
                     // off background for biometric-capture, which relies on LEVEL_NOTICE animations
                     self.stop_center(LEVEL_FOREGROUND, true);
@@ -503,13 +644,13 @@ impl Runner {
                 }
                 self.stop_ring(LEVEL_FOREGROUND, true);
             }
-            Event::BiometricCaptureHalfObjectivesCompleted => {
+            LedEvent::BiometricCaptureHalfObjectivesCompleted => {
                 // do nothing
             }
-            Event::BiometricCaptureAllObjectivesCompleted => {
+            LedEvent::BiometricCaptureAllObjectivesCompleted => {
                 self.operator_signup_phase.irises_captured();
             }
-            Event::BiometricCaptureProgress { progress } => {
+            LedEvent::BiometricCaptureProgress { progress } => {
                 if self
                     .ring_animations_stack
                     .stack
@@ -523,7 +664,7 @@ impl Runner {
                     // initialize ring with short segment to invite user to start iris capture
                     self.set_ring(
                         LEVEL_NOTICE,
-                        ring::Slider::new(0.0, Rgb::USER_SIGNUP).pulse_remaining(),
+                        ring::Slider::new(0.0, self.palette.capturing).pulse_remaining(),
                     );
                 }
                 let ring_progress =
@@ -536,21 +677,61 @@ impl Runner {
                     ring_progress.set_progress(*progress, true);
                 }
             }
-            Event::BiometricCaptureOcclusion { occlusion_detected } => {
+            LedEvent::BiometricCaptureObjectiveTicks { completed, total } => {
+                // ticks are drawn by the progress slider, set up by the progress event
+                let ring_progress =
+                    self.ring_animations_stack.stack.get_mut(&LEVEL_NOTICE).and_then(
+                        |RunningAnimation { animation, .. }| {
+                            animation.as_any_mut().downcast_mut::<ring::Slider>()
+                        },
+                    );
+                if let Some(ring_progress) = ring_progress {
+                    ring_progress.set_ticks(*completed, *total);
+                }
+            }
+            LedEvent::BiometricCaptureOcclusion { occlusion_detected } => {
+                // the event comes with every frame, the center is only touched on changes
+                let occlusion_shown =
+                    self.center_animations_stack.stack.get(&LEVEL_NOTICE).is_some_and(
+                        |RunningAnimation { animation, .. }| {
+                            animation.as_any().is::<center::Static>()
+                        },
+                    );
                 if *occlusion_detected {
                     self.operator_signup_phase.capture_occlusion_issue();
+                    if !occlusion_shown {
+                        self.set_center(
+                            LEVEL_NOTICE,
+                            center::Static::new(self.palette.occlusion, None),
+                        );
+                    }
                 } else {
                     self.operator_signup_phase.capture_occlusion_ok();
+                    if occlusion_shown {
+                        self.stop_center(LEVEL_NOTICE, true);
+                    }
+                }
+            }
+            LedEvent::BiometricCaptureTimeRemaining { .. } => {
+                // The ring keeps showing the sharpness progress, the remaining
+                // time is not visualized yet.
+            }
+            LedEvent::FaceIllumination { level } => {
+                if *level == 0 {
+                    self.stop_ring(LEVEL_ILLUMINATION, true);
+                } else {
+                    let color = FACE_ILLUMINATION_TINT * (f64::from(*level) / f64::from(u8::MAX));
+                    self.set_ring(LEVEL_ILLUMINATION, ring::Idle::new(Some(color), None));
                 }
             }
-            Event::BiometricCaptureDistance { in_range } => {
+            LedEvent::BiometricCaptureDistance { in_range } => {
                 if *in_range {
                     self.operator_signup_phase.capture_distance_ok();
                 } else {
                     self.operator_signup_phase.capture_distance_issue();
                 }
             }
-            Event::BiometricCaptureSuccess => {
+            LedEvent::BiometricCaptureSuccess => {
                 // set ring to full circle based on previous progress animation
                 // ring will be reset when biometric pipeline starts showing progress
                 let _ = self
@@ -566,7 +747,14 @@ impl Runner {
                 self.stop_center(LEVEL_NOTICE, true);
                 self.operator_signup_phase.iris_scan_complete();
             }
-            Event::BiometricPipelineProgress { progress } => {
+            LedEvent::BiometricCaptureFailed => {
+                let mut arc_dash = ring::ArcDash::new(self.palette.error, 4);
+                arc_dash.set_flash_color(self.palette.error);
+                arc_dash.wave(self.palette.error);
+                self.transition_ring(LEVEL_NOTICE, arc_dash);
+                self.stop_center(LEVEL_NOTICE, true);
+            }
+            LedEvent::BiometricPipelineProgress { progress } => {
                 let ring_animation =
                     self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
                         |RunningAnimation { animation, .. }| {
@@ -589,7 +777,7 @@ impl Runner {
                     self.operator_signup_phase.processing_2();
                 }
             }
-            Event::StartingEnrollment => {
+            LedEvent::StartingEnrollment => {
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
                     |RunningAnimation { animation, .. }| {
                         animation.as_any_mut().downcast_mut::<ring::Progress>()
@@ -600,7 +788,7 @@ impl Runner {
                 }
                 self.operator_signup_phase.uploading();
             }
-            Event::BiometricPipelineSuccess => {
+            LedEvent::BiometricPipelineSuccess => {
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
                     |RunningAnimation { animation, .. }| {
                         animation.as_any_mut().downcast_mut::<ring::Progress>()
@@ -611,7 +799,7 @@ impl Runner {
                 }
                 self.operator_signup_phase.biometric_pipeline_successful();
             }
-            Event::SignupFail => {
+            LedEvent::SignupFail => {
                 self.operator_signup_phase.failure();
 
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
@@ -626,7 +814,7 @@ impl Runner {
                 self.stop_ring(LEVEL_NOTICE, true);
                 self.stop_center(LEVEL_NOTICE, true);
             }
-            Event::SignupSuccess => {
+            LedEvent::SignupSuccess => {
                 self.operator_signup_phase.signup_successful();
 
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
@@ -641,9 +829,12 @@ impl Runner {
 
                 self.stop_ring(LEVEL_NOTICE, true);
                 self.stop_center(LEVEL_NOTICE, true);
-                self.set_ring(LEVEL_FOREGROUND, ring::Idle::new(Some(Rgb::USER_SIGNUP), Some(3.0)));
+                self.set_ring(
+                    LEVEL_FOREGROUND,
+                    ring::Idle::new(Some(self.palette.success), Some(3.0)),
+                );
             }
-            Event::SoftwareVersionDeprecated => {
+            LedEvent::SoftwareVersionDeprecated => {
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
                     |RunningAnimation { animation, .. }| {
                         animation.as_any_mut().downcast_mut::<ring::Progress>()
@@ -656,7 +847,7 @@ impl Runner {
                 self.operator_blink
                     .trigger(Rgb::OPERATOR_VERSIONS_DEPRECATED, vec![0.4, 0.4, 0.4, 0.4, 0.4, 0.4]);
             }
-            Event::SoftwareVersionBlocked => {
+            LedEvent::SoftwareVersionBlocked => {
                 let slider = self.ring_animations_stack.stack.get_mut(&LEVEL_FOREGROUND).and_then(
                     |RunningAnimation { animation, .. }| {
                         animation.as_any_mut().downcast_mut::<ring::Progress>()
@@ -669,42 +860,67 @@ impl Runner {
                 self.operator_blink
                     .trigger(Rgb::OPERATOR_VERSIONS_OUTDATED, vec![0.4, 0.4, 0.4, 0.4, 0.4, 0.4]);
             }
-            Event::Idle => {
+            LedEvent::Idle => {
                 self.stop_ring(LEVEL_FOREGROUND, false);
                 self.stop_center(LEVEL_FOREGROUND, false);
                 self.operator_signup_phase.idle();
             }
-            Event::GoodInternet => {
+            LedEvent::Standby => {
+                self.set_ring(
+                    LEVEL_STANDBY,
+                    ring::Fade::new(
+                        ring::Breathe::new(Rgb::USER_STANDBY, 8.0).with_brightness(0.2, 1.0),
+                        2.0,
+                    ),
+                );
+            }
+            LedEvent::Wake => {
+                self.stop_ring(LEVEL_STANDBY, true);
+            }
+            LedEvent::GoodInternet => {
                 self.operator_connection.good_internet();
             }
-            Event::SlowInternet => {
+            LedEvent::SlowInternet => {
                 self.operator_connection.slow_internet();
             }
-            Event::NoInternet => {
+            LedEvent::NoInternet => {
                 self.operator_connection.no_internet();
             }
-            Event::GoodWlan => {
+            LedEvent::GoodWlan => {
                 self.operator_connection.good_wlan();
             }
-            Event::SlowWlan => {
+            LedEvent::SlowWlan => {
                 self.operator_connection.slow_wlan();
             }
-            Event::NoWlan => {
+            LedEvent::NoWlan => {
                 self.operator_connection.no_wlan();
             }
-            Event::BatteryCapacity { percentage } => {
+            LedEvent::BatteryCapacity { percentage } => {
                 self.operator_battery.capacity(*percentage);
             }
-            Event::BatteryIsCharging { is_charging } => {
+            LedEvent::BatteryIsCharging { is_charging } => {
                 self.operator_battery.set_charging(*is_charging);
             }
-            Event::Pause => {
+            LedEvent::Pause => {
                 self.paused = true;
             }
-            Event::Resume => {
+            LedEvent::Resume => {
                 self.paused = false;
             }
-            Event::RecoveryImage => {
+            LedEvent::RingGamma { gamma } => {
+                self.ring_gamma = *gamma;
+            }
+            LedEvent::Palette { palette } => {
+                self.palette = *palette;
+                self.transition_ring(
+                    LEVEL_BACKGROUND,
+                    ring::Idle::new(Some(self.palette.idle), None),
+                );
+            }
+            LedEvent::FrameRate { .. } => {
+                // Handled by the event loop.
+            }
+            LedEvent::RecoveryImage => {
                 self.set_ring(LEVEL_NOTICE, ring::Spinner::triple(Rgb::USER_RED));
             }
         }
@@ -731,7 +947,8 @@ impl Runner {
         self.ring_animations_stack.run(&mut self.ring_frame, dt);
         time::sleep(Duration::from_millis(2)).await;
         if !self.paused {
-            self.main_mcu.send_uart(mcu::main::Input::RingLeds(self.ring_frame.into()))?;
+            let ring_frame = self.ring_frame.map(|led| led.gamma(self.ring_gamma));
+            self.main_mcu.send_uart(mcu::main::Input::RingLeds(ring_frame.into()))?;
         }
         Ok(())
     }
@@ -740,6 +957,26 @@ impl Runner {
         self.ring_animations_stack.set(level, Box::new(animation));
     }
 
+    // Like `set_ring`, but cross-fades to `animation` if the level is already
+    // running a `ring::RingAnimator`, or fades it in from the current frame.
+    fn transition_ring(&mut self, level: u8, animation: impl Animation<Frame = RingFrame>) {
+        let animator = self
+            .ring_animations_stack
+            .stack
+            .get_mut(&level)
+            .filter(|RunningAnimation { kill, .. }| !*kill)
+            .and_then(|RunningAnimation { animation, .. }| {
+                animation.as_any_mut().downcast_mut::<ring::RingAnimator>()
+            });
+        if let Some(animator) = animator {
+            animator.transition_to(animation);
+        } else {
+            let mut animator = ring::RingAnimator::new(RING_TRANSITION_DURATION);
+            animator.push(animation);
+            self.set_ring(level, animator);
+        }
+    }
+
     fn set_center(&mut self, level: u8, animation: impl Animation<Frame = CenterFrame>) {
         self.center_animations_stack.set(level, Box::new(animation));
     }
@@ -807,3 +1044,45 @@ impl<Frame: 'static> AnimationsStack<Frame> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording() {
+        let recording = Recording::default();
+        let engine = Engine::clone(&recording);
+        engine.biometric_capture_progress(0.5);
+        engine.biometric_capture_occlusion(true);
+        assert_eq!(recording.events(), [
+            LedEvent::BiometricCaptureProgress { progress: 0.5 },
+            LedEvent::BiometricCaptureOcclusion { occlusion_detected: true },
+        ]);
+    }
+    #[test]
+    fn test_palette() {
+        let success = Rgb(0, 40, 10);
+        let mut runner = Runner::new(Box::<mcu::main::Fake>::default());
+        runner
+            .event(&LedEvent::Palette { palette: LedPalette { success, ..LedPalette::default() } });
+        runner.event(&LedEvent::SignupSuccess);
+        let mut frame = [Rgb::OFF; RING_LED_COUNT];
+        runner.ring_animations_stack.run(&mut frame, 0.0);
+        assert!(frame.iter().all(|&led| led == success));
+    }
+
+    #[test]
+    fn test_palette_idle() {
+        let idle = Rgb(3, 2, 1);
+        let mut runner = Runner::new(Box::<mcu::main::Fake>::default());
+        runner.event(&LedEvent::Bootup);
+        runner.event(&LedEvent::Palette { palette: LedPalette { idle, ..LedPalette::default() } });
+        let mut frame = [Rgb(9, 9, 9); RING_LED_COUNT];
+        // The cross-fade starts from the previous idle color.
+        runner.ring_animations_stack.run(&mut frame, RING_TRANSITION_DURATION);
+        assert!(frame.iter().all(|&led| led == LedPalette::default().idle));
+        runner.ring_animations_stack.run(&mut frame, 0.0);
+        assert!(frame.iter().all(|&led| led == idle));
+    }
+}
@@ -0,0 +1,159 @@
+use super::{fade::Blend, Animation};
+use crate::led::{AnimationState, RingFrame};
+use std::{any::Any, collections::VecDeque};
+
+type RingAnimation = Box<dyn Animation<Frame = RingFrame>>;
+
+/// Plays ring animations one after another, cross-fading from the outgoing to
+/// the incoming animation.
+pub struct RingAnimator {
+    duration: f64,
+    current: Option<RingAnimation>,
+    queue: VecDeque<RingAnimation>,
+    fade: Option<CrossFade>,
+}
+
+struct CrossFade {
+    // The outgoing animation, or `None` if it has already finished.
+    animation: Option<RingAnimation>,
+    blend: Blend,
+}
+
+impl RingAnimator {
+    /// Creates a new empty [`RingAnimator`] with cross-fades lasting
+    /// `duration` seconds.
+    #[must_use]
+    pub fn new(duration: f64) -> Self {
+        Self { duration, current: None, queue: VecDeque::new(), fade: None }
+    }
+
+    /// Queues `animation` to play after the current and the already queued
+    /// animations finish.
+    pub fn push(&mut self, animation: impl Animation<Frame = RingFrame>) {
+        if self.current.is_none() {
+            self.start(Box::new(animation), None);
+        } else {
+            self.queue.push_back(Box::new(animation));
+        }
+    }
+
+    /// Drops the queued animations and cross-fades from the current animation
+    /// to `animation` right away.
+    pub fn transition_to(&mut self, animation: impl Animation<Frame = RingFrame>) {
+        self.queue.clear();
+        let outgoing = self.current.take();
+        self.start(Box::new(animation), outgoing);
+    }
+
+    /// Returns the number of animations waiting in the queue.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn start(&mut self, animation: RingAnimation, outgoing: Option<RingAnimation>) {
+        self.current = Some(animation);
+        self.fade = Some(CrossFade { animation: outgoing, blend: Blend::new(self.duration) });
+    }
+}
+
+impl Animation for RingAnimator {
+    type Frame = RingFrame;
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn animate(&mut self, frame: &mut RingFrame, dt: f64, idle: bool) -> AnimationState {
+        let Some(current) = &mut self.current else {
+            return AnimationState::Finished;
+        };
+        if let Some(CrossFade { animation: outgoing, blend }) = &mut self.fade {
+            // The outgoing animation keeps rendering into the frame faded from.
+            let from = blend.source_mut(frame);
+            if let Some(animation) = outgoing.as_mut() {
+                if !animation.animate(from, dt, idle).is_running() {
+                    *outgoing = None;
+                }
+            }
+        }
+        let state = current.animate(frame, dt, idle);
+        if let Some(fade) = &mut self.fade {
+            fade.blend.apply(frame, dt, idle);
+            if fade.blend.is_finished() {
+                self.fade = None;
+            }
+        }
+        if !state.is_running() {
+            self.current = None;
+            if let Some(next) = self.queue.pop_front() {
+                self.start(next, None);
+            }
+        }
+        if self.current.is_some() {
+            AnimationState::Running
+        } else {
+            AnimationState::Finished
+        }
+    }
+
+    fn transition_from(&mut self, superseded: &dyn Any) {
+        if let Some(current) = &mut self.current {
+            current.transition_from(superseded);
+        }
+    }
+
+    fn stop(&mut self) {
+        self.queue.clear();
+        if let Some(current) = &mut self.current {
+            current.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::Solid, *};
+    use crate::mcu::main::{Rgb, RING_LED_COUNT};
+
+    fn is_filled(frame: &RingFrame, color: Rgb) -> bool {
+        frame.iter().all(|&led| led == color)
+    }
+
+    #[test]
+    fn test_transition_to() {
+        let mut animator = RingAnimator::new(1.0);
+        let mut frame = [Rgb(0, 0, 0); RING_LED_COUNT];
+        animator.push(Solid(Rgb(100, 0, 0), None));
+        animator.animate(&mut frame, 1.0, false);
+        assert!(animator.animate(&mut frame, 1.0, false).is_running());
+        assert!(is_filled(&frame, Rgb(100, 0, 0)));
+        animator.transition_to(Solid(Rgb(0, 100, 0), None));
+        animator.animate(&mut frame, 0.5, false);
+        assert!(is_filled(&frame, Rgb(100, 0, 0)));
+        animator.animate(&mut frame, 0.5, false);
+        assert!(is_filled(&frame, Rgb(50, 50, 0)));
+        animator.animate(&mut frame, 0.5, false);
+        assert!(is_filled(&frame, Rgb(0, 100, 0)));
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut animator = RingAnimator::new(0.0);
+        let mut frame = [Rgb(0, 0, 0); RING_LED_COUNT];
+        animator.push(Solid(Rgb(100, 0, 0), Some(1)));
+        animator.push(Solid(Rgb(0, 100, 0), Some(0)));
+        assert_eq!(animator.queued(), 1);
+        assert!(animator.animate(&mut frame, 0.1, false).is_running());
+        assert!(is_filled(&frame, Rgb(100, 0, 0)));
+        assert!(animator.animate(&mut frame, 0.1, false).is_running());
+        assert_eq!(animator.queued(), 0);
+        assert!(!animator.animate(&mut frame, 0.1, false).is_running());
+        assert!(is_filled(&frame, Rgb(0, 100, 0)));
+        assert!(!animator.animate(&mut frame, 0.1, false).is_running());
+    }
+}
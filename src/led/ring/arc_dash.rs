@@ -6,27 +6,45 @@ use crate::{
 use std::{any::Any, f64::consts::PI, ops::Range};
 
 /// Maximum number of arcs.
-pub const MAX_ARC_COUNT: usize = 4;
+pub const MAX_ARC_COUNT: usize = 12;
 
 const WAVE_SPEED: f64 = PI * 2.0 / 3.0; // 3 seconds per blink
 const WAVE_MIN: f64 = 0.1;
 const GAP_SPEED: f64 = PI / 0.175; // 0.175 seconds to grow the gaps
 const FLASH_ON_TIME: f64 = 0.1;
-const FLASH_COLOR: Rgb = Rgb(255, 255, 255);
+const DEFAULT_GAP_RATIO: f64 = 2.5;
+const DEFAULT_FLASH_COLOR: Rgb = Rgb(255, 255, 255);
+
+/// Brightness curve of the [`ArcDash`] wave.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Easing {
+    /// Triangle wave.
+    Linear,
+    /// Raised cosine wave.
+    #[default]
+    Cosine,
+    /// Smoothstep applied to the triangle wave.
+    EaseInOut,
+}
 
 /// Dashed arc.
 pub struct ArcDash {
     color: Rgb,
     arc_count: usize,
+    easing: Easing,
+    flash_color: Rgb,
     flash_phase: Option<f64>,
     wave_phase: Option<f64>,
+    rotation_speed: f64,
     pub(crate) shape: Shape,
 }
 
 #[derive(Clone)]
 pub struct Shape {
     arc_count: usize,
+    gap_ratio: f64,
     gap_phase: f64,
+    rotation_phase: f64,
 }
 
 impl ArcDash {
@@ -41,15 +59,63 @@ impl ArcDash {
         Self {
             color,
             arc_count,
+            easing: Easing::default(),
+            flash_color: DEFAULT_FLASH_COLOR,
             flash_phase: None,
             wave_phase: None,
-            shape: Shape { arc_count, gap_phase: 0.0 },
+            rotation_speed: 0.0,
+            shape: Shape {
+                arc_count,
+                gap_ratio: DEFAULT_GAP_RATIO,
+                gap_phase: 0.0,
+                rotation_phase: 0.0,
+            },
         }
     }
 
+    /// Sets the brightness curve of the wave animation.
+    #[must_use]
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets the rotation speed of the arcs around the ring in radians per
+    /// second. Zero keeps the arcs stationary.
+    #[must_use]
+    pub fn with_rotation_speed(mut self, rotation_speed: f64) -> Self {
+        self.rotation_speed = rotation_speed;
+        self
+    }
+
+    /// Sets the gap size between the arcs. At the full gap, each gap takes
+    /// `2 / gap_ratio` of the arc period, so a greater ratio gives thicker
+    /// dashes.
+    ///
+    /// # Panics
+    ///
+    /// If `gap_ratio` is not greater than 2.0, which would make the gaps
+    /// consume the arcs.
+    #[must_use]
+    pub fn with_gap_ratio(mut self, gap_ratio: f64) -> Self {
+        assert!(gap_ratio > 2.0, "invalid gap ratio: {gap_ratio}");
+        self.shape.gap_ratio = gap_ratio;
+        self
+    }
+
+    /// Sets the color of the flash preceding the wave animation.
+    pub fn set_flash_color(&mut self, color: Rgb) {
+        self.flash_color = color;
+    }
+
     /// Runs the wave animation.
     pub fn wave(&mut self, color: Rgb) {
-        self.shape = Shape { arc_count: self.arc_count, gap_phase: PI };
+        self.shape = Shape {
+            arc_count: self.arc_count,
+            gap_ratio: self.shape.gap_ratio,
+            gap_phase: PI,
+            rotation_phase: self.shape.rotation_phase,
+        };
         self.flash_phase = Some(0.0);
         self.color = color;
     }
@@ -69,10 +135,10 @@ impl Animation for ArcDash {
     fn animate(&mut self, frame: &mut RingFrame, dt: f64, idle: bool) -> AnimationState {
         let mut current_color = self.color;
         if let Some(phase) = &mut self.wave_phase {
-            current_color *= (1.0 - phase.cos()) / 2.0 * (1.0 - WAVE_MIN) + WAVE_MIN;
+            current_color *= self.easing.brightness(*phase);
             *phase = (*phase + dt * WAVE_SPEED) % (PI * 2.0);
         } else if let Some(phase) = &mut self.flash_phase {
-            current_color = FLASH_COLOR;
+            current_color = self.flash_color;
             *phase += dt;
             if *phase >= FLASH_ON_TIME {
                 self.wave_phase = Some(0.0);
@@ -80,6 +146,8 @@ impl Animation for ArcDash {
         } else {
             self.shape.gap_phase = (self.shape.gap_phase + dt * GAP_SPEED).min(PI);
         };
+        self.shape.rotation_phase =
+            (self.shape.rotation_phase + dt * self.rotation_speed).rem_euclid(PI * 2.0);
         if !idle {
             self.shape.render(frame, current_color);
         }
@@ -87,17 +155,100 @@ impl Animation for ArcDash {
     }
 }
 
+impl Easing {
+    /// Returns the brightness multiplier for the wave `phase`, going from
+    /// `WAVE_MIN` at `0` to `1.0` at `PI`.
+    fn brightness(self, phase: f64) -> f64 {
+        let linear = 1.0 - (phase - PI).abs() / PI;
+        let value = match self {
+            Self::Linear => linear,
+            Self::Cosine => (1.0 - phase.cos()) / 2.0,
+            Self::EaseInOut => linear * linear * (3.0 - 2.0 * linear),
+        };
+        value * (1.0 - WAVE_MIN) + WAVE_MIN
+    }
+}
+
 impl Shape {
-    #[allow(clippy::cast_precision_loss)]
     pub fn render(&self, frame: &mut RingFrame, color: Rgb) {
-        let mut ranges: [Range<f64>; MAX_ARC_COUNT] = [0.0..0.0, 0.0..0.0, 0.0..0.0, 0.0..0.0];
-        for (i, range) in ranges.iter_mut().enumerate().take(self.arc_count) {
+        render_lines(frame, Rgb::OFF, color, &self.ranges());
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn ranges(&self) -> Vec<Range<f64>> {
+        let mut ranges = Vec::with_capacity(self.arc_count + 1);
+        for i in 0..self.arc_count {
             let start = PI * 2.0 / self.arc_count as f64 * i as f64
-                + (1.0 - self.gap_phase.cos()) * PI / (self.arc_count as f64 * 2.5);
+                + (1.0 - self.gap_phase.cos()) * PI / (self.arc_count as f64 * self.gap_ratio)
+                + self.rotation_phase;
             let end = PI * 2.0 / self.arc_count as f64 * (i + 1) as f64
-                - (1.0 - self.gap_phase.cos()) * PI / (self.arc_count as f64 * 2.5);
-            *range = start..end;
+                - (1.0 - self.gap_phase.cos()) * PI / (self.arc_count as f64 * self.gap_ratio)
+                + self.rotation_phase;
+            let offset = start - start % (PI * 2.0);
+            let (start, end) = (start - offset, end - offset);
+            if end <= PI * 2.0 {
+                ranges.push(start..end);
+            } else {
+                // The arc crosses the origin, so it's split in two.
+                ranges.push(start..PI * 2.0);
+                ranges.push(0.0..end - PI * 2.0);
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcu::main::RING_LED_COUNT;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_easing_brightness() {
+        for easing in [Easing::Linear, Easing::Cosine, Easing::EaseInOut] {
+            assert_abs_diff_eq!(easing.brightness(0.0), WAVE_MIN, epsilon = 1e-9);
+            assert_abs_diff_eq!(easing.brightness(PI), 1.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_rotation_shifts_ranges() {
+        let mut arc_dash = ArcDash::new(Rgb(255, 255, 255), 2).with_rotation_speed(0.5);
+        arc_dash.shape.gap_phase = PI;
+        let mut frame = [Rgb::OFF; RING_LED_COUNT];
+        let dt = 0.1;
+        let mut previous = arc_dash.shape.ranges();
+        for _ in 0..5 {
+            arc_dash.animate(&mut frame, dt, false);
+            let current = arc_dash.shape.ranges();
+            assert_eq!(current.len(), 2);
+            for (prev, cur) in previous.iter().zip(&current) {
+                assert_abs_diff_eq!(cur.start - prev.start, dt * 0.5, epsilon = 1e-9);
+                assert_abs_diff_eq!(cur.end - prev.end, dt * 0.5, epsilon = 1e-9);
+            }
+            previous = current;
         }
-        render_lines(frame, Rgb::OFF, color, &ranges);
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_gap_ratio() {
+        let mut arc_dash = ArcDash::new(Rgb(255, 255, 255), 4).with_gap_ratio(4.0);
+        arc_dash.shape.gap_phase = PI;
+        let ranges = arc_dash.shape.ranges();
+        assert_eq!(ranges.len(), 4);
+        for (i, range) in ranges.iter().enumerate() {
+            // Each quarter loses an eighth of its length on both sides.
+            let quarter = PI / 2.0 * i as f64;
+            assert_abs_diff_eq!(range.start, quarter + PI / 8.0, epsilon = 1e-9);
+            assert_abs_diff_eq!(range.end, quarter + PI / 2.0 - PI / 8.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid gap ratio")]
+    fn test_invalid_gap_ratio() {
+        let _ = ArcDash::new(Rgb(255, 255, 255), 4).with_gap_ratio(2.0);
     }
 }
@@ -0,0 +1,58 @@
+use super::{render_lines, Animation};
+use crate::{
+    led::{AnimationState, RingFrame},
+    mcu::main::Rgb,
+};
+use std::{any::Any, f64::consts::PI};
+
+const DEFAULT_MIN: f64 = 0.1;
+const DEFAULT_MAX: f64 = 1.0;
+
+/// Whole ring breathing.
+pub struct Breathe {
+    color: Rgb,
+    period: f64,
+    min: f64,
+    max: f64,
+    phase: f64,
+}
+
+impl Breathe {
+    /// Creates a new [`Breathe`] with `period` in seconds per breath.
+    #[must_use]
+    pub fn new(color: Rgb, period: f64) -> Self {
+        Self { color, period, min: DEFAULT_MIN, max: DEFAULT_MAX, phase: 0.0 }
+    }
+
+    /// Sets the brightness range of the breathing.
+    #[must_use]
+    pub fn with_brightness(mut self, min: f64, max: f64) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    fn brightness(&self) -> f64 {
+        self.min + (1.0 - self.phase.cos()) / 2.0 * (self.max - self.min)
+    }
+}
+
+impl Animation for Breathe {
+    type Frame = RingFrame;
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn animate(&mut self, frame: &mut RingFrame, dt: f64, idle: bool) -> AnimationState {
+        if !idle {
+            render_lines(frame, Rgb::OFF, self.color * self.brightness(), &[0.0..PI * 2.0]);
+        }
+        self.phase = (self.phase + dt * PI * 2.0 / self.period) % (PI * 2.0);
+        AnimationState::Running
+    }
+}
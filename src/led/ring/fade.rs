@@ -0,0 +1,104 @@
+use super::Animation;
+use crate::led::{AnimationState, RingFrame};
+use std::any::Any;
+
+/// Cross-fades from the last rendered ring frame to a wrapped animation.
+pub struct Fade<A: Animation<Frame = RingFrame>> {
+    inner: A,
+    blend: Blend,
+}
+
+// Blends the frames of an incoming animation with the frame it replaces over
+// `duration` seconds. Shared with `RingAnimator`.
+pub(super) struct Blend {
+    duration: f64,
+    elapsed: f64,
+    from: Option<RingFrame>,
+}
+
+impl Blend {
+    pub(super) fn new(duration: f64) -> Self {
+        Self { duration, elapsed: 0.0, from: None }
+    }
+
+    // Returns the frame to fade from. It's captured from `frame` on the first
+    // call, as the frame still holds the output of the previous animation.
+    pub(super) fn source_mut(&mut self, frame: &RingFrame) -> &mut RingFrame {
+        self.from.get_or_insert(*frame)
+    }
+
+    // Blends `frame` rendered by the incoming animation with the frame to fade
+    // from, and advances the fade by `dt`.
+    pub(super) fn apply(&mut self, frame: &mut RingFrame, dt: f64, idle: bool) {
+        if let Some(from) = self.from.filter(|_| !idle && self.elapsed < self.duration) {
+            let t = self.elapsed / self.duration;
+            for (led, from) in frame.iter_mut().zip(from) {
+                *led = from.lerp(*led, t);
+            }
+        }
+        self.elapsed += dt;
+    }
+
+    pub(super) fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+impl<A: Animation<Frame = RingFrame>> Fade<A> {
+    /// Creates a new [`Fade`] into `inner` lasting `duration` seconds.
+    #[must_use]
+    pub fn new(inner: A, duration: f64) -> Self {
+        Self { inner, blend: Blend::new(duration) }
+    }
+
+    /// Returns a reference to the wrapped animation.
+    #[must_use]
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+impl<A: Animation<Frame = RingFrame>> Animation for Fade<A> {
+    type Frame = RingFrame;
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn animate(&mut self, frame: &mut RingFrame, dt: f64, idle: bool) -> AnimationState {
+        self.blend.source_mut(frame);
+        let state = self.inner.animate(frame, dt, idle);
+        self.blend.apply(frame, dt, idle);
+        state
+    }
+
+    fn transition_from(&mut self, superseded: &dyn Any) {
+        self.inner.transition_from(superseded);
+    }
+
+    fn stop(&mut self) {
+        self.inner.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::Solid, *};
+    use crate::mcu::main::{Rgb, RING_LED_COUNT};
+
+    #[test]
+    fn test_fade() {
+        let mut fade = Fade::new(Solid(Rgb(100, 100, 100), None), 1.0);
+        let mut frame = [Rgb(0, 0, 0); RING_LED_COUNT];
+        fade.animate(&mut frame, 0.5, false);
+        assert!(frame.iter().all(|&led| led == Rgb(0, 0, 0)));
+        fade.animate(&mut frame, 0.5, false);
+        assert!(frame.iter().all(|&led| led == Rgb(50, 50, 50)));
+        fade.animate(&mut frame, 0.5, false);
+        assert!(frame.iter().all(|&led| led == Rgb(100, 100, 100)));
+    }
+}
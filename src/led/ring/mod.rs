@@ -1,8 +1,11 @@
 //! Animations for the ring LEDs.
 
 mod alert;
+mod animator;
 mod arc_dash;
 mod arc_pulse;
+mod breathe;
+mod fade;
 mod fake_progress;
 mod idle;
 mod progress;
@@ -12,8 +15,11 @@ mod spinner;
 
 pub use self::{
     alert::Alert,
-    arc_dash::{ArcDash, MAX_ARC_COUNT},
+    animator::RingAnimator,
+    arc_dash::{ArcDash, Easing, MAX_ARC_COUNT},
     arc_pulse::ArcPulse,
+    breathe::Breathe,
+    fade::Fade,
     fake_progress::FakeProgress,
     idle::Idle,
     progress::Progress,
@@ -23,21 +29,18 @@ pub use self::{
 };
 
 use super::{Animation, RingFrame};
-use crate::{
-    led::GAMMA,
-    mcu::main::{Rgb, RING_LED_COUNT},
-};
+use crate::mcu::main::{Rgb, RING_LED_COUNT};
 use std::{f64::consts::PI, ops::Range};
 
 const LIGHT_BLEEDING_OFFSET_RAD: f64 = PI / 180.0 * 6.0; // 6° offset of the start to compensate for light bleeding.
 
 /// Renders a set of lines with smooth ends.
 #[allow(clippy::cast_precision_loss)]
-pub fn render_lines<const N: usize>(
+pub fn render_lines(
     frame: &mut RingFrame,
     background: Rgb,
     foreground: Rgb,
-    ranges_angle_rad: &[Range<f64>; N],
+    ranges_angle_rad: &[Range<f64>],
 ) {
     'leds: for (i, led) in frame.iter_mut().enumerate() {
         const LED: f64 = PI * 2.0 / RING_LED_COUNT as f64;
@@ -53,10 +56,46 @@ pub fn render_lines<const N: usize>(
             }
             *led = foreground;
             if start_fill < LED || end_fill < LED {
-                *led *= ((start_fill.min(LED) + end_fill.min(LED) - LED) / LED).powf(GAMMA);
+                // linear fill, the gamma correction is applied on the way to the MCU
+                *led *= (start_fill.min(LED) + end_fill.min(LED) - LED) / LED;
             }
             continue 'leds;
         }
         *led = background;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::led::AnimationState;
+    use std::any::Any;
+
+    /// Fills the ring with one color. Finishes after the given number of
+    /// further frames, or runs forever.
+    pub struct Solid(pub Rgb, pub Option<usize>);
+
+    impl Animation for Solid {
+        type Frame = RingFrame;
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn animate(&mut self, frame: &mut RingFrame, _dt: f64, _idle: bool) -> AnimationState {
+            frame.fill(self.0);
+            match &mut self.1 {
+                Some(0) => AnimationState::Finished,
+                Some(frames) => {
+                    *frames -= 1;
+                    AnimationState::Running
+                }
+                None => AnimationState::Running,
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::{any::Any, f64::consts::PI};
 
 use crate::{
     led::{ring::LIGHT_BLEEDING_OFFSET_RAD, AnimationState, RingFrame},
-    mcu::main::Rgb,
+    mcu::main::{Rgb, RING_LED_COUNT},
 };
 
 use super::{render_lines, Animation, ArcPulse};
@@ -30,6 +30,14 @@ pub struct Slider {
 pub struct Shape {
     progress: f64,
     pulse_phase: Option<f64>,
+    ticks: Option<Ticks>,
+}
+
+// Objective boundaries drawn on top of the slider.
+#[derive(Clone, Copy)]
+struct Ticks {
+    completed: usize,
+    total: usize,
 }
 
 impl Slider {
@@ -40,7 +48,7 @@ impl Slider {
             color,
             progress,
             complete_time: COMPLETE_TIME,
-            shape: Shape { progress, pulse_phase: None },
+            shape: Shape { progress, pulse_phase: None, ticks: None },
         }
     }
 
@@ -50,6 +58,13 @@ impl Slider {
         self.progress = progress.min(upper_bound);
     }
 
+    /// Marks the boundaries between `total` objectives on the slider. The
+    /// boundaries of the `completed` objectives are cut out of the slider, the
+    /// remaining ones are lit ahead of it. `total` of zero removes the marks.
+    pub fn set_ticks(&mut self, completed: usize, total: usize) {
+        self.shape.ticks = (total > 0).then_some(Ticks { completed, total });
+    }
+
     /// Pulses remaining progress.
     #[must_use]
     pub fn pulse_remaining(mut self) -> Self {
@@ -100,7 +115,7 @@ impl Animation for Slider {
 }
 
 impl Shape {
-    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     pub fn render(&self, frame: &mut RingFrame, color: Rgb) {
         let mut progress = self.progress.clamp(0.0, 1.0);
         if progress
@@ -113,5 +128,16 @@ impl Shape {
         let angle = (PI - ARC_LENGTH) * progress + ARC_LENGTH;
         let ranges = [PI - angle..PI, PI..PI + angle];
         render_lines(frame, Rgb::OFF, color, &ranges);
+        if let Some(Ticks { completed, total }) = self.ticks {
+            const LED: f64 = PI * 2.0 / RING_LED_COUNT as f64;
+            for boundary in 1..total {
+                let tick = (PI - ARC_LENGTH) * (boundary as f64 / total as f64) + ARC_LENGTH;
+                let tick_color = if boundary <= completed { Rgb::OFF } else { color };
+                for side in [PI - tick, PI + tick] {
+                    let i = (side / LED).round() as usize % RING_LED_COUNT;
+                    frame[i] = tick_color;
+                }
+            }
+        }
     }
 }
@@ -324,9 +324,31 @@ pub enum IrLed {
 }
 
 /// RGB LED color.
-#[derive(Eq, PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Copy, Clone, Default, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Rgb(pub u8, pub u8, pub u8);
 
+impl Rgb {
+    /// Linearly interpolates each channel from `self` to `other`, with `t`
+    /// clamped to `0.0..=1.0`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn lerp(self, other: Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let channel =
+            |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+        Rgb(channel(self.0, other.0), channel(self.1, other.1), channel(self.2, other.2))
+    }
+
+    /// Applies gamma correction to each channel, mapping linear brightness to
+    /// the LED PWM duty.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn gamma(self, gamma: f64) -> Rgb {
+        let channel = |c: u8| ((f64::from(c) / 255.0).powf(gamma) * 255.0).round() as u8;
+        Rgb(channel(self.0), channel(self.1), channel(self.2))
+    }
+}
+
 impl ops::Mul<f64> for Rgb {
     type Output = Self;
 
@@ -490,6 +512,20 @@ impl From<&orb_messages::mcu_main::Versions> for Versions {
     }
 }
 
+/// Extracts the `(latitude, longitude)` position from a GPS message, if it
+/// contains a fix.
+#[must_use]
+pub fn gps_fix(message: &nmea_parser::ParsedMessage) -> Option<(f64, f64)> {
+    let (latitude, longitude) = match message {
+        nmea_parser::ParsedMessage::Gga(message) => (message.latitude, message.longitude),
+        nmea_parser::ParsedMessage::Gll(message) => (message.latitude, message.longitude),
+        nmea_parser::ParsedMessage::Gns(message) => (message.latitude, message.longitude),
+        nmea_parser::ParsedMessage::Rmc(message) => (message.latitude, message.longitude),
+        _ => (None, None),
+    };
+    latitude.zip(longitude)
+}
+
 impl Interface for Main {
     type Input = Input;
     type Log = Log;
@@ -1137,3 +1173,25 @@ impl Default for Log {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_lerp() {
+        let a = Rgb(10, 200, 0);
+        let b = Rgb(30, 100, 255);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Rgb(20, 150, 128));
+        assert_eq!(a.lerp(b, 2.0), b);
+    }
+
+    #[test]
+    fn test_rgb_gamma() {
+        assert_eq!(Rgb(0, 128, 255).gamma(1.0), Rgb(0, 128, 255));
+        assert_eq!(Rgb(0, 128, 255).gamma(2.2), Rgb(0, 56, 255));
+        assert_eq!(Rgb(10, 64, 192).gamma(2.2), Rgb(0, 12, 137));
+    }
+}
@@ -57,6 +57,7 @@ const SSID_POLLING_DIVIDER: u16 = 5;
 const REPORT_CAPACITY: usize = 10;
 const LAG_FILTER_RC: f64 = 2.0;
 const RSSI_FILTER_RC: f64 = 1.5;
+const PROC_NET_WIRELESS: &str = "/proc/net/wireless";
 
 /// Network monitor trait.
 pub trait Monitor: Stream<Item = Report> + Send + Unpin {
@@ -65,6 +66,10 @@ pub trait Monitor: Stream<Item = Report> + Send + Unpin {
 
     /// Returns the latest network monitor report.
     fn last_report(&mut self) -> Result<Option<&Report>>;
+
+    /// Returns a snapshot of the WiFi connection quality, or `None` if there
+    /// is no WiFi connection information.
+    fn wifi_quality(&mut self) -> Option<WifiSnapshot>;
 }
 
 /// Network monitor for the Orb hardware.
@@ -91,6 +96,21 @@ pub struct Report {
     pub ssid: String,
 }
 
+/// WiFi connection quality snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct WifiSnapshot {
+    /// WiFi SSID name.
+    pub ssid: String,
+    /// Bit rate in Mb/s, if known.
+    pub bit_rate: Option<f64>,
+    /// Link quality, if known.
+    pub link_quality: Option<i64>,
+    /// WiFi Signal level in dBm.
+    pub signal_level: i64,
+    /// Noise level in dBm, if known.
+    pub noise_level: Option<i64>,
+}
+
 struct Reply {
     addr: IpAddr,
     identifier: u16,
@@ -118,6 +138,21 @@ impl Monitor for Jetson {
         }
         Ok(self.last_report.as_ref())
     }
+
+    fn wifi_quality(&mut self) -> Option<WifiSnapshot> {
+        let report = self.last_report().ok()??;
+        let (link_quality, noise_level) = std::fs::read_to_string(PROC_NET_WIRELESS)
+            .ok()
+            .and_then(|wireless| parse_proc_net_wireless(&wireless))
+            .map_or((None, None), |(link, _level, noise)| (Some(link), Some(noise)));
+        Some(WifiSnapshot {
+            ssid: report.ssid.clone(),
+            bit_rate: None,
+            link_quality,
+            signal_level: report.rssi,
+            noise_level,
+        })
+    }
 }
 
 impl Stream for Fake {
@@ -136,6 +171,10 @@ impl Monitor for Fake {
     fn last_report(&mut self) -> Result<Option<&Report>> {
         Ok(None)
     }
+
+    fn wifi_quality(&mut self) -> Option<WifiSnapshot> {
+        None
+    }
 }
 
 /// Network monitor external trigger.
@@ -544,6 +583,14 @@ fn poll_ssid() -> Result<String> {
     }
 }
 
+// Parses link quality, signal level, and noise level of the first interface
+// listed in `/proc/net/wireless`.
+fn parse_proc_net_wireless(wireless: &str) -> Option<(i64, i64, i64)> {
+    let mut fields = wireless.lines().nth(2)?.split_whitespace().skip(2);
+    let mut next = || fields.next()?.trim_end_matches('.').parse().ok();
+    Some((next()?, next()?, next()?))
+}
+
 fn resolve_addr() -> Option<IpAddr> {
     match (NETWORK_MONITOR_HOST.as_str(), 0).to_socket_addrs() {
         Ok(mut addrs) => {
@@ -596,3 +643,19 @@ fn echo_request_v6(
     echo_request.set_checksum(util::checksum(echo_request.packet(), 1));
     echo_request
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net_wireless() {
+        let wireless = "\
+Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+ wlan0: 0000   70.  -40.  -256        0      0      0      0      0        0
+";
+        assert_eq!(parse_proc_net_wireless(wireless), Some((70, -40, -256)));
+        assert_eq!(parse_proc_net_wireless(""), None);
+    }
+}
@@ -2,37 +2,60 @@
 
 use crate::{
     agents::{
-        camera, mirror,
+        camera, distance,
+        ir_auto_focus::FocusStrategy,
+        mirror,
         python::{face_identifier, ir_net, ir_net::EstimateOutput, rgb_net},
     },
     brokers::{BrokerFlow, Orb, OrbPlan},
-    config::Config,
+    config::{BiometricCaptureConfig, Config},
     consts::{
         CONTINUOUS_CALIBRATION_REDUCER, IRIS_BRIGHTNESS_RANGE, IRIS_SCORE_MIN, IRIS_SHARPNESS_MIN,
-        RGB_REDUCED_HEIGHT, RGB_REDUCED_WIDTH, THRESHOLD_OCCLUSION_30,
+        IR_FOCUS_RANGE, IR_LED_MAX_DURATION, IR_LED_MAX_DURATION_740NM, IR_LED_MIN_DURATION,
+        IR_NET_WARMUP_TIMEOUT, RGB_REDUCED_HEIGHT, RGB_REDUCED_WIDTH,
     },
-    ext::broadcast::ReceiverExt as _,
+    ext::{broadcast::ReceiverExt as _, mpsc::SenderExt as _},
+    fisheye,
     logger::{LogOnError, DATADOG, NO_TAGS},
     mcu::{self, main::IrLed},
-    pid::{derivative::LowPassFilter, InstantTimer, Timer},
-    port,
+    pid::derivative::LowPassFilter,
+    port, sound,
+    sound::Melody,
 };
-use eyre::Result;
-use futures::{future::Fuse, prelude::*};
+use eyre::{ensure, Result};
+use futures::{channel::mpsc, future::Fuse, prelude::*};
 use ordered_float::OrderedFloat;
 use rand::random;
 use std::{
     collections::VecDeque,
     mem::take,
+    ops::RangeInclusive,
     pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
     time::{Duration, Instant},
 };
+use thiserror::Error;
 use tokio::time;
 
 /// Minimal viable sharpness.
 pub const MIN_SHARPNESS: f64 = 1.2;
 
+/// Default RC time constant of the sharpness low-pass filter for the progress
+/// bar.
+pub const SHARPNESS_LOW_PASS_FILTER_RC: f64 = 0.2;
+
+/// Default board temperature in degrees Celsius above which the IR LEDs are
+/// paused during the capture.
+pub const OVERHEAT_TEMPERATURE: f64 = 75.0;
+
+/// Default temperature drop in degrees Celsius below [`OVERHEAT_TEMPERATURE`]
+/// required to resume the IR LEDs.
+pub const OVERHEAT_HYSTERESIS: f64 = 5.0;
+
 /// IR frame pixel mean value.
 pub const IR_TARGET_MEAN: f64 = 135.0;
 
@@ -42,6 +65,43 @@ const OCCLUSION_CENTER_LED_LOW_PASS_FILTER_RC: f64 = 0.4;
 /// Delay before the occlusion indicator can turn off after being set.
 const OCCLUSION_INDICATOR_MIN_TIME_INTERVAL: Duration = Duration::from_millis(450);
 
+/// Minimal delay between two voice prompts asking the user to move.
+const DISTANCE_VOICE_MIN_TIME_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Delay without any eye tracking before the mirror starts a scan sweep.
+const MIRROR_SCAN_DELAY: Duration = Duration::from_secs(3);
+
+/// Interval between the mirror scan sweep points.
+const MIRROR_SCAN_STEP_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Number of points in the mirror scan sweep.
+const MIRROR_SCAN_STEPS: usize = 60;
+
+/// Minimal change of the remaining time fraction to update the LEDs.
+const TIME_REMAINING_RESOLUTION: f64 = 0.01;
+
+/// Number of scored frames in an objective before the adaptive gate kicks in.
+const ADAPTIVE_GATE_MIN_FRAMES: usize = 5;
+
+/// Tolerance for the progress phase weights to sum to 1.0.
+const PROGRESS_WEIGHTS_TOLERANCE: f64 = 1e-6;
+
+/// Source of the current time for the biometric capture plan.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// [`Clock`] backed by [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
 /// Biometric data captured for one of the user's eyes.
 #[derive(Debug, Clone, Default)]
 pub struct EyeCapture {
@@ -53,10 +113,70 @@ pub struct EyeCapture {
     pub ir_frame_740nm: Option<camera::ir::Frame>,
     /// IR-Net estimate.
     pub ir_net_estimate: ir_net::EstimateOutput,
+    /// IR exposure applied when the IR frame was captured.
+    pub ir_exposure: u16,
+    /// IR camera gain applied when the IR frame was captured.
+    pub ir_gain: i64,
+    /// Time when the IR camera captured the IR frame.
+    pub ir_timestamp: Option<Instant>,
     /// RGB frame.
     pub rgb_frame: camera::rgb::Frame,
+    /// Time when the RGB camera captured the RGB frame.
+    pub rgb_timestamp: Option<Instant>,
     /// RGB-Net estimate.
     pub rgb_net_estimate: rgb_net::EstimateOutput,
+    /// RGB-Net primary prediction face bounding box.
+    pub rgb_net_bbox: rgb_net::Rectangle,
+    /// RGB-Net primary prediction eye landmarks (left, right).
+    pub rgb_net_eye_landmarks: (rgb_net::Point, rgb_net::Point),
+}
+
+/// Quality of an eye capture against the minimum quality bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityReport {
+    /// IR-Net iris score.
+    pub score: f64,
+    /// Whether the score is at least [`IRIS_SCORE_MIN`].
+    pub score_ok: bool,
+    /// IR-Net fractional sharpness.
+    pub sharpness: f64,
+    /// Whether the sharpness is at least [`IRIS_SHARPNESS_MIN`].
+    pub sharpness_ok: bool,
+    /// IR frame mean brightness.
+    pub brightness: u8,
+    /// Whether the brightness is within [`IRIS_BRIGHTNESS_RANGE`].
+    pub brightness_ok: bool,
+}
+
+impl QualityReport {
+    /// Checks an IR-Net estimate and the corresponding IR frame mean
+    /// brightness against the minimum quality bars.
+    #[must_use]
+    pub fn new(estimate: &ir_net::EstimateOutput, brightness: u8) -> Self {
+        Self {
+            score: estimate.score,
+            score_ok: estimate.score >= IRIS_SCORE_MIN,
+            sharpness: estimate.sharpness,
+            sharpness_ok: estimate.sharpness >= IRIS_SHARPNESS_MIN,
+            brightness,
+            brightness_ok: IRIS_BRIGHTNESS_RANGE.contains(&brightness),
+        }
+    }
+}
+
+impl EyeCapture {
+    /// Returns the quality of this capture against the minimum quality bars.
+    #[must_use]
+    pub fn quality_report(&self) -> QualityReport {
+        QualityReport::new(&self.ir_net_estimate, self.ir_frame.mean())
+    }
+
+    /// Returns `true` if this capture meets all minimum quality bars.
+    #[must_use]
+    pub fn is_acceptable(&self) -> bool {
+        let report = self.quality_report();
+        report.score_ok && report.sharpness_ok && report.brightness_ok
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -85,6 +205,79 @@ pub struct Capture {
     pub longitude: Option<f64>,
 }
 
+impl Capture {
+    /// Feeds the captured IR eye and RGB frames into fake camera ports, left
+    /// eye first, as if they were captured live. The ports are the inner ends
+    /// of the ones given to [`Builder::ir_eye_camera_fake_port`] and
+    /// [`Builder::rgb_camera_fake_port`].
+    ///
+    /// [`Builder::ir_eye_camera_fake_port`]: crate::brokers::Builder::ir_eye_camera_fake_port
+    /// [`Builder::rgb_camera_fake_port`]: crate::brokers::Builder::rgb_camera_fake_port
+    pub async fn replay(
+        &self,
+        ir_eye_camera: &mut port::Inner<camera::ir::Sensor>,
+        rgb_camera: &mut port::Inner<camera::rgb::Sensor>,
+    ) -> Result<()> {
+        for eye in [&self.eye_left, &self.eye_right] {
+            ir_eye_camera.send(port::Output::new(eye.ir_frame.clone())).await?;
+            rgb_camera.send(port::Output::new(eye.rgb_frame.clone())).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns the distance in pixels between the RGB-Net eye landmarks of the
+    /// face self-custody candidate frame. The landmarks are normalized to the
+    /// frame dimensions.
+    ///
+    /// Returns `None` if the landmarks are degenerate, e.g. not detected or
+    /// coincident.
+    #[must_use]
+    pub fn interpupillary_distance_px(&self) -> Option<f64> {
+        let candidate = &self.face_self_custody_candidate;
+        let (left, right) = candidate.rgb_net_eye_landmarks;
+        let width = f64::from(candidate.rgb_frame.width());
+        let height = f64::from(candidate.rgb_frame.height());
+        let distance = ((left.x - right.x) * width).hypot((left.y - right.y) * height);
+        (distance.is_finite() && distance > 0.0).then_some(distance)
+    }
+}
+
+/// Data captured for one of the user's eyes before the capture timed out.
+#[derive(Debug, Clone, Default)]
+pub struct PartialEyeCapture {
+    /// IR frame.
+    pub ir_frame: Option<camera::ir::Frame>,
+    /// IR-Net estimate.
+    pub ir_net_estimate: Option<ir_net::EstimateOutput>,
+    /// RGB frame.
+    pub rgb_frame: Option<camera::rgb::Frame>,
+    /// RGB-Net estimate.
+    pub rgb_net_estimate: Option<rgb_net::EstimateOutput>,
+}
+
+impl PartialEyeCapture {
+    /// Returns `true` if both IR and RGB data were captured for the eye.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.ir_frame.is_some() && self.rgb_frame.is_some()
+    }
+}
+
+/// Whatever data was captured before the capture timed out.
+#[derive(Debug, Clone, Default)]
+pub struct PartialCapture {
+    /// Data for the left eye.
+    pub eye_left: PartialEyeCapture,
+    /// Data for the right eye.
+    pub eye_right: PartialEyeCapture,
+    /// Candidate data for self-custody face.
+    pub face_self_custody_candidate: Option<SelfCustodyCandidate>,
+    /// Average GPS latitude during capture.
+    pub latitude: Option<f64>,
+    /// Average GPS longitude during capture.
+    pub longitude: Option<f64>,
+}
+
 /// Configuration history of the biometric capture.
 #[derive(Debug)]
 pub struct Log {
@@ -96,6 +289,9 @@ pub struct Log {
     pub main_mcu: mcu::main::Log,
     /// Movable mirrors configuration history.
     pub mirror: mirror::Log,
+    /// Ambient light level in lux at the start of each objective, or `None`
+    /// if the board has no ambient light sensor.
+    pub ambient_light: Vec<(IrLed, Option<f64>)>,
 }
 
 /// Biometric capture output.
@@ -103,31 +299,282 @@ pub struct Log {
 pub struct Output {
     /// Biometric data if the capture was successful.
     pub capture: Option<Capture>,
+    /// Partially captured data if the capture timed out.
+    pub partial_capture: Option<PartialCapture>,
+    /// Whether the capture was cancelled through the cancellation flag given
+    /// to [`Plan::run`].
+    pub cancelled: bool,
     /// Configuration history.
     pub log: Log,
+    /// Occlusion filter state at the end of the capture. Can be passed to
+    /// [`Plan::with_occlusion_filter`] to seed a retry.
+    pub occlusion_filter: LowPassFilter,
+    /// Aggregate statistics of the capture.
+    pub summary: CaptureSummary,
+    /// Whether the capture failed and the [`CaptureBudget`] given to
+    /// [`Plan::run_with_budget`] doesn't allow another attempt.
+    pub budget_exhausted: bool,
+}
+
+/// Limits of the biometric capture attempts across retries, see
+/// [`Plan::run_with_budget`].
+#[derive(Clone, Debug)]
+pub struct CaptureBudget {
+    /// Maximum number of attempts.
+    pub max_attempts: usize,
+    /// Maximum cumulative duration of all attempts.
+    pub max_total_duration: Duration,
+    attempts: usize,
+    elapsed: Duration,
+}
+
+impl CaptureBudget {
+    /// Creates a new unused budget.
+    #[must_use]
+    pub fn new(max_attempts: usize, max_total_duration: Duration) -> Self {
+        Self { max_attempts, max_total_duration, attempts: 0, elapsed: Duration::ZERO }
+    }
+
+    /// Returns the number of attempts made so far.
+    #[must_use]
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Returns the cumulative duration of the attempts made so far.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the time left for the next attempts.
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.max_total_duration.saturating_sub(self.elapsed)
+    }
+
+    /// Returns `true` if no more attempts are allowed.
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.attempts >= self.max_attempts || self.remaining().is_zero()
+    }
+
+    fn record(&mut self, duration: Duration) {
+        self.attempts += 1;
+        self.elapsed += duration;
+    }
+}
+
+/// Aggregate statistics of a biometric capture. Per-eye values are indexed as
+/// `[left, right]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CaptureSummary {
+    /// Number of objectives in the capture.
+    pub objectives_total: usize,
+    /// Number of objectives achieved.
+    pub objectives_completed: usize,
+    /// Number of IR-Net estimates seen for the target eye.
+    pub frames_seen_per_eye: [usize; 2],
+    /// Highest IR-Net sharpness seen for the target eye.
+    pub max_sharpness_per_eye: [f64; 2],
+    /// Score of the best face self-custody candidate.
+    pub self_custody_score: Option<f64>,
+    /// Number of GPS fixes received during the capture.
+    pub gps_points: usize,
+    /// Duration of the capture.
+    pub duration: Option<Duration>,
+    /// Whether the capture was successful.
+    pub success: bool,
+}
+
+impl CaptureSummary {
+    /// Logs the summary as a single structured line.
+    pub fn log(&self) {
+        let [frames_seen_left, frames_seen_right] = self.frames_seen_per_eye;
+        let [max_sharpness_left, max_sharpness_right] = self.max_sharpness_per_eye;
+        tracing::info!(
+            objectives_total = self.objectives_total,
+            objectives_completed = self.objectives_completed,
+            frames_seen_left,
+            frames_seen_right,
+            max_sharpness_left,
+            max_sharpness_right,
+            self_custody_score = ?self.self_custody_score,
+            gps_points = self.gps_points,
+            duration = ?self.duration,
+            success = self.success,
+            "Biometric capture summary"
+        );
+    }
+}
+
+/// Error returned by [`Plan::validate`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    /// The plan has no objectives, e.g. it was given no wavelengths.
+    #[error("biometric capture plan has no objectives")]
+    NoObjectives,
+    /// An objective IR LED duration is outside of the range supported for its
+    /// wavelength.
+    #[error("invalid IR LED duration {duration} for {wavelength:?}, expected {range:?}")]
+    InvalidDuration {
+        /// IR LED wavelength of the objective.
+        wavelength: IrLed,
+        /// Requested IR LED duration.
+        duration: u16,
+        /// Supported IR LED duration range.
+        range: RangeInclusive<u16>,
+    },
+    /// An eye has no objectives, e.g. after truncating the plan to
+    /// `max_objectives`.
+    #[error("biometric capture plan has no objectives for an eye (left: {left})")]
+    NoEyeObjectives {
+        /// Whether the eye without objectives is the left one.
+        left: bool,
+    },
+}
+
+/// Weights of the biometric capture phases in the progress bar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressModel {
+    max_progress: f64,
+    objectives: f64,
+    face_identified: f64,
+}
+
+impl Default for ProgressModel {
+    fn default() -> Self {
+        Self { max_progress: 0.8, objectives: 0.6875, face_identified: 0.3125 }
+    }
+}
+
+impl ProgressModel {
+    /// Creates a new progress model. The progress bar reaches `max_progress`
+    /// when all phases are complete. `objectives` and `face_identified` are
+    /// the shares of the phases and must sum to 1.0.
+    pub fn new(max_progress: f64, objectives: f64, face_identified: f64) -> Result<Self> {
+        ensure!((0.0..=1.0).contains(&max_progress), "invalid max progress: {max_progress}");
+        ensure!(
+            objectives >= 0.0 && face_identified >= 0.0,
+            "negative progress weights: {objectives}, {face_identified}"
+        );
+        ensure!(
+            (objectives + face_identified - 1.0).abs() < PROGRESS_WEIGHTS_TOLERANCE,
+            "progress weights don't sum to 1.0: {objectives} + {face_identified}"
+        );
+        Ok(Self { max_progress, objectives, face_identified })
+    }
+
+    /// Returns the progress bar value given the fraction of the objectives
+    /// completed and whether the face was identified.
+    #[must_use]
+    pub fn progress(&self, objectives: f64, face_identified: bool) -> f64 {
+        let face_identified = if face_identified { self.face_identified } else { 0.0 };
+        self.max_progress * (self.objectives * objectives.clamp(0.0, 1.0) + face_identified)
+    }
 }
 
 /// Biometric capture plan.
 #[allow(missing_docs, clippy::struct_excessive_bools)]
 pub struct Plan {
     pub objectives: VecDeque<Objective>,
+    /// When set, once enough frames were seen in the current objective, only
+    /// frames with a score within this fraction of the objective's maximum
+    /// score are stored. `None` keeps only the fixed [`IRIS_SCORE_MIN`] bar.
+    pub adaptive_gate_fraction: Option<f64>,
+    /// When set, IR frames are not stored while the user distance is known and
+    /// outside of this range.
+    pub distance_range: Option<RangeInclusive<f64>>,
+    /// Whether the Orb has a dedicated eye camera for each eye. Otherwise
+    /// objectives targeting both eyes are split into sequential single-eye
+    /// objectives.
+    pub dual_eye_cameras: bool,
+    /// When set, progress events are sent to this channel in addition to the
+    /// LED engine. Events are dropped while the channel is full.
+    pub events: Option<mpsc::Sender<CaptureEvent>>,
     target_left_eye: bool,
+    target_both_eyes: bool,
+    min_sharpness: f64,
+    ir_target_mean: f64,
     timeout: Fuse<Pin<Box<time::Sleep>>>,
+    timeout_duration: Option<Duration>,
+    time_remaining: Option<f64>,
     timed_out: bool,
+    cancel: Option<Arc<AtomicBool>>,
+    cancelled: bool,
     left_ir: Option<FrameInfoIr>,
     left_rgb: Option<FrameInfoRgb>,
     right_ir: Option<FrameInfoIr>,
     right_rgb: Option<FrameInfoRgb>,
+    left_ir_exposure: (u16, i64),
+    right_ir_exposure: (u16, i64),
     self_custody_candidate_rgb: Option<FrameInfoSelfCustodyCandidate>,
     latitude: Option<f64>,
     longitude: Option<f64>,
     gps_points: usize,
     max_sharpness: f64,
+    sharpness_filter: LowPassFilter,
+    sharpness_filter_rc: f64,
+    sharpness_last_time: Option<Instant>,
+    overheat_temperature: f64,
+    overheat_hysteresis: f64,
+    overheated: bool,
+    max_score: f64,
+    scored_frames: usize,
     total_objectives: usize,
-    occlusion_center_led_timer: InstantTimer,
+    objectives_completed: usize,
+    frames_seen_per_eye: [usize; 2],
+    max_sharpness_per_eye: [f64; 2],
+    progress_model: ProgressModel,
+    clock: Box<dyn Clock>,
+    occlusion_last_time: Option<Instant>,
     occlusion_30_filter: LowPassFilter,
+    occlusion_30_filter_seed: Option<LowPassFilter>,
+    occlusion_thresholds: OcclusionThresholds,
     occlusion_indicator_on_time: Option<Instant>,
+    occlusion_detected: bool,
+    distance_zone: Option<distance::Zone>,
+    distance_voice_time: Option<Instant>,
     mirror_offsets: Vec<mirror::Point>,
+    start_time: Option<Instant>,
+    objective_start: Option<(IrLed, Instant)>,
+    ambient_light: Vec<(IrLed, Option<f64>)>,
+    eye_elapsed: [Duration; 2],
+    eye_time: Option<Instant>,
+    mirror_scan: Vec<mirror::Point>,
+    mirror_scan_index: usize,
+    mirror_scan_timer: Pin<Box<time::Sleep>>,
+    face_illumination: u8,
+    face_illuminated: bool,
+    thermal_camera_started: bool,
+}
+
+/// Biometric capture progress event, see [`Plan::events`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CaptureEvent {
+    /// The progress bar value changed.
+    ProgressUpdated(f64),
+    /// The current objective was achieved.
+    ObjectiveCompleted,
+    /// The occlusion indicator was switched on or off.
+    OcclusionChanged(bool),
+    /// The first self-custody candidate was found.
+    SelfCustodyFound,
+}
+
+/// Order of the biometric capture objectives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ObjectiveOrder {
+    /// Captures all wavelengths for one eye before switching to the other eye.
+    #[default]
+    EyeMajor,
+    /// Captures both eyes for one wavelength before switching to the next
+    /// wavelength.
+    WavelengthMajor,
+    /// Captures both eyes at once for each wavelength. Requires
+    /// [`Plan::dual_eye_cameras`], otherwise falls back to
+    /// [`ObjectiveOrder::WavelengthMajor`].
+    Parallel,
 }
 
 /// Biometric capture objective.
@@ -135,25 +582,48 @@ pub struct Plan {
 #[derive(Debug)]
 pub struct Objective {
     pub target_left_eye: bool,
+    pub target_both_eyes: bool,
     pub ir_led_wavelength: IrLed,
     pub ir_led_duration: u16,
     pub only_rgb_net_frames: bool,
 }
 
+// Absolute occlusion 30 values derived from the config.
+struct OcclusionThresholds {
+    threshold: f64,
+    invalid: f64,
+    on: f64,
+    off: f64,
+}
+
+impl OcclusionThresholds {
+    fn new(config: &BiometricCaptureConfig) -> Self {
+        let threshold = config.occlusion_30_threshold;
+        Self {
+            threshold,
+            invalid: threshold * config.occlusion_30_invalid_factor,
+            on: threshold * config.occlusion_30_on_factor,
+            off: threshold * config.occlusion_30_off_factor,
+        }
+    }
+}
+
 type FrameInfoIr = FrameInfo<ir_net::EstimateOutput, camera::ir::Frame>;
 type FrameInfoRgb = FrameInfo<rgb_net::EstimateOutput, camera::rgb::Frame>;
 type FrameInfoSelfCustodyCandidate =
     FrameInfo<face_identifier::types::IsValidOutput, camera::rgb::Frame>;
 
+#[derive(Clone)]
 struct FrameInfo<T, U> {
-    _timestamp: Instant,
+    // Source timestamp of the camera frame.
+    timestamp: Instant,
     estimate: T,
     frame: U,
 }
 
 impl<T, U> FrameInfo<T, U> {
-    fn new(estimate: T, frame: U) -> Self {
-        Self { _timestamp: Instant::now(), estimate, frame }
+    fn new(timestamp: Instant, estimate: T, frame: U) -> Self {
+        Self { timestamp, estimate, frame }
     }
 }
 
@@ -164,41 +634,76 @@ impl OrbPlan for Plan {
         output: port::Output<ir_net::Model>,
         frame: Option<camera::ir::Frame>,
     ) -> Result<BrokerFlow> {
+        let source_ts = output.source_ts;
         match output.value {
             ir_net::Output::Estimate(estimate) => {
+                if self.overheated {
+                    tracing::debug!("IR LEDs are paused due to overheating, skipping frame");
+                    return Ok(BrokerFlow::Continue);
+                }
                 self.update_occlusion(orb, &estimate);
-                if let Some(perceived_side) = estimate.perceived_side {
-                    if perceived_side != i32::from(!self.target_left_eye) {
-                        tracing::debug!("Skipping frame due to target and perceived side mismatch");
-                        return Ok(BrokerFlow::Continue);
-                    }
-                } else {
+                let Some(perceived_side) = estimate.perceived_side else {
                     tracing::debug!("IRNet perceived_side=None, skipping frame");
                     return Ok(BrokerFlow::Continue);
+                };
+                let left_eye =
+                    if self.target_both_eyes { perceived_side == 0 } else { self.target_left_eye };
+                if perceived_side != i32::from(!left_eye) {
+                    tracing::debug!("Skipping frame due to target and perceived side mismatch");
+                    return Ok(BrokerFlow::Continue);
                 }
 
                 self.update_ux(orb, estimate.sharpness);
+                let eye = usize::from(!left_eye);
+                self.frames_seen_per_eye[eye] += 1;
+                self.max_sharpness_per_eye[eye] =
+                    self.max_sharpness_per_eye[eye].max(estimate.sharpness);
 
                 let frame = frame.expect("frame must be set for an estimate output");
-                let valid_capture = estimate.score >= IRIS_SCORE_MIN
-                    && (!orb.ir_auto_exposure.is_enabled()
-                        || IRIS_BRIGHTNESS_RANGE.contains(&frame.mean()));
+                let quality = QualityReport::new(&estimate, frame.mean());
+                self.max_score = self.max_score.max(estimate.score);
+                self.scored_frames += 1;
+                let valid_capture = quality.score_ok
+                    && (!orb.ir_auto_exposure.is_enabled() || quality.brightness_ok)
+                    && adaptive_gate(
+                        self.adaptive_gate_fraction,
+                        self.max_score,
+                        self.scored_frames,
+                        estimate.score,
+                    );
 
-                if valid_capture {
-                    let slot =
-                        if self.target_left_eye { &mut self.left_ir } else { &mut self.right_ir };
+                if valid_capture && !self.is_user_in_range(orb) {
+                    tracing::debug!(
+                        "Skipping frame due to user distance: {:?}",
+                        orb.user_distance()
+                    );
+                    DATADOG
+                        .incr(
+                            "orb.main.count.signup.during.biometric_capture.frame_skipped_distance",
+                            NO_TAGS,
+                        )
+                        .or_log();
+                } else if valid_capture {
+                    let slot = if left_eye { &mut self.left_ir } else { &mut self.right_ir };
                     if slot.is_none() {
                         DATADOG.incr(
                             "orb.main.count.signup.during.biometric_capture.\
                              first_side_sharp_iris_detected",
-                            [format!(
-                                "side:{}",
-                                if self.target_left_eye { "left" } else { "right" }
-                            )],
+                            [format!("side:{}", if left_eye { "left" } else { "right" })],
                         )?;
                     }
-                    tracing::debug!("Found sharp iris: {}", estimate.score);
-                    *slot = Some(FrameInfoIr::new(estimate, frame));
+                    tracing::debug!(
+                        "Found sharp iris: {}, focus: {:?}",
+                        estimate.score,
+                        orb.current_focus()
+                    );
+                    *slot = Some(FrameInfoIr::new(source_ts, estimate, frame));
+                    let exposure_slot = if left_eye {
+                        &mut self.left_ir_exposure
+                    } else {
+                        &mut self.right_ir_exposure
+                    };
+                    *exposure_slot = orb.current_ir_exposure();
                 }
             }
             ir_net::Output::Version(_) => {}
@@ -220,21 +725,45 @@ impl OrbPlan for Plan {
             if let Some(prediction) = estimate.primary() {
                 if prediction.bbox.coordinates.is_correct() {
                     let frame = frame.expect("frame must be set for an estimate output");
-                    let slot =
-                        if self.target_left_eye { &mut self.left_rgb } else { &mut self.right_rgb };
-                    *slot = Some(FrameInfoRgb::new(estimate, frame));
+                    let info = FrameInfoRgb::new(output.source_ts, estimate, frame);
+                    if self.target_both_eyes {
+                        // The RGB camera sees both eyes at once.
+                        self.left_rgb = Some(info.clone());
+                        self.right_rgb = Some(info);
+                    } else {
+                        let slot = if self.target_left_eye {
+                            &mut self.left_rgb
+                        } else {
+                            &mut self.right_rgb
+                        };
+                        *slot = Some(info);
+                    }
                 }
             }
         }
         Ok(BrokerFlow::Continue)
     }
 
+    fn handle_distance(
+        &mut self,
+        orb: &mut Orb,
+        output: port::Output<distance::Agent>,
+    ) -> Result<BrokerFlow> {
+        let distance::DistanceState { meters, zone } = output.value;
+        if let Some(voice) = self.distance_voice(zone) {
+            tracing::debug!("User out of the focus range: {meters:.2} m");
+            orb.sound.build(sound::Type::Voice(voice))?.push()?;
+        }
+        Ok(BrokerFlow::Continue)
+    }
+
     fn handle_face_identifier(
         &mut self,
         orb: &mut Orb,
         output: port::Output<face_identifier::Model>,
         frame: Option<camera::rgb::Frame>,
     ) -> Result<BrokerFlow> {
+        let source_ts = output.source_ts;
         if let face_identifier::Output::IsValidImage(output) = output.value {
             tracing::debug!("Face self-custody frame score: {:?}", output.score);
             if output.error.is_some() {
@@ -251,7 +780,11 @@ impl OrbPlan for Plan {
                         "New face self-custody frame captured with score: {:?}",
                         output.score
                     );
+                    if self.self_custody_candidate_rgb.is_none() {
+                        self.emit(CaptureEvent::SelfCustodyFound);
+                    }
                     self.self_custody_candidate_rgb = Some(FrameInfoSelfCustodyCandidate::new(
+                        source_ts,
                         output,
                         frame.expect("frame must be set for FaceIdentifier::IsValidImage"),
                     ));
@@ -266,17 +799,22 @@ impl OrbPlan for Plan {
     fn poll_extra(&mut self, orb: &mut Orb, cx: &mut Context<'_>) -> Result<BrokerFlow> {
         while let Poll::Ready(output) = orb.main_mcu.rx_mut().next_broadcast().poll_unpin(cx) {
             if let mcu::main::Output::Gps(message) = output? {
-                self.track_gps(message);
+                self.track_gps(&message);
             }
         }
+        self.update_overheat(orb)?;
 
-        let (rgb, ir) = if self.target_left_eye {
-            (&self.left_rgb, &self.left_ir)
+        let left = self.left_rgb.is_some() && self.left_ir.is_some();
+        let right = self.right_rgb.is_some() && self.right_ir.is_some();
+        let complete = if self.target_both_eyes {
+            left && right
+        } else if self.target_left_eye {
+            left
         } else {
-            (&self.right_rgb, &self.right_ir)
+            right
         };
 
-        if let (Some(_rgb), Some(_ir)) = (rgb, ir) {
+        if complete {
             if !self.is_last_objective() {
                 return Ok(BrokerFlow::Break);
             }
@@ -285,6 +823,24 @@ impl OrbPlan for Plan {
             }
         }
 
+        self.poll_mirror_scan(orb, cx)?;
+        self.update_time_remaining(orb);
+
+        if self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            self.cancelled = true;
+            return Ok(BrokerFlow::Break);
+        }
+        self.account_eye_time();
+        if self.eye_timed_out() {
+            let side = if self.target_left_eye { "left" } else { "right" };
+            tracing::info!("Biometric capture {side} eye ran out of its time share");
+            let tags = [format!("side:{side}")];
+            DATADOG
+                .incr("orb.main.count.signup.during.biometric_capture.eye_timeout", tags)
+                .or_log();
+            self.timed_out = true;
+            return Ok(BrokerFlow::Break);
+        }
         if let Poll::Ready(()) = self.timeout.poll_unpin(cx) {
             self.timed_out = true;
             return Ok(BrokerFlow::Break);
@@ -295,53 +851,132 @@ impl OrbPlan for Plan {
 
 impl Plan {
     /// Creates a new biometric capture plan.
+    ///
+    /// The tunables are initialized from the `config` biometric capture
+    /// section, see [`BiometricCaptureConfig`](crate::config::BiometricCaptureConfig).
     #[must_use]
-    pub fn new(wavelengths: &[(IrLed, u16)], timeout: Option<Duration>, _config: &Config) -> Self {
-        let target_left_eye: bool = random();
-        let mut objectives = VecDeque::new();
-        for (target_left_eye, only_rgb_net_frames) in
-            [(target_left_eye, true), (!target_left_eye, false)]
-        {
-            for &(ir_led_wavelength, ir_led_duration) in wavelengths {
-                objectives.push_back(Objective {
-                    target_left_eye,
-                    ir_led_wavelength,
-                    ir_led_duration,
-                    only_rgb_net_frames,
-                });
-            }
-        }
+    pub fn new(
+        wavelengths: &[(IrLed, u16)],
+        order: ObjectiveOrder,
+        timeout: Option<Duration>,
+        config: &Config,
+    ) -> Self {
+        let objectives = objectives(wavelengths, random(), order);
         let total_objectives = objectives.len();
         tracing::debug!("OBJECTIVES {:?}", objectives);
+        let capture_config = &config.biometric_capture;
         Self {
             objectives,
+            adaptive_gate_fraction: capture_config.adaptive_gate_fraction,
+            distance_range: Some(IR_FOCUS_RANGE),
+            dual_eye_cameras: capture_config.dual_eye_cameras,
+            events: None,
             target_left_eye: false,
+            target_both_eyes: false,
+            min_sharpness: capture_config.min_sharpness,
+            ir_target_mean: capture_config.ir_target_mean,
             timeout: timeout
                 .map_or_else(Fuse::terminated, |timeout| Box::pin(time::sleep(timeout)).fuse()),
+            timeout_duration: timeout,
+            time_remaining: None,
             timed_out: false,
+            cancel: None,
+            cancelled: false,
             left_ir: None,
             left_rgb: None,
             right_ir: None,
             right_rgb: None,
+            left_ir_exposure: (0, 0),
+            right_ir_exposure: (0, 0),
             self_custody_candidate_rgb: None,
             latitude: None,
             longitude: None,
             gps_points: 0,
             max_sharpness: 0.0,
+            sharpness_filter: LowPassFilter::default(),
+            sharpness_filter_rc: capture_config.sharpness_filter_rc,
+            sharpness_last_time: None,
+            overheat_temperature: capture_config.overheat_temperature,
+            overheat_hysteresis: capture_config.overheat_hysteresis,
+            overheated: false,
+            max_score: 0.0,
+            scored_frames: 0,
+            objectives_completed: 0,
+            frames_seen_per_eye: [0; 2],
+            max_sharpness_per_eye: [0.0; 2],
             total_objectives,
-            occlusion_center_led_timer: InstantTimer::default(),
+            progress_model: ProgressModel::default(),
+            clock: Box::new(RealClock),
+            occlusion_last_time: None,
             occlusion_30_filter: LowPassFilter::default(),
+            occlusion_30_filter_seed: None,
+            occlusion_thresholds: OcclusionThresholds::new(capture_config),
             occlusion_indicator_on_time: None,
+            occlusion_detected: false,
+            distance_zone: None,
+            distance_voice_time: None,
             mirror_offsets: Vec::new(),
+            start_time: None,
+            objective_start: None,
+            ambient_light: Vec::new(),
+            eye_elapsed: [Duration::ZERO; 2],
+            eye_time: None,
+            mirror_scan: Vec::new(),
+            mirror_scan_index: 0,
+            mirror_scan_timer: Box::pin(time::sleep(MIRROR_SCAN_DELAY)),
+            face_illumination: capture_config.face_illumination,
+            face_illuminated: false,
+            thermal_camera_started: false,
         }
     }
 
+    /// Truncates the objectives to shorten the capture, e.g. for a fast
+    /// re-authentication. The first eye keeps all its objectives and the
+    /// second eye gets the remaining budget. [`Plan::validate`] fails if an
+    /// eye is left without objectives.
+    #[must_use]
+    pub fn with_max_objectives(mut self, max_objectives: usize) -> Self {
+        self.objectives = truncate_objectives(take(&mut self.objectives), max_objectives);
+        self.total_objectives = self.objectives.len();
+        self
+    }
+
+    /// Continues the occlusion moving average of a previous attempt smoothly.
+    /// Otherwise the filter starts with negative occlusion.
+    #[must_use]
+    pub fn with_occlusion_filter(mut self, occlusion_filter: LowPassFilter) -> Self {
+        self.occlusion_30_filter_seed = Some(occlusion_filter);
+        self
+    }
+
+    /// Weights the capture phases in the progress bar. Defaults to
+    /// [`ProgressModel::default`].
+    #[must_use]
+    pub fn with_progress_model(mut self, progress_model: ProgressModel) -> Self {
+        self.progress_model = progress_model;
+        self
+    }
+
+    /// Sets the time source for the plan timings. Defaults to [`RealClock`].
+    #[must_use]
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Runs the biometric capture plan.
     ///
-    /// # Panics
+    /// Setting the optional `cancel` flag aborts the capture on the next
+    /// broker poll, and the output is marked as cancelled.
+    ///
+    /// # Errors
     ///
-    /// If `wavelength` given to the [`Plan::new`] constructor was empty.
-    pub async fn run(mut self, orb: &mut Orb) -> Result<Output> {
+    /// Returns a [`PlanError`] if the plan is invalid, e.g.
+    /// [`PlanError::NoObjectives`] if `wavelength` given to the [`Plan::new`]
+    /// constructor was empty.
+    pub async fn run(mut self, orb: &mut Orb, cancel: Option<Arc<AtomicBool>>) -> Result<Output> {
+        self.validate()?;
+        self.cancel = cancel;
         self.run_pre(orb).await?;
         loop {
             orb.run(&mut self).await?;
@@ -352,33 +987,123 @@ impl Plan {
         self.run_post(orb).await
     }
 
+    /// Checks that the plan has objectives for both eyes and that their IR LED
+    /// durations are supported. Called by [`Plan::run`] before starting the
+    /// capture.
+    pub fn validate(&self) -> Result<(), PlanError> {
+        if self.objectives.is_empty() {
+            return Err(PlanError::NoObjectives);
+        }
+        for left in [true, false] {
+            if !self.objectives.iter().any(|o| o.target_both_eyes || o.target_left_eye == left) {
+                return Err(PlanError::NoEyeObjectives { left });
+            }
+        }
+        for objective in &self.objectives {
+            let range = ir_led_duration_range(objective.ir_led_wavelength);
+            if !range.contains(&objective.ir_led_duration) {
+                return Err(PlanError::InvalidDuration {
+                    wavelength: objective.ir_led_wavelength,
+                    duration: objective.ir_led_duration,
+                    range,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the biometric capture plan as one of the attempts limited by
+    /// `budget`. The plan timeout is shortened to fit into the remaining
+    /// budget time.
+    ///
+    /// Returns an error if the `budget` is already exhausted.
+    pub async fn run_with_budget(
+        mut self,
+        orb: &mut Orb,
+        cancel: Option<Arc<AtomicBool>>,
+        budget: &mut CaptureBudget,
+    ) -> Result<Output> {
+        ensure!(
+            !budget.is_exhausted(),
+            "biometric capture budget exhausted after {} attempts in {:?}",
+            budget.attempts(),
+            budget.elapsed()
+        );
+        let remaining = budget.remaining();
+        if self.timeout_duration.map_or(true, |timeout| timeout > remaining) {
+            self.timeout = Box::pin(time::sleep(remaining)).fuse();
+            self.timeout_duration = Some(remaining);
+        }
+        let mut output = self.run(orb, cancel).await?;
+        budget.record(output.summary.duration.unwrap_or_default());
+        output.budget_exhausted =
+            output.capture.is_none() && !output.cancelled && budget.is_exhausted();
+        if output.budget_exhausted {
+            tracing::info!(
+                "Biometric capture budget exhausted after {} attempts in {:?}",
+                budget.attempts(),
+                budget.elapsed()
+            );
+        }
+        Ok(output)
+    }
+
     pub(crate) async fn run_pre(&mut self, orb: &mut Orb) -> Result<()> {
+        self.start_time = Some(self.clock.now());
         orb.main_mcu.rx_mut().clear()?;
         orb.main_mcu.log_start();
-        orb.enable_ir_net().await?;
+        orb.enable_ir_net_awaiting_warmup(IR_NET_WARMUP_TIMEOUT).await?;
         orb.enable_rgb_net(false).await?; // Forward RGB frames to both RGB-Net and FaceIdentifier.
         orb.start_ir_eye_camera().await?;
         orb.start_ir_face_camera().await?;
         orb.start_rgb_camera().await?;
         if orb.config.lock().await.thermal_camera {
-            orb.start_thermal_camera().await?;
+            // Thermal data is optional, so a missing or flaky sensor shouldn't
+            // abort the signup.
+            match orb.start_thermal_camera().await {
+                Ok(()) => self.thermal_camera_started = true,
+                Err(err) => {
+                    tracing::error!(
+                        "Thermal camera failed to start, capturing without it: {err:?}"
+                    );
+                    DATADOG
+                        .incr(
+                            "orb.main.count.signup.during.biometric_capture.thermal_camera_failed",
+                            NO_TAGS,
+                        )
+                        .or_log();
+                    orb.disable_thermal_camera();
+                }
+            }
         }
         orb.enable_mirror()?;
         orb.enable_distance()?;
-        orb.start_ir_auto_focus(MIN_SHARPNESS, true).await?;
+        orb.start_ir_auto_focus(self.min_sharpness, FocusStrategy::RgbDistanceSeed).await?;
         orb.enable_eye_tracker()?;
         orb.enable_eye_pid_controller()?;
-        orb.start_ir_auto_exposure(IR_TARGET_MEAN).await?;
+        orb.start_ir_auto_exposure(self.ir_target_mean).await?;
         orb.set_fisheye(RGB_REDUCED_WIDTH, RGB_REDUCED_HEIGHT, false).await?;
+        if !self.dual_eye_cameras && self.objectives.iter().any(|o| o.target_both_eyes) {
+            tracing::info!("No dual eye cameras, capturing both eyes sequentially");
+            self.objectives = sequential_objectives(take(&mut self.objectives));
+            self.total_objectives = self.objectives.len();
+        }
         tracing::info!("Starting biometric capture with {} objectives", self.objectives.len());
         assert!(self.set_next_objective(orb).await?, "given no wavelengths");
-        // Start with negative occlusion.
-        self.occlusion_30_filter.reset();
-        self.occlusion_30_filter.add(
-            THRESHOLD_OCCLUSION_30 * 1.5,
-            0.0,
-            OCCLUSION_CENTER_LED_LOW_PASS_FILTER_RC,
-        );
+        self.mirror_scan = mirror::scan_pattern(orb.calibration(), MIRROR_SCAN_STEPS);
+        self.mirror_scan_index = 0;
+        self.mirror_scan_timer.as_mut().reset(time::Instant::now() + MIRROR_SCAN_DELAY);
+        if let Some(occlusion_filter) = self.occlusion_30_filter_seed.take() {
+            self.occlusion_30_filter = occlusion_filter;
+        } else {
+            // Start with negative occlusion.
+            self.occlusion_30_filter.reset();
+            self.occlusion_30_filter.add(
+                self.occlusion_thresholds.threshold * 1.5,
+                0.0,
+                OCCLUSION_CENTER_LED_LOW_PASS_FILTER_RC,
+            );
+        }
         Ok(())
     }
 
@@ -388,6 +1113,12 @@ impl Plan {
             tracing::info!("Biometric capture timeout");
             return Ok(true);
         }
+        if self.cancelled {
+            tracing::info!("Biometric capture cancelled");
+            return Ok(true);
+        }
+        self.objectives_completed += 1;
+        self.emit(CaptureEvent::ObjectiveCompleted);
         if !self.set_next_objective(orb).await? {
             DATADOG.incr(
                 "orb.main.count.signup.during.biometric_capture.both_eye_captured",
@@ -400,6 +1131,16 @@ impl Plan {
     }
 
     pub(crate) async fn run_post(mut self, orb: &mut Orb) -> Result<Output> {
+        self.finish_objective();
+        if self.overheated {
+            orb.resume_ir_led()?;
+        }
+        self.set_face_illumination(orb, false);
+        if self.timed_out {
+            // Let the user know the capture failed while cleaning up.
+            orb.led.biometric_capture_failed();
+            orb.sound.build(sound::Type::Melody(Melody::SoundError))?.cancel_all().push()?;
+        }
         orb.disable_ir_net();
         orb.disable_rgb_net();
         orb.disable_ir_auto_exposure();
@@ -408,7 +1149,7 @@ impl Plan {
         orb.try_enable_ir_auto_focus();
         orb.stop_ir_auto_focus().await?;
         orb.stop_distance().await?;
-        if orb.thermal_camera.is_enabled() {
+        if self.thermal_camera_started && orb.thermal_camera.is_enabled() {
             orb.stop_thermal_camera().await?;
         }
         orb.stop_rgb_camera().await?;
@@ -420,7 +1161,47 @@ impl Plan {
         let log_main_mcu = orb.main_mcu.log_stop();
 
         let mirror_offsets = take(&mut self.mirror_offsets);
-        let capture = self.into_capture();
+        let ambient_light = take(&mut self.ambient_light);
+        let occlusion_filter = self.occlusion_filter_snapshot();
+        let duration = self
+            .start_time
+            .map(|start_time| self.clock.now().saturating_duration_since(start_time));
+        let mut summary = self.summary(duration);
+        let cancelled = self.cancelled;
+        let result = if self.timed_out {
+            "timeout"
+        } else if cancelled {
+            "cancelled"
+        } else {
+            "incomplete"
+        };
+        let (capture, partial_capture) = if cancelled {
+            (None, None)
+        } else if self.timed_out {
+            let partial_capture = self.into_partial_capture();
+            tracing::info!(
+                "Biometric capture timed out with partial data: left eye: {}, right eye: {}, \
+                 self-custody candidate: {}",
+                partial_capture.eye_left.is_complete(),
+                partial_capture.eye_right.is_complete(),
+                partial_capture.face_self_custody_candidate.is_some(),
+            );
+            (None, Some(partial_capture))
+        } else {
+            (self.into_capture(), None)
+        };
+        if let Some(duration) = duration {
+            let result = if capture.is_some() { "success" } else { result };
+            DATADOG
+                .histogram(
+                    "orb.main.timing.biometric_capture.duration_ms",
+                    duration.as_millis().to_string(),
+                    [format!("result:{result}")],
+                )
+                .or_log();
+        }
+        summary.success = capture.is_some();
+        summary.log();
         if capture.is_some() {
             continuous_calibration(orb, mirror_offsets).await?;
         }
@@ -430,40 +1211,99 @@ impl Plan {
             ir_face_camera: log_ir_face_camera,
             main_mcu: log_main_mcu,
             mirror: orb.stop_mirror().await?,
+            ambient_light,
         };
 
-        Ok(Output { capture, log })
+        Ok(Output {
+            capture,
+            partial_capture,
+            cancelled,
+            log,
+            occlusion_filter,
+            summary,
+            budget_exhausted: false,
+        })
+    }
+
+    fn summary(&self, duration: Option<Duration>) -> CaptureSummary {
+        CaptureSummary {
+            objectives_total: self.total_objectives,
+            objectives_completed: self.objectives_completed,
+            frames_seen_per_eye: self.frames_seen_per_eye,
+            max_sharpness_per_eye: self.max_sharpness_per_eye,
+            self_custody_score: self
+                .self_custody_candidate_rgb
+                .as_ref()
+                .and_then(|candidate| candidate.estimate.score),
+            gps_points: self.gps_points,
+            duration,
+            success: false,
+        }
+    }
+
+    /// Returns the current state of the occlusion filter.
+    #[must_use]
+    pub fn occlusion_filter_snapshot(&self) -> LowPassFilter {
+        self.occlusion_30_filter.clone()
     }
 
     fn into_capture(self) -> Option<Capture> {
-        let FrameInfoIr { estimate: left_ir_net_estimate, frame: left_ir_frame, .. } =
-            self.left_ir?;
-        let FrameInfoRgb { estimate: left_rgb_net_estimate, frame: left_rgb_frame, .. } =
-            self.left_rgb?;
-        let FrameInfoIr { estimate: right_ir_net_estimate, frame: right_ir_frame, .. } =
-            self.right_ir?;
-        let FrameInfoRgb { estimate: right_rgb_net_estimate, frame: right_rgb_frame, .. } =
-            self.right_rgb?;
+        let FrameInfoIr {
+            timestamp: left_ir_timestamp,
+            estimate: left_ir_net_estimate,
+            frame: left_ir_frame,
+        } = self.left_ir?;
+        let FrameInfoRgb {
+            timestamp: left_rgb_timestamp,
+            estimate: left_rgb_net_estimate,
+            frame: left_rgb_frame,
+        } = self.left_rgb?;
+        let FrameInfoIr {
+            timestamp: right_ir_timestamp,
+            estimate: right_ir_net_estimate,
+            frame: right_ir_frame,
+        } = self.right_ir?;
+        let FrameInfoRgb {
+            timestamp: right_rgb_timestamp,
+            estimate: right_rgb_net_estimate,
+            frame: right_rgb_frame,
+        } = self.right_rgb?;
         let FrameInfoSelfCustodyCandidate {
             estimate: face_identifier_output,
             frame: self_custody_candidate_rgb_frame,
             ..
         } = self.self_custody_candidate_rgb?;
+        let (left_rgb_net_bbox, left_rgb_net_eye_landmarks) =
+            primary_prediction(&left_rgb_net_estimate)?;
+        let (right_rgb_net_bbox, right_rgb_net_eye_landmarks) =
+            primary_prediction(&right_rgb_net_estimate)?;
         let eye_left = EyeCapture {
             ir_frame: left_ir_frame,
             ir_frame_940nm: None,
             ir_frame_740nm: None,
             ir_net_estimate: left_ir_net_estimate,
+            ir_exposure: self.left_ir_exposure.0,
+            ir_gain: self.left_ir_exposure.1,
+            ir_timestamp: Some(left_ir_timestamp),
             rgb_frame: left_rgb_frame,
+            rgb_timestamp: Some(left_rgb_timestamp),
             rgb_net_estimate: left_rgb_net_estimate,
+            rgb_net_bbox: left_rgb_net_bbox,
+            rgb_net_eye_landmarks: left_rgb_net_eye_landmarks,
         };
         let eye_right = EyeCapture {
             ir_frame: right_ir_frame,
             ir_frame_940nm: None,
             ir_frame_740nm: None,
             ir_net_estimate: right_ir_net_estimate,
+            ir_exposure: self.right_ir_exposure.0,
+            ir_gain: self.right_ir_exposure.1,
+            ir_timestamp: Some(right_ir_timestamp),
             rgb_frame: right_rgb_frame,
+            rgb_timestamp: Some(right_rgb_timestamp),
             rgb_net_estimate: right_rgb_net_estimate,
+            rgb_net_bbox: right_rgb_net_bbox,
+            rgb_net_eye_landmarks: right_rgb_net_eye_landmarks,
         };
         Some(Capture {
             eye_left,
@@ -471,67 +1311,242 @@ impl Plan {
             latitude: self.latitude,
             longitude: self.longitude,
             face_self_custody_candidate: SelfCustodyCandidate {
-                rgb_frame: self_custody_candidate_rgb_frame,
+                rgb_frame: undistort_self_custody_candidate(self_custody_candidate_rgb_frame),
                 rgb_net_eye_landmarks: face_identifier_output.rgb_net_eye_landmarks,
                 rgb_net_bbox: face_identifier_output.rgb_net_bbox,
             },
         })
     }
 
+    /// Returns whatever data was captured so far, without requiring all the
+    /// slots to be filled. Unlike the strict [`Capture`] path, this is used when the
+    /// capture times out.
+    #[must_use]
+    pub fn into_partial_capture(self) -> PartialCapture {
+        fn eye(ir: Option<FrameInfoIr>, rgb: Option<FrameInfoRgb>) -> PartialEyeCapture {
+            let (ir_frame, ir_net_estimate) = ir.map(|ir| (ir.frame, ir.estimate)).unzip();
+            let (rgb_frame, rgb_net_estimate) = rgb.map(|rgb| (rgb.frame, rgb.estimate)).unzip();
+            PartialEyeCapture { ir_frame, ir_net_estimate, rgb_frame, rgb_net_estimate }
+        }
+        PartialCapture {
+            eye_left: eye(self.left_ir, self.left_rgb),
+            eye_right: eye(self.right_ir, self.right_rgb),
+            face_self_custody_candidate: self.self_custody_candidate_rgb.map(
+                |FrameInfoSelfCustodyCandidate { estimate, frame, .. }| SelfCustodyCandidate {
+                    rgb_frame: undistort_self_custody_candidate(frame),
+                    rgb_net_eye_landmarks: estimate.rgb_net_eye_landmarks,
+                    rgb_net_bbox: estimate.rgb_net_bbox,
+                },
+            ),
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+
+    fn is_user_in_range(&self, orb: &Orb) -> bool {
+        match (&self.distance_range, orb.user_distance()) {
+            (Some(distance_range), Some(user_distance)) => distance_range.contains(&user_distance),
+            _ => true,
+        }
+    }
+
     async fn set_next_objective(&mut self, orb: &mut Orb) -> Result<bool> {
+        self.finish_objective();
+        self.account_eye_time();
         if let Some(objective) = self.objectives.pop_front() {
             tracing::info!("Biometric capture objective: {objective:?}");
+            self.objective_start = Some((objective.ir_led_wavelength, self.clock.now()));
+            self.record_ambient_light(orb, objective.ir_led_wavelength);
             self.max_sharpness = 0.0;
+            self.sharpness_filter.reset();
+            self.sharpness_last_time = None;
+            self.max_score = 0.0;
+            self.scored_frames = 0;
             self.target_left_eye = objective.target_left_eye;
+            self.target_both_eyes = objective.target_both_eyes;
             orb.set_target_left_eye(objective.target_left_eye).await?;
             orb.set_ir_wavelength(objective.ir_led_wavelength).await?;
             orb.set_ir_duration(objective.ir_led_duration)?;
             orb.only_rgb_net_frames = objective.only_rgb_net_frames;
+            self.set_face_illumination(orb, !objective.only_rgb_net_frames);
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    // Illuminates the face only during the objectives feeding the face
+    // identifier with the self-custody candidates.
+    fn set_face_illumination(&mut self, orb: &mut Orb, on: bool) {
+        let on = on && self.face_illumination > 0;
+        if on != self.face_illuminated {
+            orb.led.face_illumination(if on { self.face_illumination } else { 0 });
+            self.face_illuminated = on;
+        }
+    }
+
+    fn finish_objective(&mut self) {
+        if let Some((wavelength, start_time)) = self.objective_start.take() {
+            DATADOG
+                .histogram(
+                    "orb.main.timing.biometric_capture.objective.duration_ms",
+                    self.clock.now().saturating_duration_since(start_time).as_millis().to_string(),
+                    [format!("wavelength:{wavelength:?}")],
+                )
+                .or_log();
+        }
+    }
+
+    fn record_ambient_light(&mut self, orb: &Orb, wavelength: IrLed) {
+        let ambient_light = orb.ambient_light();
+        if let Some(ambient_light) = ambient_light {
+            DATADOG
+                .gauge(
+                    "orb.main.gauge.signup.during.biometric_capture.ambient_light",
+                    ambient_light.to_string(),
+                    [format!("wavelength:{wavelength:?}")],
+                )
+                .or_log();
+        }
+        self.ambient_light.push((wavelength, ambient_light));
+    }
+
+    // Adds the time since the last call to the eye targeted by the current
+    // objective. Objectives targeting both eyes are only limited by the global
+    // timeout.
+    fn account_eye_time(&mut self) {
+        let now = self.clock.now();
+        if let Some(last_time) = self.eye_time.replace(now) {
+            if !self.target_both_eyes {
+                self.eye_elapsed[usize::from(!self.target_left_eye)] +=
+                    now.saturating_duration_since(last_time);
+            }
+        }
+    }
+
+    // Checks whether the currently targeted eye spent its share of the timeout.
+    fn eye_timed_out(&self) -> bool {
+        let Some(timeout) = self.timeout_duration else {
+            return false;
+        };
+        if self.target_both_eyes {
+            return false;
+        }
+        let eye = usize::from(!self.target_left_eye);
+        let other_pending = self.objectives.iter().any(|objective| {
+            !objective.target_both_eyes && objective.target_left_eye != (eye == 0)
+        });
+        self.eye_elapsed[eye] >= eye_allowance(timeout, self.eye_elapsed[1 - eye], other_pending)
+    }
+
+    fn update_time_remaining(&mut self, orb: &mut Orb) {
+        let (Some(timeout), Some(start_time)) = (self.timeout_duration, self.start_time) else {
+            return;
+        };
+        let elapsed = self.clock.now().saturating_duration_since(start_time);
+        let fraction = 1.0 - (elapsed.as_secs_f64() / timeout.as_secs_f64()).clamp(0.0, 1.0);
+        if self
+            .time_remaining
+            .map_or(true, |prev| (prev - fraction).abs() >= TIME_REMAINING_RESOLUTION)
+        {
+            self.time_remaining = Some(fraction);
+            orb.led.biometric_capture_time_remaining(fraction);
+        }
+    }
+
+    // Sweeps the mirror through the scan pattern until the eye tracker sets a
+    // mirror point, so the user's eyes have a chance to get into view.
+    fn poll_mirror_scan(&mut self, orb: &mut Orb, cx: &mut Context<'_>) -> Result<()> {
+        if orb.mirror_point.is_some() || self.mirror_scan.is_empty() {
+            return Ok(());
+        }
+        while self.mirror_scan_timer.poll_unpin(cx).is_ready() {
+            let point = self.mirror_scan[self.mirror_scan_index % self.mirror_scan.len()];
+            self.mirror_scan_index += 1;
+            if self.mirror_scan_index == 1 {
+                tracing::info!("No eye tracking, starting mirror scan");
+            }
+            if let Some(mirror) = orb.mirror.enabled() {
+                mirror.send_now(port::Input::new(mirror::Command::SetPoint(point)))?;
+            }
+            self.mirror_scan_timer.as_mut().reset(time::Instant::now() + MIRROR_SCAN_STEP_INTERVAL);
+        }
+        Ok(())
+    }
+
     fn is_last_objective(&self) -> bool {
         self.objectives.is_empty()
     }
 
     #[allow(clippy::cast_precision_loss)]
     fn update_ux(&mut self, orb: &mut Orb, sharpness: f64) {
-        const MAX_PROGRESS: f64 = 0.8;
-        const FACE_IDENTIFIED_PROGRESS: f64 = 0.25;
-        // self.max_sharpness should be monotonic
-        self.max_sharpness = sharpness.max(self.max_sharpness);
+        self.update_max_sharpness(sharpness);
         // one self.objectives has been popped when we first update the UX, so add 1 to its length
         // to take it into account and start the progress bar at 0.0
         let curr_objective_index = self.total_objectives - self.objectives.len() - 1;
         let curr_objective_progress = (self.max_sharpness / IRIS_SCORE_MIN).min(1.0);
         // maximum measured sharpness is used as the progress bar for all the objectives
-        // we keep FACE_IDENTIFIED_PROGRESS for a concurrent process that's taken into account
-        // into the progress bar
+        // the face identification is a concurrent process with its own share of the progress bar
         let total_objective_progress =
             (curr_objective_index as f64 + curr_objective_progress) / self.total_objectives as f64;
-        let progress = (total_objective_progress * (MAX_PROGRESS - FACE_IDENTIFIED_PROGRESS))
-            + self.self_custody_candidate_rgb.as_ref().map_or(0.0, |_| FACE_IDENTIFIED_PROGRESS);
+        let progress = self
+            .progress_model
+            .progress(total_objective_progress, self.self_custody_candidate_rgb.is_some());
         if self.objectives.len() <= self.total_objectives / 2 {
             orb.led.biometric_capture_half_objectives_completed();
         } else if self.objectives.is_empty() {
             orb.led.biometric_capture_all_objectives_completed();
         }
         orb.led.biometric_capture_progress(progress);
+        self.emit(CaptureEvent::ProgressUpdated(progress));
+        orb.led.biometric_capture_objective_ticks(curr_objective_index, self.total_objectives);
     }
 
-    #[allow(clippy::cast_precision_loss)]
-    fn track_gps(&mut self, message: nmea_parser::ParsedMessage) {
-        let (latitude, longitude) = match message {
-            nmea_parser::ParsedMessage::Gga(message) => (message.latitude, message.longitude),
-            nmea_parser::ParsedMessage::Gll(message) => (message.latitude, message.longitude),
-            nmea_parser::ParsedMessage::Gns(message) => (message.latitude, message.longitude),
-            nmea_parser::ParsedMessage::Rmc(message) => (message.latitude, message.longitude),
-            _ => (None, None),
+    // Smooths out the sharpness jitter before updating the monotonic maximum.
+    fn update_max_sharpness(&mut self, sharpness: f64) {
+        if !sharpness.is_finite() {
+            return;
+        }
+        let now = self.clock.now();
+        let dt = self
+            .sharpness_last_time
+            .replace(now)
+            .map_or(0.0, |last_time| now.saturating_duration_since(last_time).as_secs_f64());
+        let filtered = self.sharpness_filter.add(sharpness, dt, self.sharpness_filter_rc);
+        self.max_sharpness = filtered.max(self.max_sharpness);
+    }
+
+    // Pauses the IR LEDs while the board is hotter than the configured
+    // threshold, as a hot liquid lens drifts the focus.
+    fn update_overheat(&mut self, orb: &mut Orb) -> Result<()> {
+        let Some(temperature) = overheat_temperature(orb) else {
+            return Ok(());
         };
-        if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        if !self.overheated && temperature > self.overheat_temperature {
+            tracing::warn!("Board temperature {temperature:.1}°C is too high, pausing IR LEDs");
+            orb.pause_ir_led()?;
+            self.overheated = true;
+            DATADOG
+                .incr("orb.main.count.signup.during.biometric_capture.overheat_paused", NO_TAGS)
+                .or_log();
+        } else if self.overheated
+            && temperature <= self.overheat_temperature - self.overheat_hysteresis
+        {
+            tracing::info!(
+                "Board temperature {temperature:.1}°C is back to normal, resuming IR LEDs"
+            );
+            orb.resume_ir_led()?;
+            self.overheated = false;
+            DATADOG
+                .incr("orb.main.count.signup.during.biometric_capture.overheat_resumed", NO_TAGS)
+                .or_log();
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn track_gps(&mut self, message: &nmea_parser::ParsedMessage) {
+        if let Some((latitude, longitude)) = mcu::main::gps_fix(message) {
             let prev_latitude = self.latitude.unwrap_or(0.0);
             let prev_longitude = self.longitude.unwrap_or(0.0);
             self.gps_points += 1;
@@ -544,31 +1559,214 @@ impl Plan {
 
     // TODO: include the occlusion 90 and make it request the threshold occlusion from the python directly
     fn update_occlusion(&mut self, orb: &mut Orb, estimate: &EstimateOutput) {
-        let dt = self.occlusion_center_led_timer.get_dt().unwrap_or(0.0);
+        let now = self.clock.now();
+        let dt = self
+            .occlusion_last_time
+            .replace(now)
+            .map_or(0.0, |last_time| now.saturating_duration_since(last_time).as_secs_f64());
         let EstimateOutput { mut occlusion_30, sharpness, .. } = *estimate;
         if occlusion_30.is_nan() || sharpness.is_nan() || sharpness < IRIS_SHARPNESS_MIN {
-            occlusion_30 = THRESHOLD_OCCLUSION_30 * 1.05;
+            occlusion_30 = self.occlusion_thresholds.invalid;
         }
         let occlusion_30_low_pass =
             self.occlusion_30_filter.add(occlusion_30, dt, OCCLUSION_CENTER_LED_LOW_PASS_FILTER_RC);
-        // Apply hysteresis and a minimum pulse time.
+        let occlusion_detected = self.occlusion_indicator(occlusion_30_low_pass);
+        orb.led.biometric_capture_occlusion(occlusion_detected);
+        if occlusion_detected != self.occlusion_detected {
+            self.occlusion_detected = occlusion_detected;
+            self.emit(CaptureEvent::OcclusionChanged(occlusion_detected));
+        }
+    }
+
+    // Sends an event to the subscriber, if any. A closed channel unsubscribes.
+    fn emit(&mut self, event: CaptureEvent) {
+        if let Some(events) = &mut self.events {
+            if let Err(err) = events.try_send(event) {
+                if err.is_disconnected() {
+                    self.events = None;
+                }
+            }
+        }
+    }
+
+    // Returns the voice prompt for entering a new distance zone, rate-limited
+    // so the prompts don't overlap.
+    fn distance_voice(&mut self, zone: distance::Zone) -> Option<sound::Voice> {
+        if self.distance_zone.replace(zone) == Some(zone) {
+            return None;
+        }
+        let voice = match zone {
+            distance::Zone::TooClose => sound::Voice::MoveFarther,
+            distance::Zone::TooFar => sound::Voice::MoveCloser,
+            distance::Zone::Optimal => return None,
+        };
+        let now = self.clock.now();
+        if self.distance_voice_time.is_some_and(|time| {
+            now.saturating_duration_since(time) < DISTANCE_VOICE_MIN_TIME_INTERVAL
+        }) {
+            return None;
+        }
+        self.distance_voice_time = Some(now);
+        Some(voice)
+    }
+
+    // Applies hysteresis and a minimum pulse time to the filtered occlusion.
+    fn occlusion_indicator(&mut self, occlusion_30_low_pass: f64) -> bool {
+        let now = self.clock.now();
         let occlusion_detected =
             if let Some(occlusion_indicator_on_time) = self.occlusion_indicator_on_time {
-                occlusion_30_low_pass < THRESHOLD_OCCLUSION_30 * 1.025
-                    || occlusion_indicator_on_time.elapsed() < OCCLUSION_INDICATOR_MIN_TIME_INTERVAL
+                occlusion_30_low_pass < self.occlusion_thresholds.off
+                    || now.saturating_duration_since(occlusion_indicator_on_time)
+                        < OCCLUSION_INDICATOR_MIN_TIME_INTERVAL
             } else {
-                occlusion_30_low_pass < THRESHOLD_OCCLUSION_30 * 0.975
+                occlusion_30_low_pass < self.occlusion_thresholds.on
             };
         if occlusion_detected {
-            self.occlusion_indicator_on_time.get_or_insert_with(Instant::now);
-            orb.led.biometric_capture_occlusion(true);
+            self.occlusion_indicator_on_time.get_or_insert(now);
         } else {
-            orb.led.biometric_capture_occlusion(false);
             self.occlusion_indicator_on_time = None;
         }
+        occlusion_detected
+    }
+}
+
+fn ir_led_duration_range(wavelength: IrLed) -> RangeInclusive<u16> {
+    match wavelength {
+        IrLed::L740 => IR_LED_MIN_DURATION..=IR_LED_MAX_DURATION_740NM,
+        _ => IR_LED_MIN_DURATION..=IR_LED_MAX_DURATION,
+    }
+}
+
+// Splits the timeout evenly between the eyes while the other eye still has
+// objectives ahead. Afterwards the time the other eye didn't use rolls over.
+fn eye_allowance(timeout: Duration, other_elapsed: Duration, other_pending: bool) -> Duration {
+    if other_pending {
+        timeout / 2
+    } else {
+        timeout.saturating_sub(other_elapsed)
+    }
+}
+
+// Returns the hottest of the sensors affecting the capture.
+fn overheat_temperature(orb: &Orb) -> Option<f64> {
+    match (orb.main_mcu_temperature(), orb.liquid_lens_temperature()) {
+        (Some(main_mcu), Some(liquid_lens)) => Some(main_mcu.max(liquid_lens)),
+        (main_mcu, liquid_lens) => main_mcu.or(liquid_lens),
+    }
+}
+
+// Builds the objective sequence starting with the `target_left_eye` eye. Only
+// the first eye objectives use RGB frames exclusively for RGB-Net, while the
+// other eye objectives also feed the face identifier. Both-eye objectives
+// always feed the face identifier.
+fn objectives(
+    wavelengths: &[(IrLed, u16)],
+    target_left_eye: bool,
+    order: ObjectiveOrder,
+) -> VecDeque<Objective> {
+    let eyes = [(target_left_eye, true), (!target_left_eye, false)];
+    let objective =
+        |(target_left_eye, only_rgb_net_frames): (bool, bool),
+         &(ir_led_wavelength, ir_led_duration): &(IrLed, u16)| Objective {
+            target_left_eye,
+            target_both_eyes: false,
+            ir_led_wavelength,
+            ir_led_duration,
+            only_rgb_net_frames,
+        };
+    match order {
+        ObjectiveOrder::EyeMajor => eyes
+            .into_iter()
+            .flat_map(|eye| wavelengths.iter().map(move |wavelength| objective(eye, wavelength)))
+            .collect(),
+        ObjectiveOrder::WavelengthMajor => wavelengths
+            .iter()
+            .flat_map(|wavelength| eyes.into_iter().map(move |eye| objective(eye, wavelength)))
+            .collect(),
+        ObjectiveOrder::Parallel => wavelengths
+            .iter()
+            .map(|wavelength| Objective {
+                target_both_eyes: true,
+                ..objective((target_left_eye, false), wavelength)
+            })
+            .collect(),
     }
 }
 
+// Keeps at most `max_objectives` objectives, all of the first eye ones first
+// and then the second eye ones in order. Both-eye objectives count towards the
+// first eye.
+fn truncate_objectives(
+    objectives: VecDeque<Objective>,
+    max_objectives: usize,
+) -> VecDeque<Objective> {
+    let Some(first_left_eye) = objectives.front().map(|o| o.target_left_eye) else {
+        return objectives;
+    };
+    let is_first_eye = |o: &Objective| o.target_both_eyes || o.target_left_eye == first_left_eye;
+    let first_eye_count = objectives.iter().filter(|o| is_first_eye(o)).count();
+    let mut first_eye_budget = max_objectives;
+    let mut second_eye_budget = max_objectives.saturating_sub(first_eye_count);
+    objectives
+        .into_iter()
+        .filter(|objective| {
+            let budget = if is_first_eye(objective) {
+                &mut first_eye_budget
+            } else {
+                &mut second_eye_budget
+            };
+            let keep = *budget > 0;
+            *budget = budget.saturating_sub(1);
+            keep
+        })
+        .collect()
+}
+
+// Splits each both-eye objective into a pair of single-eye objectives, as
+// they would be built by `ObjectiveOrder::WavelengthMajor`.
+fn sequential_objectives(objectives: VecDeque<Objective>) -> VecDeque<Objective> {
+    objectives
+        .into_iter()
+        .flat_map(|objective| {
+            if objective.target_both_eyes {
+                let first =
+                    Objective { target_both_eyes: false, only_rgb_net_frames: true, ..objective };
+                let second = Objective {
+                    target_left_eye: !first.target_left_eye,
+                    only_rgb_net_frames: false,
+                    ..first
+                };
+                vec![first, second]
+            } else {
+                vec![objective]
+            }
+        })
+        .collect()
+}
+
+// Extracts the face bounding box and the eye landmarks of the primary
+// prediction. `handle_rgb_net` only stores estimates with a correct primary
+// prediction.
+fn primary_prediction(
+    estimate: &rgb_net::EstimateOutput,
+) -> Option<(rgb_net::Rectangle, (rgb_net::Point, rgb_net::Point))> {
+    let prediction = estimate.primary()?;
+    let bbox = prediction.bbox.coordinates;
+    bbox.is_correct()
+        .then_some((bbox, (prediction.landmarks.left_eye, prediction.landmarks.right_eye)))
+}
+
+// The main RGB pipeline runs without undistortion for RGB-Net, so only the
+// chosen self-custody candidate is undistorted.
+fn undistort_self_custody_candidate(frame: camera::rgb::Frame) -> camera::rgb::Frame {
+    let config = fisheye::Config::default();
+    fisheye::undistort_frame(&frame, &config)
+        .map_err(|err| {
+            tracing::error!("Failed to undistort self-custody candidate frame: {err:?}");
+        })
+        .unwrap_or(frame)
+}
+
 /// Performs light re-calibration at the end of each successful biometric
 /// capture.
 ///
@@ -606,7 +1804,325 @@ pub async fn continuous_calibration(
     let mut calibration = orb.calibration().clone();
     calibration.mirror.horizontal_offset += horizontal * CONTINUOUS_CALIBRATION_REDUCER;
     calibration.mirror.vertical_offset += vertical * CONTINUOUS_CALIBRATION_REDUCER;
+    if let Err(err) = calibration.validate() {
+        tracing::warn!("Clamping continuous calibration: {err}");
+        calibration.clamp();
+    }
     calibration.store().await?;
     orb.recalibrate(calibration).await?;
     Ok(())
 }
+
+/// Returns `true` if `score` passes the adaptive gate given the maximum score
+/// and the number of frames seen in the current objective.
+fn adaptive_gate(fraction: Option<f64>, max_score: f64, frames: usize, score: f64) -> bool {
+    fraction
+        .map_or(true, |fraction| frames < ADAPTIVE_GATE_MIN_FRAMES || score >= max_score * fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::THRESHOLD_OCCLUSION_30;
+    use std::sync::Mutex;
+
+    fn sequence(order: ObjectiveOrder) -> Vec<(bool, IrLed, bool)> {
+        let wavelengths = [(IrLed::L850, 100), (IrLed::L940, 200)];
+        summary(objectives(&wavelengths, true, order))
+    }
+
+    fn summary(objectives: VecDeque<Objective>) -> Vec<(bool, IrLed, bool)> {
+        objectives
+            .into_iter()
+            .map(|objective| {
+                (
+                    objective.target_left_eye,
+                    objective.ir_led_wavelength,
+                    objective.only_rgb_net_frames,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_objective_order() {
+        assert_eq!(sequence(ObjectiveOrder::EyeMajor), [
+            (true, IrLed::L850, true),
+            (true, IrLed::L940, true),
+            (false, IrLed::L850, false),
+            (false, IrLed::L940, false),
+        ]);
+        assert_eq!(sequence(ObjectiveOrder::WavelengthMajor), [
+            (true, IrLed::L850, true),
+            (false, IrLed::L850, false),
+            (true, IrLed::L940, true),
+            (false, IrLed::L940, false),
+        ]);
+        assert!(sequence(ObjectiveOrder::Parallel)
+            .iter()
+            .all(|&(left, _, only_rgb)| left && !only_rgb));
+    }
+
+    #[test]
+    fn test_sequential_fallback() {
+        let wavelengths = [(IrLed::L850, 100), (IrLed::L940, 200)];
+        let parallel = objectives(&wavelengths, true, ObjectiveOrder::Parallel);
+        assert!(parallel.iter().all(|objective| objective.target_both_eyes));
+        let sequential = sequential_objectives(parallel);
+        assert!(sequential.iter().all(|objective| !objective.target_both_eyes));
+        assert_eq!(summary(sequential), sequence(ObjectiveOrder::WavelengthMajor));
+    }
+
+    #[test]
+    fn test_truncate_objectives() {
+        let wavelengths = [(IrLed::L850, 100), (IrLed::L940, 200)];
+        let truncated = |order, max_objectives| {
+            summary(truncate_objectives(objectives(&wavelengths, true, order), max_objectives))
+        };
+        assert_eq!(truncated(ObjectiveOrder::EyeMajor, 3), [
+            (true, IrLed::L850, true),
+            (true, IrLed::L940, true),
+            (false, IrLed::L850, false),
+        ]);
+        assert_eq!(truncated(ObjectiveOrder::WavelengthMajor, 3), [
+            (true, IrLed::L850, true),
+            (false, IrLed::L850, false),
+            (true, IrLed::L940, true),
+        ]);
+        assert_eq!(truncated(ObjectiveOrder::EyeMajor, 1), [(true, IrLed::L850, true)]);
+        assert_eq!(truncated(ObjectiveOrder::Parallel, 1).len(), 1);
+        assert_eq!(truncated(ObjectiveOrder::EyeMajor, 10), sequence(ObjectiveOrder::EyeMajor));
+    }
+
+    #[test]
+    fn test_interpupillary_distance() {
+        let mut capture = Capture::default();
+        assert_eq!(capture.interpupillary_distance_px(), None);
+        let candidate = &mut capture.face_self_custody_candidate;
+        candidate.rgb_frame =
+            camera::rgb::Frame::from_vec(vec![0; 400 * 200 * 3], Duration::ZERO, 400, 200);
+        candidate.rgb_net_eye_landmarks =
+            (rgb_net::Point { x: 0.6, y: 0.5 }, rgb_net::Point { x: 0.3, y: 0.25 });
+        let distance = capture.interpupillary_distance_px().unwrap();
+        assert!((distance - 130.0).abs() < 1e-9);
+        capture.face_self_custody_candidate.rgb_net_eye_landmarks.0.x = f64::NAN;
+        assert_eq!(capture.interpupillary_distance_px(), None);
+    }
+
+    #[test]
+    fn test_progress_model() {
+        let model = ProgressModel::default();
+        assert!((model.progress(0.0, false)).abs() < f64::EPSILON);
+        assert!((model.progress(1.0, false) - 0.55).abs() < 1e-9);
+        assert!((model.progress(0.0, true) - 0.25).abs() < 1e-9);
+        assert!((model.progress(1.0, true) - 0.8).abs() < 1e-9);
+        assert!((model.progress(2.0, true) - 0.8).abs() < 1e-9);
+        assert_eq!(ProgressModel::new(0.8, 0.6875, 0.3125).unwrap(), model);
+        assert!(ProgressModel::new(0.8, 0.5, 0.25).is_err());
+        assert!(ProgressModel::new(0.8, 1.5, -0.5).is_err());
+        assert!(ProgressModel::new(1.5, 0.5, 0.5).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_emit() {
+        let mut plan =
+            Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &Config::default());
+        plan.emit(CaptureEvent::ObjectiveCompleted);
+        let (tx, mut rx) = mpsc::channel(1);
+        plan.events = Some(tx);
+        plan.emit(CaptureEvent::ProgressUpdated(0.5));
+        plan.emit(CaptureEvent::SelfCustodyFound);
+        plan.emit(CaptureEvent::SelfCustodyFound);
+        assert_eq!(rx.try_next().unwrap(), Some(CaptureEvent::ProgressUpdated(0.5)));
+        assert_eq!(rx.try_next().unwrap(), Some(CaptureEvent::SelfCustodyFound));
+        assert!(rx.try_next().is_err());
+        drop(rx);
+        plan.emit(CaptureEvent::OcclusionChanged(true));
+        assert!(plan.events.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_capture_summary() {
+        let mut plan = Plan::new(
+            &[(IrLed::L850, 100), (IrLed::L940, 200)],
+            ObjectiveOrder::default(),
+            None,
+            &Config::default(),
+        );
+        plan.objectives_completed = 3;
+        plan.frames_seen_per_eye = [10, 7];
+        plan.max_sharpness_per_eye = [1.5, 2.5];
+        plan.gps_points = 2;
+        let summary = plan.summary(Some(Duration::from_secs(5)));
+        assert_eq!(summary, CaptureSummary {
+            objectives_total: 4,
+            objectives_completed: 3,
+            frames_seen_per_eye: [10, 7],
+            max_sharpness_per_eye: [1.5, 2.5],
+            self_custody_score: None,
+            gps_points: 2,
+            duration: Some(Duration::from_secs(5)),
+            success: false,
+        });
+    }
+
+    #[test]
+    fn test_capture_budget() {
+        let mut budget = CaptureBudget::new(3, Duration::from_secs(60));
+        assert!(!budget.is_exhausted());
+        budget.record(Duration::from_secs(20));
+        budget.record(Duration::from_secs(20));
+        assert_eq!(budget.attempts(), 2);
+        assert_eq!(budget.remaining(), Duration::from_secs(20));
+        assert!(!budget.is_exhausted());
+        budget.record(Duration::from_secs(5));
+        assert!(budget.is_exhausted());
+        let mut budget = CaptureBudget::new(3, Duration::from_secs(60));
+        budget.record(Duration::from_secs(61));
+        assert_eq!(budget.remaining(), Duration::ZERO);
+        assert!(budget.is_exhausted());
+    }
+
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<Instant>>);
+
+    impl FakeClock {
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_occlusion_hysteresis() {
+        const ON: f64 = THRESHOLD_OCCLUSION_30 * 0.9;
+        const OFF: f64 = THRESHOLD_OCCLUSION_30 * 1.1;
+        let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+        let mut plan =
+            Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &Config::default())
+                .with_clock(Box::new(clock.clone()));
+        assert!(!plan.occlusion_indicator(OFF));
+        assert!(plan.occlusion_indicator(ON));
+        clock.advance(OCCLUSION_INDICATOR_MIN_TIME_INTERVAL - Duration::from_millis(1));
+        assert!(plan.occlusion_indicator(OFF));
+        clock.advance(Duration::from_millis(1));
+        assert!(!plan.occlusion_indicator(OFF));
+        assert!(!plan.occlusion_indicator(THRESHOLD_OCCLUSION_30));
+        assert!(plan.occlusion_indicator(ON));
+    }
+
+    #[tokio::test]
+    async fn test_distance_voice() {
+        let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+        let mut plan =
+            Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &Config::default())
+                .with_clock(Box::new(clock.clone()));
+        assert_eq!(plan.distance_voice(distance::Zone::TooFar), Some(sound::Voice::MoveCloser));
+        assert_eq!(plan.distance_voice(distance::Zone::TooFar), None);
+        assert_eq!(plan.distance_voice(distance::Zone::Optimal), None);
+        assert_eq!(plan.distance_voice(distance::Zone::TooClose), None);
+        clock.advance(DISTANCE_VOICE_MIN_TIME_INTERVAL);
+        assert_eq!(plan.distance_voice(distance::Zone::TooClose), None);
+        assert_eq!(plan.distance_voice(distance::Zone::Optimal), None);
+        assert_eq!(plan.distance_voice(distance::Zone::TooClose), Some(sound::Voice::MoveFarther));
+    }
+
+    #[tokio::test]
+    async fn test_occlusion_thresholds_config() {
+        let mut config = Config::default();
+        config.biometric_capture.occlusion_30_threshold = 0.5;
+        config.biometric_capture.occlusion_30_on_factor = 0.8;
+        config.biometric_capture.occlusion_30_off_factor = 1.2;
+        let mut plan = Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &config);
+        assert!(!plan.occlusion_indicator(0.45));
+        assert!(plan.occlusion_indicator(0.35));
+        plan.occlusion_indicator_on_time = Some(Instant::now() - Duration::from_secs(1));
+        assert!(plan.occlusion_indicator(0.55));
+        assert!(!plan.occlusion_indicator(0.65));
+    }
+
+    #[tokio::test]
+    async fn test_capture_config() {
+        let mut config = Config::default();
+        config.biometric_capture.adaptive_gate_fraction = Some(0.9);
+        config.biometric_capture.dual_eye_cameras = true;
+        config.biometric_capture.min_sharpness = 1.5;
+        let plan = Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &config);
+        assert_eq!(plan.adaptive_gate_fraction, Some(0.9));
+        assert!(plan.dual_eye_cameras);
+        assert!((plan.min_sharpness - 1.5).abs() < f64::EPSILON);
+        assert!((plan.ir_target_mean - IR_TARGET_MEAN).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let plan = |wavelengths: &[(IrLed, u16)]| {
+            Plan::new(wavelengths, ObjectiveOrder::default(), None, &Config::default())
+        };
+        assert!(plan(&[(IrLed::L850, 100), (IrLed::L740, 10000)]).validate().is_ok());
+        assert_eq!(plan(&[]).validate(), Err(PlanError::NoObjectives));
+        assert_eq!(
+            plan(&[(IrLed::L940, 5)]).validate(),
+            Err(PlanError::InvalidDuration {
+                wavelength: IrLed::L940,
+                duration: 5,
+                range: IR_LED_MIN_DURATION..=IR_LED_MAX_DURATION,
+            })
+        );
+        assert!(plan(&[(IrLed::L850, 10000)]).validate().is_err());
+        let truncated = |max_objectives| {
+            Plan::new(
+                &[(IrLed::L850, 100), (IrLed::L940, 200)],
+                ObjectiveOrder::default(),
+                None,
+                &Config::default(),
+            )
+            .with_max_objectives(max_objectives)
+        };
+        let plan = truncated(3);
+        assert!(plan.validate().is_ok());
+        assert_eq!(plan.total_objectives, 3);
+        assert!(matches!(truncated(2).validate(), Err(PlanError::NoEyeObjectives { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_max_sharpness_filter() {
+        let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+        let mut config = Config::default();
+        config.biometric_capture.sharpness_filter_rc = 0.3;
+        let mut plan = Plan::new(&[(IrLed::L850, 100)], ObjectiveOrder::default(), None, &config)
+            .with_clock(Box::new(clock.clone()));
+        plan.update_max_sharpness(1.0);
+        assert!((plan.max_sharpness - 1.0).abs() < f64::EPSILON);
+        clock.advance(Duration::from_millis(100));
+        plan.update_max_sharpness(3.0);
+        // A single spike moves the filtered value by dt / (rc + dt).
+        assert!((plan.max_sharpness - 1.5).abs() < 1e-9);
+        clock.advance(Duration::from_millis(100));
+        plan.update_max_sharpness(f64::NAN);
+        plan.update_max_sharpness(0.0);
+        assert!((plan.max_sharpness - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_eye_allowance() {
+        let timeout = Duration::from_secs(10);
+        assert_eq!(eye_allowance(timeout, Duration::from_secs(1), true), Duration::from_secs(5));
+        assert_eq!(eye_allowance(timeout, Duration::from_secs(1), false), Duration::from_secs(9));
+        assert_eq!(eye_allowance(timeout, Duration::from_secs(5), false), Duration::from_secs(5));
+        assert_eq!(eye_allowance(timeout, Duration::ZERO, false), timeout);
+    }
+
+    #[test]
+    fn test_adaptive_gate() {
+        assert!(adaptive_gate(None, 1.0, 100, 0.1));
+        assert!(adaptive_gate(Some(0.9), 1.0, ADAPTIVE_GATE_MIN_FRAMES - 1, 0.1));
+        assert!(!adaptive_gate(Some(0.9), 1.0, ADAPTIVE_GATE_MIN_FRAMES, 0.8));
+        assert!(adaptive_gate(Some(0.9), 1.0, ADAPTIVE_GATE_MIN_FRAMES, 0.95));
+    }
+}
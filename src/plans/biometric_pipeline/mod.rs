@@ -214,33 +214,11 @@ impl Plan {
             face_left: capture.eye_left.rgb_frame.clone(),
             face_right: capture.eye_right.rgb_frame.clone(),
             face_self_custody_candidate: capture.face_self_custody_candidate.rgb_frame.clone(),
-            face_bbox_left: capture
-                .eye_left
-                .rgb_net_estimate
-                .primary()
-                .expect("prediction should be guaranteed by capture phase")
-                .bbox
-                .coordinates,
-            face_bbox_right: capture
-                .eye_right
-                .rgb_net_estimate
-                .primary()
-                .expect("prediction should be guaranteed by capture phase")
-                .bbox
-                .coordinates,
+            face_bbox_left: capture.eye_left.rgb_net_bbox,
+            face_bbox_right: capture.eye_right.rgb_net_bbox,
             face_bbox_self_custody_candidate: capture.face_self_custody_candidate.rgb_net_bbox,
-            eye_landmarks_left: capture
-                .eye_left
-                .rgb_net_estimate
-                .primary()
-                .map(|prediction| (prediction.landmarks.left_eye, prediction.landmarks.right_eye))
-                .expect("prediction should be guaranteed by capture phase"),
-            eye_landmarks_right: capture
-                .eye_right
-                .rgb_net_estimate
-                .primary()
-                .map(|prediction| (prediction.landmarks.left_eye, prediction.landmarks.right_eye))
-                .expect("prediction should be guaranteed by capture phase"),
+            eye_landmarks_left: capture.eye_left.rgb_net_eye_landmarks,
+            eye_landmarks_right: capture.eye_right.rgb_net_eye_landmarks,
             eye_landmarks_self_custody_candidate: capture
                 .face_self_custody_candidate
                 .rgb_net_eye_landmarks,
@@ -337,6 +337,13 @@ fn make_signature(user_qr_code: &qr_scan::user::Data, pipeline: &Pipeline) -> Re
     ctx.update(pipeline.v2.eye_right.iris_code.as_bytes());
     ctx.update(pipeline.v2.eye_right.mask_code.as_bytes());
     ctx.update(pipeline.v2.eye_right.iris_code_version.as_bytes());
-    let signed = secure_element::sign(ctx.finish())?;
+    let digest = ctx.finish();
+    let signed = match secure_element::try_sign(digest) {
+        Err(err) if err.is_transient() => {
+            tracing::warn!("Retrying signature after transient failure: {err}");
+            secure_element::sign(digest)?
+        }
+        result => result?,
+    };
     Ok(BASE64.encode(&signed))
 }
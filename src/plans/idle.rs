@@ -26,6 +26,7 @@ impl OrbPlan for Plan {
             match output? {
                 // Detect short button press to start a new signup.
                 mcu::main::Output::Button(true) => {
+                    orb.mark_activity();
                     self.is_pressed = true;
                     self.press_time = Some(SystemTime::now());
                 }
@@ -8,9 +8,8 @@ use crate::{
     calibration::Calibration,
     config::Config,
     consts::{
-        BIOMETRIC_CAPTURE_TIMEOUT, DBUS_SIGNUP_OBJECT_PATH, DEFAULT_IR_LED_DURATION,
-        DEFAULT_IR_LED_WAVELENGTH, EXTRA_IR_LED_WAVELENGTHS, IR_CAMERA_FRAME_RATE,
-        QR_SCAN_INTERVAL, QR_SCAN_TIMEOUT,
+        DBUS_SIGNUP_OBJECT_PATH, DEFAULT_IR_LED_DURATION, DEFAULT_IR_LED_WAVELENGTH,
+        EXTRA_IR_LED_WAVELENGTHS, IR_CAMERA_FRAME_RATE, QR_SCAN_INTERVAL, QR_SCAN_TIMEOUT,
     },
     dbus,
     debug_report::{self, DebugReport},
@@ -136,11 +135,7 @@ impl MasterPlan {
 
     /// Runs the high-level plan of the orb.
     pub async fn run(&mut self, orb: &mut Orb) -> Result<bool> {
-        let dbus = orb
-            .dbus_conn
-            .as_ref()
-            .map(|conn| zbus::SignalContext::new(conn, DBUS_SIGNUP_OBJECT_PATH))
-            .transpose()?;
+        let mut dbus = signup_dbus_context(orb)?;
         self.reset_hardware(orb, Duration::from_secs(10)).await?;
         loop {
             orb.led.idle();
@@ -152,6 +147,10 @@ impl MasterPlan {
                 }
             }
 
+            if dbus.is_none() && orb.retry_dbus().await {
+                dbus = signup_dbus_context(orb)?;
+            }
+
             DATADOG.incr("orb.main.count.signup.during.general.signup_started", NO_TAGS).or_log();
             let mut debug_report = None;
             let success = Box::pin(self.do_signup(orb, &mut debug_report, dbus.as_ref())).await?;
@@ -573,12 +572,15 @@ impl MasterPlan {
         let t = Instant::now();
         let mut wavelengths = vec![(DEFAULT_IR_LED_WAVELENGTH, DEFAULT_IR_LED_DURATION)];
         wavelengths.extend_from_slice(EXTRA_IR_LED_WAVELENGTHS);
+        let config = orb.config.lock().await.clone();
         let plan = biometric_capture::Plan::new(
             &wavelengths,
-            Some(BIOMETRIC_CAPTURE_TIMEOUT),
-            &orb.config.lock().await.clone(),
+            biometric_capture::ObjectiveOrder::default(),
+            Some(config.biometric_capture.timeout),
+            &config,
         );
-        let biometric_capture::Output { capture, log: bio_capture_log } = plan.run(orb).await?;
+        let biometric_capture::Output { capture, log: bio_capture_log, .. } =
+            plan.run(orb, None).await?;
         DATADOG
             .timing("orb.main.time.signup.biometric_capture", inst_elapsed!(t), NO_TAGS)
             .or_log();
@@ -1103,6 +1105,16 @@ async fn try_upload_iris_normalized_images(
     Ok(Some(res?))
 }
 
+// Returns the signal context for the signup dbus service, if the dbus
+// connection is available.
+fn signup_dbus_context(orb: &Orb) -> Result<Option<zbus::SignalContext<'static>>> {
+    Ok(orb
+        .dbus_conn
+        .as_ref()
+        .map(|conn| zbus::SignalContext::new(conn, DBUS_SIGNUP_OBJECT_PATH))
+        .transpose()?)
+}
+
 async fn check_signup_conditions(orb: &mut Orb) -> Result<bool> {
     if let Some(report) = orb.net_monitor.last_report()? {
         // Drop the mutex lock fast.
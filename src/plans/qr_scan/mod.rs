@@ -18,9 +18,12 @@ use tokio::time;
 use tokio_stream::wrappers::IntervalStream;
 
 pub mod operator;
+pub mod payload;
 pub mod user;
 pub mod wifi;
 
+pub use self::payload::{QrError, QrPayload};
+
 /// QR-code scanning schema.
 pub trait Schema: Send + Sized {
     /// Returns the sound to tell the user which kind of QR-code is expected.
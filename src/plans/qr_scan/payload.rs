@@ -0,0 +1,115 @@
+//! Tagged QR-code payloads.
+//!
+//! A tagged payload has the form `<tag>.<body>.<checksum>`, where `<tag>` is
+//! either `op` for operator QR-codes or `user` for user QR-codes, `<body>` is
+//! the QR-code value understood by the corresponding [`Schema`], and
+//! `<checksum>` is the first 4 bytes of SHA-256 of `<tag>.<body>` encoded as
+//! lowercase hex.
+
+use super::{operator, user, Schema};
+use ring::digest::{digest, SHA256};
+use std::str::FromStr;
+use thiserror::Error;
+
+const OPERATOR_TAG: &str = "op";
+const USER_TAG: &str = "user";
+const CHECKSUM_LEN: usize = 4;
+
+/// Parsed QR-code payload.
+#[derive(Clone, Debug)]
+pub enum QrPayload {
+    /// Operator QR-code.
+    Operator(operator::Data),
+    /// User QR-code.
+    User(user::Data),
+}
+
+/// Error returned when parsing a QR-code payload.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    /// The payload doesn't have the `<tag>.<body>.<checksum>` structure.
+    #[error("QR-code payload is not tagged")]
+    Untagged,
+    /// The payload tag is not known.
+    #[error("unknown QR-code payload tag: {0}")]
+    UnknownTag(String),
+    /// The payload checksum doesn't match its content.
+    #[error("QR-code payload checksum mismatch")]
+    Checksum,
+    /// The payload body doesn't match the schema of its tag.
+    #[error("malformed QR-code payload body")]
+    Malformed,
+}
+
+impl QrPayload {
+    /// Parses a decoded QR-code string into a typed payload.
+    pub fn parse(code: &str) -> Result<Self, QrError> {
+        let (tagged, expected) = code.rsplit_once('.').ok_or(QrError::Untagged)?;
+        let (tag, body) = tagged.split_once('.').ok_or(QrError::Untagged)?;
+        if tag != OPERATOR_TAG && tag != USER_TAG {
+            return Err(QrError::UnknownTag(tag.to_owned()));
+        }
+        if !expected.eq_ignore_ascii_case(&checksum(tagged)) {
+            return Err(QrError::Checksum);
+        }
+        if tag == OPERATOR_TAG {
+            operator::Data::try_parse(body).map(Self::Operator)
+        } else {
+            user::Data::try_parse(body).map(Self::User)
+        }
+        .ok_or(QrError::Malformed)
+    }
+}
+
+impl FromStr for QrPayload {
+    type Err = QrError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Self::parse(code)
+    }
+}
+
+fn checksum(tagged: &str) -> String {
+    hex::encode(&digest(&SHA256, tagged.as_bytes()).as_ref()[..CHECKSUM_LEN])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(tag: &str, body: &str) -> String {
+        let tagged = format!("{tag}.{body}");
+        let checksum = checksum(&tagged);
+        format!("{tagged}.{checksum}")
+    }
+
+    #[test]
+    fn test_valid_payloads() {
+        let code = tag("op", operator::DUMMY_OPERATOR_QR_CODE);
+        assert!(matches!(
+            QrPayload::parse(&code),
+            Ok(QrPayload::Operator(operator::Data::Normal(_)))
+        ));
+        let code = tag("op", "magic_action:reset_wifi_credentials");
+        assert!(matches!(
+            QrPayload::parse(&code),
+            Ok(QrPayload::Operator(operator::Data::MagicResetWifi))
+        ));
+        let code = tag("user", user::DUMMY_USER_QR_CODE);
+        let Ok(QrPayload::User(data)) = QrPayload::parse(&code) else {
+            panic!("expected a user payload");
+        };
+        assert_eq!(data.user_id, "cf37084e-5087-484c-b5a3-3ca3c34016d1");
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        assert_eq!(QrPayload::parse("random_text").unwrap_err(), QrError::Untagged);
+        let code = tag("admin", user::DUMMY_USER_QR_CODE);
+        assert_eq!(QrPayload::parse(&code).unwrap_err(), QrError::UnknownTag("admin".into()));
+        let code = format!("user.{}.00000000", user::DUMMY_USER_QR_CODE);
+        assert_eq!(QrPayload::parse(&code).unwrap_err(), QrError::Checksum);
+        let code = tag("user", "magic_action:reset_wifi_credentials");
+        assert_eq!(QrPayload::parse(&code).unwrap_err(), QrError::Malformed);
+    }
+}
@@ -10,7 +10,8 @@ use crate::{
 };
 use eyre::{bail, Result};
 use futures::prelude::*;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::time;
 
 const CPU_OVERLOAD_THRESHOLD: f64 = 0.8;
 
@@ -64,6 +65,23 @@ impl OrbPlan for Plan {
 }
 
 impl Plan {
+    /// Warms up the already enabled IR-Net model alone, waiting up to
+    /// `timeout` for the response. Returns `false` if the response didn't
+    /// arrive in time.
+    pub async fn run_ir_net(orb: &mut Orb, timeout: Duration) -> Result<bool> {
+        let mut plan = Self {
+            ir_net_estimate_received: false,
+            rgb_net_estimate_received: true,
+            face_identifier_response_received: true,
+        };
+        let fence = Instant::now();
+        plan.ir_net_warmup(orb).await?;
+        match time::timeout(timeout, orb.run_with_fence(&mut plan, fence)).await {
+            Ok(result) => result.map(|()| true),
+            Err(_) => Ok(false),
+        }
+    }
+
     /// Runs the warm up plan.
     pub async fn run(&mut self, orb: &mut Orb) -> Result<()> {
         tracing::info!("Started warming up");
@@ -1,54 +1,470 @@
 //! Secure Element interface.
 
+use crate::identification::ORB_ID;
 use close_fds::close_open_fds;
-use data_encoding::BASE64;
-use eyre::{bail, Result, WrapErr};
+use data_encoding::{DecodeError, BASE64};
+use eyre::{ensure, eyre, Result, WrapErr};
+use once_cell::sync::OnceCell;
+use ring::{
+    digest::{Context, Digest, SHA256},
+    signature::{Ed25519KeyPair, KeyPair as _},
+};
+use serde::Serialize;
 use std::{
+    fs, io,
     io::prelude::*,
+    ops::RangeInclusive,
     os::unix::process::CommandExt,
-    process::{Command, Stdio},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use thiserror::Error;
+
+/// Default timeout for the `orb-sign-iris-code` process.
+pub const SIGN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Development-only ed25519 key in PKCS#8 format, used when signing without a
+// secure element is explicitly allowed.
+const INSECURE_KEY_PATH: &str = "/usr/persistent/insecure-signing-key.pk8";
+
+// Marker in the `orb-sign-iris-code` error output when the chip is missing.
+const NO_DEVICE_MARKER: &str = "no device";
+
+const BATCH_FLAG: &str = "--batch";
+const BATCH_PROBE_FLAG: &str = "--supports-batch";
+// Line printed by `orb-sign-iris-code --supports-batch` when the batch mode is
+// supported. Older versions may ignore the flag and exit successfully, so the
+// exit code alone is not enough.
+const BATCH_PROBE_RESPONSE: &str = "batch";
+
+static BATCH_SUPPORTED: OnceCell<bool> = OnceCell::new();
+
+/// Signature scheme of the signatures returned by `orb-sign-iris-code`.
+/// Signatures not matching this scheme are rejected, see
+/// [`SignatureScheme::check`].
+pub const SIGNATURE_SCHEME: SignatureScheme = SignatureScheme::EcdsaP256Der;
+
+// DER tag of the ECDSA signature `SEQUENCE { r INTEGER, s INTEGER }`.
+const DER_SEQUENCE_TAG: u8 = 0x30;
+
+/// Signature scheme, which determines the expected signature length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureScheme {
+    /// DER-encoded ECDSA P-256 signature.
+    EcdsaP256Der,
+    /// Ed25519 signature.
+    Ed25519,
+}
+
+/// Error returned by the Secure Element signing process.
+#[derive(Error, Debug)]
+pub enum SignError {
+    /// `orb-sign-iris-code` couldn't be spawned.
+    #[error("running orb-sign-iris-code: {0}")]
+    Spawn(#[source] io::Error),
+    /// `orb-sign-iris-code` exited with a non-zero exit code.
+    #[error("orb-sign-iris-code exited with non-zero exit code: {0}")]
+    NonZeroExit(i32),
+    /// `orb-sign-iris-code` was terminated by a signal.
+    #[error("orb-sign-iris-code terminated by signal")]
+    Signal,
+    /// `orb-sign-iris-code` didn't complete in time and was killed.
+    #[error("orb-sign-iris-code timed out after {0:?}")]
+    Timeout(Duration),
+    /// `orb-sign-iris-code` output is not valid base64.
+    #[error("decoding orb-sign-iris-code output: {0}")]
+    Decode(#[from] DecodeError),
+    /// Communication with `orb-sign-iris-code` failed.
+    #[error("communicating with orb-sign-iris-code: {0}")]
+    Io(#[from] io::Error),
+    /// `orb-sign-iris-code` couldn't find the secure element.
+    #[error("orb-sign-iris-code found no secure element")]
+    NoDevice,
+    /// `orb-sign-iris-code` output is not a complete signature, e.g. due to a
+    /// partial read.
+    #[error("orb-sign-iris-code returned a malformed {scheme:?} signature of {len} bytes")]
+    Malformed {
+        /// Expected signature scheme.
+        scheme: SignatureScheme,
+        /// Length of the decoded signature.
+        len: usize,
+    },
+}
+
+/// Iris code signed by the Secure Element, bundled with the signing context.
+/// See [`SignedIrisCode::to_json`] for the form submitted to the backend.
+#[derive(Clone, Debug)]
+pub struct SignedIrisCode {
+    /// Raw iris code template.
+    pub code: Vec<u8>,
+    /// Signature of [`SignedIrisCode::digest`].
+    pub signature: Vec<u8>,
+    /// Identifier of the signing Orb.
+    pub orb_id: String,
+    /// Signing time in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Scheme of `signature`.
+    pub scheme: SignatureScheme,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignedIrisCodeJson<'a> {
+    scheme: &'static str,
+    orb_id: &'a str,
+    timestamp: u64,
+    code: String,
+    signature: String,
+}
+
+impl SignatureScheme {
+    /// Returns the scheme identifier used in the signed payloads.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::EcdsaP256Der => "ecdsa_p256_der",
+            Self::Ed25519 => "ed25519",
+        }
+    }
+
+    /// Returns the allowed signature length in bytes.
+    #[must_use]
+    pub fn len_range(self) -> RangeInclusive<usize> {
+        match self {
+            // Two up to 33-byte integers with their headers.
+            Self::EcdsaP256Der => 8..=72,
+            Self::Ed25519 => 64..=64,
+        }
+    }
+
+    /// Checks that `signature` is a complete signature of this scheme.
+    pub fn check(self, signature: &[u8]) -> Result<(), SignError> {
+        let len = signature.len();
+        let complete = self.len_range().contains(&len)
+            && match self {
+                Self::EcdsaP256Der => {
+                    signature[0] == DER_SEQUENCE_TAG && usize::from(signature[1]) + 2 == len
+                }
+                Self::Ed25519 => true,
+            };
+        if complete {
+            Ok(())
+        } else {
+            Err(SignError::Malformed { scheme: self, len })
+        }
+    }
+}
+
+impl SignedIrisCode {
+    /// Signs the iris `code` with the Secure Element, together with the Orb ID
+    /// and the current time.
+    pub fn sign(code: Vec<u8>) -> Result<Self> {
+        let timestamp = u64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis())?;
+        let mut signed = Self {
+            code,
+            signature: Vec::new(),
+            orb_id: ORB_ID.as_str().to_owned(),
+            timestamp,
+            scheme: SIGNATURE_SCHEME,
+        };
+        signed.signature = sign(signed.digest())?;
+        Ok(signed)
+    }
+
+    /// Returns the signed digest. It covers the scheme, the Orb ID, the
+    /// timestamp, and the code, each prefixed with its length.
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        let mut ctx = Context::new(&SHA256);
+        for field in [
+            self.scheme.as_str().as_bytes(),
+            self.orb_id.as_bytes(),
+            &self.timestamp.to_be_bytes(),
+            &self.code,
+        ] {
+            ctx.update(&(field.len() as u64).to_be_bytes());
+            ctx.update(field);
+        }
+        ctx.finish()
+    }
+
+    /// Serializes into the canonical JSON form submitted to the backend. The
+    /// fields are in a fixed order, and the binary ones are base64-encoded.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&SignedIrisCodeJson {
+            scheme: self.scheme.as_str(),
+            orb_id: &self.orb_id,
+            timestamp: self.timestamp,
+            code: BASE64.encode(&self.code),
+            signature: BASE64.encode(&self.signature),
+        })
+        .expect("signed iris code serialization failed")
+    }
+}
+
+impl SignError {
+    /// Returns `true` if the error is transient and the signing can be retried.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Spawn(_))
+    }
+
+    /// Returns `true` if there is no secure element to sign with.
+    #[must_use]
+    pub fn is_no_device(&self) -> bool {
+        match self {
+            Self::NoDevice => true,
+            Self::Spawn(err) => err.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
 
 /// Signs this buffer with Secure Element and returns the output.
 pub fn sign<T: AsRef<[u8]>>(data: T) -> Result<Vec<u8>> {
-    fn inner(data: &[u8]) -> Result<Vec<u8>> {
-        let encoded = BASE64.encode(data);
-
-        tracing::info!("Running orb-sign-iris-code");
-        let mut command = Command::new("orb-sign-iris-code");
-        command.stdin(Stdio::piped());
-        command.stdout(Stdio::piped());
-        command.stderr(Stdio::piped());
-        unsafe {
-            command.pre_exec(|| {
-                close_open_fds(libc::STDERR_FILENO + 1, &[]);
-                Ok(())
-            });
-        }
-        let mut child = command.spawn().wrap_err("running orb-sign-iris-code")?;
-
-        let mut stdin = child.stdin.take().unwrap();
-        stdin.write_all(encoded.as_bytes())?;
+    sign_with_timeout(data, SIGN_TIMEOUT)
+}
+
+/// Signs this buffer with Secure Element and returns the output. The signing
+/// process is killed if it doesn't complete within `timeout`.
+pub fn sign_with_timeout<T: AsRef<[u8]>>(data: T, timeout: Duration) -> Result<Vec<u8>> {
+    Ok(try_sign_with_timeout(data, timeout)?)
+}
+
+/// Same as [`sign`], but when `allow_insecure` is set and there is no secure
+/// element, falls back to a development-only software ed25519 key. Production
+/// callers must pass `false`.
+pub fn sign_allow_insecure<T: AsRef<[u8]>>(data: T, allow_insecure: bool) -> Result<Vec<u8>> {
+    match try_sign(&data) {
+        Err(err) if allow_insecure && err.is_no_device() => {
+            tracing::warn!(
+                "No secure element ({err}), signing with the insecure software key from \
+                 {INSECURE_KEY_PATH}. The signature is NOT hardware-backed"
+            );
+            sign_insecure(data.as_ref())
+        }
+        result => Ok(result?),
+    }
+}
+
+/// Same as [`sign`], but returns a structured [`SignError`].
+pub fn try_sign<T: AsRef<[u8]>>(data: T) -> Result<Vec<u8>, SignError> {
+    try_sign_with_timeout(data, SIGN_TIMEOUT)
+}
+
+/// Same as [`sign_with_timeout`], but returns a structured [`SignError`].
+pub fn try_sign_with_timeout<T: AsRef<[u8]>>(
+    data: T,
+    timeout: Duration,
+) -> Result<Vec<u8>, SignError> {
+    let output = run(&[], BASE64.encode(data.as_ref()).as_bytes(), timeout)?;
+    let signature = BASE64.decode(&output)?;
+    SIGNATURE_SCHEME.check(&signature)?;
+    Ok(signature)
+}
+
+/// Signs multiple buffers with a single Secure Element process and returns the
+/// outputs in the same order. Fails if any of the items fails.
+///
+/// Falls back to signing the items one by one if `orb-sign-iris-code` doesn't
+/// support the batch mode.
+#[allow(clippy::cast_possible_truncation)]
+pub fn sign_batch(items: &[Vec<u8>]) -> Result<Vec<Vec<u8>>> {
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !*BATCH_SUPPORTED.get_or_init(probe_batch) {
+        return items.iter().map(sign).collect();
+    }
+    let mut input = String::new();
+    for item in items {
+        input.push_str(&BASE64.encode(item));
+        input.push('\n');
+    }
+    let output = run(&[BATCH_FLAG], input.as_bytes(), SIGN_TIMEOUT * items.len() as u32)?;
+    let signatures = output
+        .split(|&byte| byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| -> Result<_, SignError> {
+            let signature = BASE64.decode(line)?;
+            SIGNATURE_SCHEME.check(&signature)?;
+            Ok(signature)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    ensure!(
+        signatures.len() == items.len(),
+        "orb-sign-iris-code returned {} signatures for {} items",
+        signatures.len(),
+        items.len()
+    );
+    Ok(signatures)
+}
+
+fn sign_insecure(data: &[u8]) -> Result<Vec<u8>> {
+    let pkcs8 = fs::read(INSECURE_KEY_PATH)
+        .wrap_err_with(|| format!("reading insecure signing key {INSECURE_KEY_PATH}"))?;
+    let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&pkcs8)
+        .map_err(|err| eyre!("parsing insecure signing key: {err}"))?;
+    tracing::debug!(
+        "Insecure signing public key: {}",
+        BASE64.encode(key_pair.public_key().as_ref())
+    );
+    Ok(key_pair.sign(data).as_ref().to_vec())
+}
+
+fn probe_batch() -> bool {
+    let supported = match run(&[BATCH_PROBE_FLAG], &[], SIGN_TIMEOUT) {
+        Ok(output) => String::from_utf8_lossy(&output).trim() == BATCH_PROBE_RESPONSE,
+        Err(err) => {
+            tracing::warn!("Probing orb-sign-iris-code batch mode: {err}");
+            false
+        }
+    };
+    tracing::info!("orb-sign-iris-code batch mode supported: {supported}");
+    supported
+}
+
+// Runs `orb-sign-iris-code` with `input` written to its stdin and returns its
+// stdout.
+fn run(args: &[&str], input: &[u8], timeout: Duration) -> Result<Vec<u8>, SignError> {
+    tracing::info!("Running orb-sign-iris-code");
+    let mut command = Command::new("orb-sign-iris-code");
+    command.args(args);
+    command.stdin(Stdio::piped());
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+    unsafe {
+        command.pre_exec(|| {
+            close_open_fds(libc::STDERR_FILENO + 1, &[]);
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().map_err(SignError::Spawn)?;
+
+    let stdout = read_in_background(child.stdout.take().unwrap());
+    let stderr = read_in_background(child.stderr.take().unwrap());
+
+    let mut stdin = child.stdin.take().unwrap();
+    if let Err(err) = stdin.write_all(input) {
         drop(stdin);
+        kill(&mut child)?;
+        return Err(err.into());
+    }
+    drop(stdin);
 
-        let output = child.wait_with_output().wrap_err("waiting for orb-sign-iris-code")?;
-        let success = output.status.success();
-        for line in String::from_utf8_lossy(&output.stderr).lines() {
-            if success {
-                tracing::trace!("orb-sign-iris-code {}", line);
-            } else {
-                tracing::error!("orb-sign-iris-code {}", line);
-            }
+    let Some(status) = wait_timeout(&mut child, timeout)? else {
+        kill(&mut child)?;
+        return Err(SignError::Timeout(timeout));
+    };
+    let stdout = stdout.join().expect("orb-sign-iris-code stdout reader panicked")?;
+    let stderr = stderr.join().expect("orb-sign-iris-code stderr reader panicked")?;
+
+    let success = status.success();
+    for line in String::from_utf8_lossy(&stderr).lines() {
+        if success {
+            tracing::trace!("orb-sign-iris-code {}", line);
+        } else {
+            tracing::error!("orb-sign-iris-code {}", line);
         }
-        if !success {
-            if let Some(code) = output.status.code() {
-                bail!("orb-sign-iris-code exited with non-zero exit code: {code}");
-            } else {
-                bail!("orb-sign-iris-code terminated by signal");
-            }
+    }
+    if !success {
+        if String::from_utf8_lossy(&stderr).to_lowercase().contains(NO_DEVICE_MARKER) {
+            return Err(SignError::NoDevice);
         }
-        BASE64.decode(&output.stdout).wrap_err("decoding orb-sign-iris-code output")
+        return Err(status.code().map_or(SignError::Signal, SignError::NonZeroExit));
     }
+    Ok(stdout)
+}
 
-    inner(data.as_ref())
+fn read_in_background<R: Read + Send + 'static>(
+    mut reader: R,
+) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+// Kills the child and reaps it to not leave a zombie process.
+fn kill(child: &mut Child) -> io::Result<()> {
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_signature() {
+        let mut der = vec![DER_SEQUENCE_TAG, 68, 0x02, 32];
+        der.extend([1; 32]);
+        der.extend([0x02, 32]);
+        der.extend([2; 32]);
+        assert!(SignatureScheme::EcdsaP256Der.check(&der).is_ok());
+        assert!(matches!(
+            SignatureScheme::EcdsaP256Der.check(&der[..40]),
+            Err(SignError::Malformed { scheme: SignatureScheme::EcdsaP256Der, len: 40 })
+        ));
+        assert!(SignatureScheme::EcdsaP256Der.check(&[]).is_err());
+        assert!(SignatureScheme::Ed25519.check(&[0; 64]).is_ok());
+        assert!(SignatureScheme::Ed25519.check(&[0; 63]).is_err());
+    }
+
+    #[test]
+    fn test_reject_truncated_signature() {
+        let mut der = vec![DER_SEQUENCE_TAG, 68, 0x02, 32];
+        der.extend([1; 32]);
+        der.extend([0x02, 32]);
+        der.extend([2; 32]);
+        assert!(SIGNATURE_SCHEME.check(&der).is_ok());
+        // A partial pipe read cuts the signature short.
+        assert!(matches!(
+            SIGNATURE_SCHEME.check(&der[..der.len() - 1]),
+            Err(SignError::Malformed { scheme: SIGNATURE_SCHEME, len: 69 })
+        ));
+        assert!(matches!(
+            SIGNATURE_SCHEME.check(&[]),
+            Err(SignError::Malformed { scheme: SIGNATURE_SCHEME, len: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_signed_iris_code() {
+        let mut signed = SignedIrisCode {
+            code: vec![1, 2, 3],
+            signature: vec![4, 5],
+            orb_id: "ea2ea744".to_owned(),
+            timestamp: 1_700_000_000_000,
+            scheme: SignatureScheme::Ed25519,
+        };
+        assert_eq!(
+            signed.to_json(),
+            r#"{"scheme":"ed25519","orbId":"ea2ea744","timestamp":1700000000000,"code":"AQID","signature":"BAU="}"#
+        );
+        let digest = signed.digest();
+        signed.signature = vec![6];
+        assert_eq!(signed.digest().as_ref(), digest.as_ref());
+        signed.timestamp += 1;
+        assert_ne!(signed.digest().as_ref(), digest.as_ref());
+    }
 }
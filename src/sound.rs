@@ -35,6 +35,13 @@ pub trait Player: Debug + Send {
 
     /// Returns a new handler to the shared queue.
     fn clone(&self) -> Box<dyn Player>;
+
+    /// Returns `true` if a sound with the given `priority` pushed with
+    /// [`SoundBuilder::preempt`] would interrupt the currently playing sound.
+    fn preempts(&self, priority: u8) -> bool;
+
+    /// Stops the currently playing sound and clears the queue.
+    fn stop_all(&self) -> Result<()>;
 }
 
 /// Sound queue for the Orb hardware.
@@ -271,6 +278,14 @@ impl Player for Jetson {
             cpu_monitor: self.cpu_monitor.clone(),
         })
     }
+
+    fn preempts(&self, priority: u8) -> bool {
+        self.queue.preempts(priority)
+    }
+
+    fn stop_all(&self) -> Result<()> {
+        Ok(self.queue.stop_all()?)
+    }
 }
 
 impl Player for Fake {
@@ -289,6 +304,14 @@ impl Player for Fake {
     fn clone(&self) -> Box<dyn Player> {
         Box::new(Fake)
     }
+
+    fn preempts(&self, _priority: u8) -> bool {
+        false
+    }
+
+    fn stop_all(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Returns SoundFile if sound in filesystem entries.
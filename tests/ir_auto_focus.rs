@@ -1,5 +1,6 @@
 use orb::{
-    agents::ir_auto_focus::{DerivedSignal, PID_INTEGRAL, PID_PROPORTIONAL},
+    agents::ir_auto_focus::{DerivedSignal, FocusSweep, PID_INTEGRAL, PID_PROPORTIONAL},
+    consts::{AUTOFOCUS_MAX, AUTOFOCUS_MIN},
     pid::{ConstDelta, Pid, Timer},
 };
 use std::fs;
@@ -49,6 +50,21 @@ fn test_pid() {
     assert_pid_gnuplot("pid", &setpoint_history, &process_history);
 }
 
+#[test]
+fn test_focus_sweep() {
+    const DT: f64 = 0.1;
+    let mut sweep = FocusSweep::default();
+    let mut focus = AUTOFOCUS_MIN;
+    let mut steps = 0;
+    while let Some(next) = sweep.update(-f64::from(focus - 100).abs(), DT) {
+        assert!(next > focus && next <= AUTOFOCUS_MAX);
+        focus = next;
+        steps += 1;
+    }
+    assert_eq!(steps, 26);
+    assert_eq!(sweep.best_focus(), 110);
+}
+
 fn test_derived_signal(sample_n: usize) {
     let mut derived = DerivedSignal::default();
     let mut sharpness_history = Vec::new();
@@ -0,0 +1,108 @@
+#[macro_use]
+mod common;
+
+use eyre::Result;
+use futures::prelude::*;
+use orb::{
+    agents::camera,
+    brokers::{BrokerFlow, Orb, OrbPlan},
+    plans::biometric_capture::{Capture, EyeCapture},
+    port,
+};
+use std::time::Duration;
+use tokio::task;
+
+#[derive(Default)]
+struct ReplayPlan {
+    ir_frames: Vec<camera::ir::Frame>,
+    rgb_frames: Vec<camera::rgb::Frame>,
+}
+
+impl OrbPlan for ReplayPlan {
+    fn handle_ir_eye_camera(
+        &mut self,
+        _orb: &mut Orb,
+        output: port::Output<camera::ir::Sensor>,
+    ) -> Result<BrokerFlow> {
+        self.ir_frames.push(output.value);
+        Ok(self.flow())
+    }
+
+    fn handle_rgb_camera(
+        &mut self,
+        _orb: &mut Orb,
+        output: port::Output<camera::rgb::Sensor>,
+    ) -> Result<BrokerFlow> {
+        self.rgb_frames.push(output.value);
+        Ok(self.flow())
+    }
+}
+
+impl ReplayPlan {
+    fn flow(&self) -> BrokerFlow {
+        if self.ir_frames.len() == 2 && self.rgb_frames.len() == 2 {
+            BrokerFlow::Break
+        } else {
+            BrokerFlow::Continue
+        }
+    }
+}
+
+fn eye(value: u8) -> EyeCapture {
+    EyeCapture {
+        ir_frame: camera::ir::Frame::new(
+            vec![value; 4],
+            Duration::from_millis(value.into()),
+            2,
+            2,
+            value,
+        ),
+        rgb_frame: camera::rgb::Frame::from_vec(
+            vec![value; 12],
+            Duration::from_millis(value.into()),
+            2,
+            2,
+        ),
+        ..EyeCapture::default()
+    }
+}
+
+broker_test!(test_replay_capture, test_replay_capture_impl, 60000);
+async fn test_replay_capture_impl() {
+    let (mut ir_eye_camera_fake_port_inner, ir_eye_camera_fake_port_outer) = port::new();
+    let (mut rgb_camera_fake_port_inner, rgb_camera_fake_port_outer) = port::new();
+    let capture = Capture { eye_left: eye(1), eye_right: eye(2), ..Capture::default() };
+    let replayed = capture.clone();
+    task::spawn(async move {
+        while let Some(command) = ir_eye_camera_fake_port_inner.next().await {
+            if let camera::ir::Command::Start = command.value {
+                break;
+            }
+        }
+        while let Some(command) = rgb_camera_fake_port_inner.next().await {
+            if let camera::rgb::Command::Start = command.value {
+                break;
+            }
+        }
+        replayed
+            .replay(&mut ir_eye_camera_fake_port_inner, &mut rgb_camera_fake_port_inner)
+            .await
+            .unwrap();
+        while rgb_camera_fake_port_inner.next().await.is_some() {}
+    });
+    let mut orb = Orb::builder()
+        .ir_eye_camera_fake_port(ir_eye_camera_fake_port_outer)
+        .rgb_camera_fake_port(rgb_camera_fake_port_outer)
+        .build()
+        .await
+        .unwrap();
+    orb.start_ir_eye_camera().await.unwrap();
+    orb.start_rgb_camera().await.unwrap();
+    let mut plan = ReplayPlan::default();
+    orb.run(&mut plan).await.unwrap();
+    orb.stop_rgb_camera().await.unwrap();
+    orb.stop_ir_eye_camera().await.unwrap();
+    assert_eq!(plan.ir_frames, [capture.eye_left.ir_frame, capture.eye_right.ir_frame]);
+    let rgb_data = plan.rgb_frames.iter().map(camera::rgb::Frame::data).collect::<Vec<_>>();
+    assert_eq!(rgb_data, [[1; 12], [2; 12]]);
+}